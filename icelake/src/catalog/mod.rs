@@ -10,6 +10,7 @@ use uuid::Uuid;
 
 use crate::config::{TableConfig, TableConfigRef};
 use crate::error::Result;
+use crate::io::ScanReport;
 use crate::table::{Namespace, TableIdentifier};
 use crate::types::{
     PartitionField, PartitionSpec, Schema, Snapshot, SnapshotReference, SnapshotReferenceType,
@@ -128,6 +129,14 @@ pub trait Catalog: Send + Sync {
             format!("update_table is not supported by {}", self.name()),
         ))
     }
+
+    /// Report the planned scan of table. Reports are dropped by default.
+    ///
+    /// It's called by planning, so reports which take long to deliver, like
+    /// requests to remote services, should be sent in background.
+    async fn report_scan(self: Arc<Self>, _table: &Table, _report: &ScanReport) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Update table requirments
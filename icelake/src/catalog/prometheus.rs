@@ -16,6 +16,7 @@ use prometheus::IntCounterVec;
 use prometheus::Registry;
 use prometheus::DEFAULT_BUCKETS;
 
+use crate::io::ScanReport;
 use crate::types::PartitionSpec;
 use crate::types::Schema;
 use crate::Namespace;
@@ -289,4 +290,9 @@ impl Catalog for PrometheusLayeredCatalog {
         let _ = self.metrics.update_table_latency.start_timer();
         self.inner.clone().update_table(udpate_table).await
     }
+
+    /// Report the planned scan of table.
+    async fn report_scan(self: Arc<Self>, table: &Table, report: &ScanReport) -> Result<()> {
+        self.inner.clone().report_scan(table, report).await
+    }
 }
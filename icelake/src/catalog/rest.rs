@@ -13,12 +13,15 @@ use crate::{
         rest::_models::{CatalogConfig, CommitTableResponse},
         IcebergTableIoArgs,
     },
+    io::ScanReport,
     table::{Namespace, TableIdentifier},
     types::TableMetadata,
     Error, ErrorKind, Table,
 };
 
-use self::_models::{CommitTableRequest, ListTablesResponse, LoadTableResult};
+use self::_models::{
+    CommitTableRequest, ListTablesResponse, LoadTableResult, ReportMetricsRequest,
+};
 
 use super::{BaseCatalogConfig, Catalog, UpdateTable};
 use crate::catalog::{OperatorCreator, CATALOG_CONFIG_PREFIX};
@@ -31,6 +34,8 @@ const PATH_V1: &str = "v1";
 pub struct RestCatalogConfig {
     uri: String,
     warehouse: Option<String>,
+    /// Whether to post reports of scans to the metrics endpoint of tables.
+    metrics_reporting_enabled: bool,
     base_config: BaseCatalogConfig,
 }
 
@@ -150,6 +155,56 @@ impl Catalog for RestCatalog {
         )
         .build()?)
     }
+
+    /// Report the planned scan of table to the metrics endpoint, if
+    /// `rest-metrics-reporting-enabled` is set.
+    ///
+    /// The request is sent in background so that planning doesn't wait for
+    /// the catalog, failures of sending it are logged.
+    async fn report_scan(self: Arc<Self>, table: &Table, report: &ScanReport) -> Result<()> {
+        if !self.config.metrics_reporting_enabled {
+            return Ok(());
+        }
+
+        let schema = table
+            .current_table_metadata()
+            .schema(report.schema_id)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Schema {} not found!", report.schema_id),
+                )
+            })?;
+        let request = self
+            .rest_client
+            .post(self.endpoints.metrics(&report.table_name)?)
+            .json(&ReportMetricsRequest::try_new(report, schema)?)
+            .build()?;
+        log::debug!("Executing request: {request:?}");
+
+        let table_name = report.table_name.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let resp = self.rest_client.execute(request).await?;
+                let status = resp.status();
+                if status.is_success() {
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::Unexpected,
+                        format!(
+                            "Failed to report metrics, status code: {status}, message: {}",
+                            resp.text().await?
+                        ),
+                    ))
+                }
+            };
+            if let Err(e) = result.await {
+                log::warn!("Failed to report scan of table {}: {}", table_name, e);
+            }
+        });
+        Ok(())
+    }
 }
 
 impl RestCatalog {
@@ -269,6 +324,15 @@ impl TryFrom<(BaseCatalogConfig, &HashMap<String, String>)> for RestCatalogConfi
             config.warehouse = Some(warehouse.clone());
         }
 
+        if let Some(enabled) = value.1.get("rest-metrics-reporting-enabled") {
+            config.metrics_reporting_enabled = enabled.parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid rest-metrics-reporting-enabled: {enabled}"),
+                )
+            })?;
+        }
+
         Ok(config)
     }
 }
@@ -308,6 +372,10 @@ impl Endpoint {
         ]
         .join("/"))
     }
+
+    fn metrics(&self, table: &TableIdentifier) -> Result<String> {
+        Ok([&self.table(table)?, "metrics"].join("/"))
+    }
 }
 
 impl Namespace {
@@ -327,13 +395,20 @@ impl Namespace {
 mod _models {
     use std::collections::HashMap;
 
+    use std::collections::BTreeMap;
+
     use crate::{error::Result, types::SchemaSerDe, ErrorKind};
     use serde::{Deserialize, Serialize};
+    use serde_json::json;
 
     use crate::{
         catalog::{self, MetadataUpdate, UpdateRquirement},
+        io::{Predicate, ScanReport},
         table,
-        types::{SnapshotSerDe, TableMetadataSerDe},
+        types::{
+            Any, AnyValue, FieldRef, PrimitiveValue, Schema, SnapshotSerDe, TableMetadataSerDe,
+            ValueSerDe,
+        },
         Error,
     };
 
@@ -608,13 +683,159 @@ mod _models {
         pub(super) metadata_location: String,
         pub(super) metadata: TableMetadataSerDe,
     }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub(super) struct ReportMetricsRequest {
+        pub(super) report_type: String,
+        pub(super) table_name: String,
+        pub(super) snapshot_id: i64,
+        pub(super) filter: serde_json::Value,
+        pub(super) schema_id: i32,
+        pub(super) projected_field_ids: Vec<i32>,
+        pub(super) projected_field_names: Vec<String>,
+        pub(super) metrics: BTreeMap<String, serde_json::Value>,
+        pub(super) metadata: HashMap<String, String>,
+    }
+
+    impl ReportMetricsRequest {
+        /// Build the request of the scan report, terms of the filter are
+        /// named by `schema`.
+        pub(super) fn try_new(report: &ScanReport, schema: &Schema) -> Result<Self> {
+            let mut names = HashMap::new();
+            field_names(schema.fields(), "", &mut names);
+
+            let m = &report.metrics;
+            let count = |value: u64| json!({ "unit": "count", "value": value });
+            let bytes = |value: u64| json!({ "unit": "bytes", "value": value });
+            let metrics = [
+                (
+                    "total-planning-duration",
+                    json!({
+                        "count": 1,
+                        "time-unit": "nanoseconds",
+                        "total-duration": m.total_planning_duration.as_nanos() as u64,
+                    }),
+                ),
+                ("result-data-files", count(m.result_data_files)),
+                ("result-delete-files", count(m.result_delete_files)),
+                ("total-data-manifests", count(m.total_data_manifests)),
+                ("total-delete-manifests", count(m.total_delete_manifests)),
+                ("scanned-data-manifests", count(m.scanned_data_manifests)),
+                ("skipped-data-manifests", count(m.skipped_data_manifests)),
+                (
+                    "scanned-delete-manifests",
+                    count(m.scanned_delete_manifests),
+                ),
+                (
+                    "skipped-delete-manifests",
+                    count(m.skipped_delete_manifests),
+                ),
+                (
+                    "total-file-size-in-bytes",
+                    bytes(m.total_file_size_in_bytes),
+                ),
+                (
+                    "total-delete-file-size-in-bytes",
+                    bytes(m.total_delete_file_size_in_bytes),
+                ),
+                ("skipped-data-files", count(m.skipped_data_files)),
+                ("skipped-delete-files", count(m.skipped_delete_files)),
+                ("indexed-delete-files", count(m.indexed_delete_files)),
+                ("equality-delete-files", count(m.equality_delete_files)),
+                ("positional-delete-files", count(m.positional_delete_files)),
+            ]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+
+            Ok(Self {
+                report_type: "scan-report".to_string(),
+                table_name: report.table_name.to_string(),
+                snapshot_id: report.snapshot_id,
+                filter: expression(&report.filter, &names)?,
+                schema_id: report.schema_id,
+                projected_field_ids: report.projected_field_ids.clone(),
+                projected_field_names: report.projected_field_names.clone(),
+                metrics,
+                metadata: HashMap::new(),
+            })
+        }
+    }
+
+    /// Collect dotted names of fields nested in structs.
+    fn field_names(fields: &[FieldRef], prefix: &str, names: &mut HashMap<i32, String>) {
+        for field in fields {
+            let name = format!("{prefix}{}", field.name);
+            if let Any::Struct(s) = &field.field_type {
+                field_names(s.fields(), &format!("{name}."), names);
+            }
+            names.insert(field.id, name);
+        }
+    }
+
+    /// Convert the predicate to expression of rest catalog.
+    fn expression(
+        predicate: &Predicate,
+        names: &HashMap<i32, String>,
+    ) -> Result<serde_json::Value> {
+        let term = |field_id: &i32| {
+            names.get(field_id).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Field {field_id} of filter not found in schema"),
+                )
+            })
+        };
+        let value = |v: &PrimitiveValue| {
+            serde_json::to_value(ValueSerDe::from(AnyValue::Primitive(v.clone())))
+        };
+
+        Ok(match predicate {
+            Predicate::AlwaysTrue => json!(true),
+            Predicate::AlwaysFalse => json!(false),
+            Predicate::IsNull(field_id) => json!({ "type": "is-null", "term": term(field_id)? }),
+            Predicate::NotNull(field_id) => {
+                json!({ "type": "not-null", "term": term(field_id)? })
+            }
+            Predicate::Eq(field_id, v) => {
+                json!({ "type": "eq", "term": term(field_id)?, "value": value(v)? })
+            }
+            Predicate::In(field_id, values) => json!({
+                "type": "in",
+                "term": term(field_id)?,
+                "values": values.iter().map(value).collect::<serde_json::Result<Vec<_>>>()?,
+            }),
+            Predicate::And(l, r) => json!({
+                "type": "and",
+                "left": expression(l, names)?,
+                "right": expression(r, names)?,
+            }),
+            Predicate::Or(l, r) => json!({
+                "type": "or",
+                "left": expression(l, names)?,
+                "right": expression(r, names)?,
+            }),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::table::Namespace;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
 
-    use super::_models::TableUpdate;
+    use crate::catalog::Catalog;
+    use crate::io::{Predicate, ScanMetrics, ScanReport};
+    use crate::table::{Namespace, TableIdentifier};
+    use crate::test_utils;
+    use crate::types::{Any, Field, Primitive, PrimitiveValue, Schema, Struct};
+
+    use super::_models::{ReportMetricsRequest, TableUpdate};
+    use super::{Endpoint, RestCatalog, RestCatalogConfig};
 
     #[test]
     fn test_namespace_encode() {
@@ -625,6 +846,105 @@ mod tests {
         assert_eq!("a%1Fb", ns.encode_in_url().unwrap());
     }
 
+    #[test]
+    fn test_serialize_report_metrics_request() {
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::required(1, "id", Any::Primitive(Primitive::Long)).into(),
+                Field::optional(
+                    2,
+                    "address",
+                    Any::Struct(
+                        Struct::new(vec![Field::optional(
+                            3,
+                            "city",
+                            Any::Primitive(Primitive::String),
+                        )
+                        .into()])
+                        .into(),
+                    ),
+                )
+                .into(),
+            ]),
+        );
+        let report = ScanReport {
+            table_name: TableIdentifier::new(["db", "t"]).unwrap(),
+            snapshot_id: 1,
+            filter: Predicate::Eq(1, PrimitiveValue::Long(7)).and(Predicate::IsNull(3)),
+            schema_id: 0,
+            projected_field_ids: vec![1],
+            projected_field_names: vec!["id".to_string()],
+            metrics: ScanMetrics {
+                total_planning_duration: Duration::from_millis(2),
+                result_data_files: 3,
+                ..Default::default()
+            },
+        };
+
+        let json =
+            serde_json::to_value(ReportMetricsRequest::try_new(&report, &schema).unwrap()).unwrap();
+        assert_eq!(json["report-type"], "scan-report");
+        assert_eq!(json["table-name"], "db.t");
+        assert_eq!(
+            json["filter"],
+            serde_json::json!({
+                "type": "and",
+                "left": { "type": "eq", "term": "id", "value": 7 },
+                "right": { "type": "is-null", "term": "address.city" },
+            })
+        );
+        assert_eq!(
+            json["metrics"]["total-planning-duration"]["total-duration"],
+            2_000_000
+        );
+        assert_eq!(json["metrics"]["result-data-files"]["value"], 3);
+
+        let report = ScanReport {
+            filter: Predicate::IsNull(4),
+            ..report
+        };
+        assert!(ReportMetricsRequest::try_new(&report, &schema).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_scan_in_background() {
+        // The server accepts the request but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri = format!("http://{}", listener.local_addr().unwrap());
+        let catalog = Arc::new(RestCatalog {
+            config: RestCatalogConfig {
+                uri: uri.clone(),
+                metrics_reporting_enabled: true,
+                ..Default::default()
+            },
+            endpoints: Endpoint::new(uri),
+            rest_client: RestCatalog::create_rest_client().unwrap(),
+        });
+        let (_dir, table) = test_utils::create_table().await.unwrap();
+        let report = ScanReport {
+            table_name: TableIdentifier::new(["db", "t"]).unwrap(),
+            snapshot_id: 1,
+            filter: Predicate::AlwaysTrue,
+            schema_id: 0,
+            projected_field_ids: vec![1],
+            projected_field_names: vec!["id".to_string()],
+            metrics: ScanMetrics::default(),
+        };
+
+        tokio::time::timeout(Duration::from_secs(5), catalog.report_scan(&table, &report))
+            .await
+            .expect("reporting must not wait for the response")
+            .unwrap();
+
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 1024];
+        let n = stream.read(&mut request).await.unwrap();
+        assert!(String::from_utf8_lossy(&request[..n])
+            .starts_with("POST /v1/namespaces/db/tables/t/metrics"));
+    }
+
     #[test]
     fn test_serialize_table_update() {
         let table_update = TableUpdate::UpgradeFormatVersion { format_version: 1 };
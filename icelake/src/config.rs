@@ -19,6 +19,49 @@ pub struct TableConfig {
     pub datafile_writer: DataFileWriterConfig,
     /// Sorted Delete Position writer configuration.
     pub sorted_delete_position_writer: SortedDeletePositionWriterConfig,
+    /// Manifest reader configuration.
+    pub manifest_reader: ManifestReaderConfig,
+    /// Parquet reader configuration.
+    pub parquet_reader: ParquetReaderConfig,
+}
+
+/// Parquet reader configuration.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParquetReaderConfig {
+    /// Byte ranges of column chunks are merged into one request if the gap
+    /// between them is not larger than this value.
+    pub range_merge_gap: usize,
+    /// Max size in bytes of a merged request.
+    pub range_max_size: usize,
+    /// Max number of concurrent requests of a reader.
+    pub range_concurrency: usize,
+    /// Size in bytes of the file tail fetched to read the footer, footers
+    /// not larger than it are read in one request.
+    pub footer_prefetch_size: usize,
+}
+
+impl Default for ParquetReaderConfig {
+    fn default() -> Self {
+        Self {
+            range_merge_gap: 1024 * 1024,
+            range_max_size: 8 * 1024 * 1024,
+            range_concurrency: 8,
+            footer_prefetch_size: 64 * 1024,
+        }
+    }
+}
+
+/// Manifest reader configuration.
+#[derive(PartialEq, Eq, Debug)]
+pub struct ManifestReaderConfig {
+    /// Max number of manifests read and decoded concurrently.
+    pub concurrency: usize,
+}
+
+impl Default for ManifestReaderConfig {
+    fn default() -> Self {
+        Self { concurrency: 16 }
+    }
 }
 
 /// Data file writer configuration.
@@ -71,6 +114,88 @@ pub struct ParquetWriterConfig {
     pub data_page_size: usize,
 }
 
+/// Configuration of splitting and combining scan tasks, parsed from the
+/// `read.split.*` properties of table metadata.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ReadSplitConfig {
+    /// Target size in bytes of combined scan tasks.
+    pub target_size: u64,
+    /// Estimated cost in bytes of opening a file, it's the minimum weight of
+    /// a file split when combining tasks.
+    pub open_file_cost: u64,
+    /// Number of bins considered when combining tasks.
+    pub planning_lookback: usize,
+}
+
+impl Default for ReadSplitConfig {
+    fn default() -> Self {
+        Self {
+            target_size: 128 * 1024 * 1024,
+            open_file_cost: 4 * 1024 * 1024,
+            planning_lookback: 10,
+        }
+    }
+}
+
+impl TryFrom<&'_ HashMap<String, String>> for ReadSplitConfig {
+    type Error = Error;
+
+    fn try_from(value: &'_ HashMap<String, String>) -> Result<Self> {
+        let mut config = ReadSplitConfig::default();
+
+        value
+            .get("read.split.target-size")
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse read.split.target-size.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.target_size = *v);
+
+        value
+            .get("read.split.open-file-cost")
+            .map(|v| v.parse::<u64>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse read.split.open-file-cost.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.open_file_cost = *v);
+
+        value
+            .get("read.split.planning-lookback")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse read.split.planning-lookback.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.planning_lookback = *v);
+
+        if config.target_size == 0 || config.planning_lookback == 0 {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "read.split.target-size and read.split.planning-lookback must be positive.",
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
 impl TryFrom<&'_ HashMap<String, String>> for TableConfig {
     type Error = Error;
 
@@ -195,6 +320,76 @@ impl TryFrom<&'_ HashMap<String, String>> for TableConfig {
             .iter()
             .for_each(|v| config.sorted_delete_position_writer.max_record_num = *v);
 
+        value
+            .get("iceberg.table.manifest_reader.concurrency")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse iceberg.manifest_reader.concurrency.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.manifest_reader.concurrency = (*v).max(1));
+
+        value
+            .get("iceberg.table.parquet_reader.range_merge_gap")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse iceberg.parquet_reader.range_merge_gap.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.parquet_reader.range_merge_gap = *v);
+
+        value
+            .get("iceberg.table.parquet_reader.range_max_size")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse iceberg.parquet_reader.range_max_size.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.parquet_reader.range_max_size = *v);
+
+        value
+            .get("iceberg.table.parquet_reader.range_concurrency")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse iceberg.parquet_reader.range_concurrency.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.parquet_reader.range_concurrency = (*v).max(1));
+
+        value
+            .get("iceberg.table.parquet_reader.footer_prefetch_size")
+            .map(|v| v.parse::<usize>())
+            .transpose()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    "Can't parse iceberg.parquet_reader.footer_prefetch_size.",
+                )
+                .set_source(e)
+            })?
+            .iter()
+            .for_each(|v| config.parquet_reader.footer_prefetch_size = *v);
+
         Ok(config)
     }
 }
@@ -286,7 +481,7 @@ impl Default for ParquetWriterConfig {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{ParquetWriterConfig, TableConfig};
+    use crate::config::{ParquetWriterConfig, ReadSplitConfig, TableConfig};
     use parquet::basic::{Compression, GzipLevel};
     use std::collections::HashMap;
 
@@ -303,6 +498,8 @@ mod tests {
             },
             datafile_writer: Default::default(),
             sorted_delete_position_writer: Default::default(),
+            manifest_reader: Default::default(),
+            parquet_reader: Default::default(),
         };
 
         let config_map = HashMap::from([
@@ -322,4 +519,23 @@ mod tests {
 
         assert_eq!(expected_config, parsed_config);
     }
+
+    #[test]
+    fn test_parse_read_split_config_from_hashmap() {
+        let config_map = HashMap::from([
+            ("read.split.target-size".to_string(), "1024".to_string()),
+            ("read.split.planning-lookback".to_string(), "5".to_string()),
+        ]);
+
+        let parsed_config = ReadSplitConfig::try_from(&config_map).unwrap();
+
+        assert_eq!(
+            parsed_config,
+            ReadSplitConfig {
+                target_size: 1024,
+                open_file_cost: 4 * 1024 * 1024,
+                planning_lookback: 5,
+            }
+        );
+    }
 }
@@ -0,0 +1,402 @@
+//! Cache of decoded metadata of immutable files, shared by tables and scans.
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::mem::{size_of, size_of_val};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use parquet::file::metadata::{ColumnChunkMetaData, ParquetMetaData, RowGroupMetaData};
+use parquet::file::page_index::index::Index;
+use parquet::format::PageLocation;
+use parquet::schema::types::ColumnDescriptor;
+
+use crate::types::{
+    AnyValue, DataFile, ManifestEntry, ManifestFile, ManifestList, ManifestListEntry,
+    PrimitiveValue, StructValue,
+};
+use crate::Result;
+
+/// Reference to [`FileCache`].
+pub type FileCacheRef = Arc<FileCache>;
+
+/// Cache of decoded manifest lists, manifests and parquet footers, keyed by
+/// file path.
+///
+/// Files of iceberg tables are never modified after written, so cached
+/// values never expire. Memory used by the cache is bounded by `capacity`,
+/// the size of a cached value is estimated by its decoded size in memory,
+/// which is usually several times of its encoded size. The least recently
+/// used values are evicted first.
+pub struct FileCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Statistics of [`FileCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileCacheStats {
+    /// Number of lookups found in cache.
+    pub hits: u64,
+    /// Number of lookups not found in cache.
+    pub misses: u64,
+    /// Number of cached files.
+    pub entries: usize,
+    /// Estimated size in bytes of decoded cached files.
+    pub size: usize,
+}
+
+#[derive(Clone)]
+enum CachedFile {
+    ManifestList(Arc<ManifestList>),
+    ManifestFile(Arc<ManifestFile>),
+    ParquetMetaData(Arc<ParquetMetaData>),
+}
+
+struct CacheEntry {
+    file: CachedFile,
+    size: usize,
+    /// Last access time, it's the key of the entry in `CacheInner::lru`.
+    tick: u64,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, CacheEntry>,
+    /// Paths of entries ordered by last access time.
+    lru: BTreeMap<u64, String>,
+    size: usize,
+    tick: u64,
+}
+
+impl FileCache {
+    /// Create a cache using at most `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(CacheInner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns statistics of the cache.
+    pub fn stats(&self) -> FileCacheStats {
+        let inner = self.inner.lock().unwrap();
+        FileCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: inner.entries.len(),
+            size: inner.size,
+        }
+    }
+
+    /// Returns the cached manifest list at `path`, or loads it with `load`.
+    pub(crate) async fn manifest_list(
+        &self,
+        path: &str,
+        load: impl Future<Output = Result<ManifestList>>,
+    ) -> Result<Arc<ManifestList>> {
+        if let Some(CachedFile::ManifestList(v)) = self.get(path) {
+            return Ok(v);
+        }
+        let v = Arc::new(load.await?);
+        self.insert(path, CachedFile::ManifestList(v.clone()));
+        Ok(v)
+    }
+
+    /// Returns the cached manifest at `path`, or loads it with `load`.
+    pub(crate) async fn manifest_file(
+        &self,
+        path: &str,
+        load: impl Future<Output = Result<ManifestFile>>,
+    ) -> Result<Arc<ManifestFile>> {
+        if let Some(CachedFile::ManifestFile(v)) = self.get(path) {
+            return Ok(v);
+        }
+        let v = Arc::new(load.await?);
+        self.insert(path, CachedFile::ManifestFile(v.clone()));
+        Ok(v)
+    }
+
+    /// Returns the cached footer of parquet file at `path`, or loads it with
+    /// `load`.
+    pub(crate) async fn parquet_metadata<E>(
+        &self,
+        path: &str,
+        load: impl Future<Output = std::result::Result<Arc<ParquetMetaData>, E>>,
+    ) -> std::result::Result<Arc<ParquetMetaData>, E> {
+        if let Some(CachedFile::ParquetMetaData(v)) = self.get(path) {
+            return Ok(v);
+        }
+        let v = load.await?;
+        self.insert(path, CachedFile::ParquetMetaData(v.clone()));
+        Ok(v)
+    }
+
+    fn get(&self, path: &str) -> Option<CachedFile> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let CacheInner { entries, lru, .. } = &mut *inner;
+        match entries.get_mut(path) {
+            Some(entry) => {
+                lru.remove(&entry.tick);
+                lru.insert(tick, path.to_string());
+                entry.tick = tick;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.file.clone())
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    fn insert(&self, path: &str, file: CachedFile) {
+        let size = file.memory_size();
+        if size > self.capacity {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some(old) = inner.entries.remove(path) {
+            inner.lru.remove(&old.tick);
+            inner.size -= old.size;
+        }
+        while inner.size + size > self.capacity {
+            let Some((_, evicted)) = inner.lru.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = inner.entries.remove(&evicted) {
+                inner.size -= evicted.size;
+            }
+        }
+        inner.lru.insert(tick, path.to_string());
+        inner
+            .entries
+            .insert(path.to_string(), CacheEntry { file, size, tick });
+        inner.size += size;
+    }
+}
+
+impl CachedFile {
+    /// Estimated size in memory of the decoded file, including heap
+    /// allocations.
+    fn memory_size(&self) -> usize {
+        match self {
+            CachedFile::ManifestList(v) => {
+                size_of::<ManifestList>()
+                    + v.entries.capacity() * size_of::<ManifestListEntry>()
+                    + v.entries
+                        .iter()
+                        .map(manifest_list_entry_heap_size)
+                        .sum::<usize>()
+            }
+            CachedFile::ManifestFile(v) => {
+                size_of::<ManifestFile>()
+                    + v.entries.capacity() * size_of::<ManifestEntry>()
+                    + v.entries
+                        .iter()
+                        .map(|entry| data_file_heap_size(&entry.data_file))
+                        .sum::<usize>()
+            }
+            CachedFile::ParquetMetaData(v) => parquet_metadata_size(v),
+        }
+    }
+}
+
+/// Size of the decoded parquet footer, the schema and metadata of column
+/// chunks are counted while the values of page indexes are not.
+fn parquet_metadata_size(metadata: &ParquetMetaData) -> usize {
+    let file_metadata = metadata.file_metadata();
+    let schema = file_metadata.schema_descr().num_columns() * size_of::<ColumnDescriptor>();
+    let key_value = file_metadata.key_value_metadata().map_or(0, |kvs| {
+        kvs.iter()
+            .map(|kv| kv.key.len() + kv.value.as_ref().map_or(0, String::len))
+            .sum()
+    });
+    let row_groups = metadata
+        .row_groups()
+        .iter()
+        .map(|row_group| {
+            size_of::<RowGroupMetaData>()
+                + row_group
+                    .columns()
+                    .iter()
+                    .map(|column| {
+                        size_of::<ColumnChunkMetaData>()
+                            + column.file_path().map_or(0, str::len)
+                            + column
+                                .statistics()
+                                .filter(|stats| stats.has_min_max_set())
+                                .map_or(0, |stats| {
+                                    stats.min_bytes().len() + stats.max_bytes().len()
+                                })
+                    })
+                    .sum::<usize>()
+        })
+        .sum::<usize>();
+    let column_index = metadata.column_index().map_or(0, |index| {
+        index.iter().map(|v| v.len() * size_of::<Index>()).sum()
+    });
+    let offset_index = metadata.offset_index().map_or(0, |index| {
+        index
+            .iter()
+            .flatten()
+            .map(|v| v.len() * size_of::<PageLocation>())
+            .sum()
+    });
+    size_of::<ParquetMetaData>() + schema + key_value + row_groups + column_index + offset_index
+}
+
+fn manifest_list_entry_heap_size(entry: &ManifestListEntry) -> usize {
+    entry.manifest_path.capacity()
+        + entry
+            .partitions
+            .iter()
+            .flatten()
+            .map(|summary| {
+                size_of_val(summary)
+                    + summary.lower_bound.as_ref().map_or(0, Vec::capacity)
+                    + summary.upper_bound.as_ref().map_or(0, Vec::capacity)
+            })
+            .sum::<usize>()
+        + entry.key_metadata.as_ref().map_or(0, Vec::capacity)
+}
+
+/// Heap size of the data file, the partition type is shared by data files
+/// of a manifest so it's not counted.
+fn data_file_heap_size(data_file: &DataFile) -> usize {
+    fn map_size<V>(map: &Option<HashMap<i32, V>>, value_size: impl Fn(&V) -> usize) -> usize {
+        map.as_ref().map_or(0, |map| {
+            map.capacity() * (size_of::<i32>() + size_of::<V>())
+                + map.values().map(value_size).sum::<usize>()
+        })
+    }
+    fn vec_size<T>(vec: &Option<Vec<T>>) -> usize {
+        vec.as_ref().map_or(0, |v| v.capacity() * size_of::<T>())
+    }
+
+    data_file.file_path.capacity()
+        + struct_value_heap_size(&data_file.partition)
+        + map_size(&data_file.column_sizes, |_| 0)
+        + map_size(&data_file.value_counts, |_| 0)
+        + map_size(&data_file.null_value_counts, |_| 0)
+        + map_size(&data_file.nan_value_counts, |_| 0)
+        + map_size(&data_file.distinct_counts, |_| 0)
+        + map_size(&data_file.lower_bounds, Vec::capacity)
+        + map_size(&data_file.upper_bounds, Vec::capacity)
+        + vec_size(&data_file.key_metadata)
+        + vec_size(&data_file.split_offsets)
+        + vec_size(&data_file.equality_ids)
+}
+
+fn struct_value_heap_size(value: &StructValue) -> usize {
+    value
+        .iter()
+        .map(|(_, v, _, _)| size_of::<AnyValue>() + v.map_or(0, any_value_heap_size))
+        .sum()
+}
+
+fn any_value_heap_size(value: &AnyValue) -> usize {
+    match value {
+        AnyValue::Primitive(PrimitiveValue::String(s)) => s.capacity(),
+        AnyValue::Primitive(PrimitiveValue::Binary(b) | PrimitiveValue::Fixed(b)) => b.capacity(),
+        AnyValue::Primitive(_) => 0,
+        AnyValue::Struct(v) => struct_value_heap_size(v),
+        AnyValue::List(values) => values
+            .iter()
+            .map(|v| size_of::<Option<AnyValue>>() + v.as_ref().map_or(0, any_value_heap_size))
+            .sum(),
+        AnyValue::Map { keys, values } => {
+            keys.iter()
+                .map(|k| size_of::<AnyValue>() + any_value_heap_size(k))
+                .chain(values.iter().map(|v| {
+                    size_of::<Option<AnyValue>>() + v.as_ref().map_or(0, any_value_heap_size)
+                }))
+                .sum()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    /// Manifest list with room for `n` entries.
+    fn manifest_list(n: usize) -> ManifestList {
+        ManifestList {
+            entries: Vec::with_capacity(n),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_cache() {
+        let size = size_of::<ManifestList>() + 2 * size_of::<ManifestListEntry>();
+        let cache = FileCache::new(size * 5 / 2);
+
+        for path in ["a", "b", "a"] {
+            cache
+                .manifest_list(path, async { Ok(manifest_list(2)) })
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache.stats(),
+            FileCacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 2,
+                size: size * 2,
+            }
+        );
+
+        // `b` is the least recently used one.
+        cache
+            .manifest_list("c", async { Ok(manifest_list(2)) })
+            .await
+            .unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.size), (2, size * 2));
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+
+        // Files larger than capacity are not cached.
+        cache
+            .manifest_list("d", async { Ok(manifest_list(10)) })
+            .await
+            .unwrap();
+        assert!(cache.get("d").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_cache_decoded_size() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut data_file = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        data_file.lower_bounds = Some(HashMap::from([(2, vec![b'a'; 1000])]));
+        data_file.upper_bounds = Some(HashMap::from([(2, vec![b'z'; 1000])]));
+        test_utils::commit(&mut table, vec![data_file], vec![]).await?;
+        let cache = Arc::new(FileCache::new(1 << 20));
+        table.set_file_cache(cache.clone());
+
+        let snapshot = table.current_table_metadata().current_snapshot()?.unwrap();
+        let manifests = table.manifests_of_snapshot(snapshot).await?;
+        assert_eq!(manifests.len(), 1);
+        let manifest_list_size = cache.get(&snapshot.manifest_list).unwrap().memory_size();
+        let manifest_size = cache
+            .get(&manifests[0].0.manifest_path)
+            .unwrap()
+            .memory_size();
+        // Bounds of the data file are counted in the decoded size.
+        assert!(manifest_size > 2000 + size_of::<ManifestEntry>());
+        assert_eq!(cache.stats().size, manifest_list_size + manifest_size);
+        Ok(())
+    }
+}
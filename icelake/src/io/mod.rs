@@ -2,6 +2,8 @@
 //! sources.
 
 mod appender;
+mod cache;
+pub use cache::*;
 pub mod file_writer;
 pub mod location_generator;
 pub mod parquet;
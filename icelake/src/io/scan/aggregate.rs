@@ -0,0 +1,412 @@
+//! Aggregates answered from manifest metadata alone, without reading data
+//! files.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use futures::TryStreamExt;
+
+use super::{DeleteFileIndex, TableScan};
+use crate::types::{Any, DataFile, FieldRef, Primitive, PrimitiveValue, TableMetadata};
+use crate::{Error, ErrorKind, Result, Table};
+
+const METRICS_MODE_COLUMN_PREFIX: &str = "write.metadata.metrics.column.";
+const DEFAULT_METRICS_MODE: &str = "write.metadata.metrics.default";
+const DEFAULT_METRICS_MODE_DEFAULT: &str = "truncate(16)";
+
+/// Answer of an aggregate from manifest metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataAggregate<T> {
+    /// The exact answer, computed from metadata alone.
+    Exact(T),
+    /// Metadata is not enough for the exact answer, data files must be
+    /// scanned. The reason tells why.
+    NeedsScan(String),
+}
+
+/// `MIN`, `MAX` and the null count of a column.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnAggregates {
+    /// Min value of the column, `None` if all values are null.
+    pub min: Option<PrimitiveValue>,
+    /// Max value of the column, `None` if all values are null.
+    pub max: Option<PrimitiveValue>,
+    /// Number of null values of the column.
+    pub null_count: i64,
+}
+
+impl Table {
+    /// Answer `COUNT(*)` of the current snapshot from record counts of data
+    /// files.
+    ///
+    /// Returns [`MetadataAggregate::NeedsScan`] if delete files apply to any
+    /// data file.
+    pub async fn count_from_metadata(&self) -> Result<MetadataAggregate<i64>> {
+        self.fold_data_files(0, |count, data_file| {
+            Ok(MetadataAggregate::Exact(count + data_file.record_count))
+        })
+        .await
+    }
+
+    /// Answer `MIN`, `MAX` and the null count of the column of the current
+    /// snapshot from bounds and null value counts of data files. Nested
+    /// columns of structs are named with dots, like `a.b`.
+    ///
+    /// Returns [`MetadataAggregate::NeedsScan`] if delete files apply to any
+    /// data file, or metrics of any data file are missing, truncated or
+    /// contain NaN.
+    pub async fn column_aggregates_from_metadata(
+        &self,
+        column: &str,
+    ) -> Result<MetadataAggregate<ColumnAggregates>> {
+        let metadata = self.current_table_metadata();
+        let field =
+            resolve_column(metadata.current_schema()?.fields(), column).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Column {column} not found in schema"),
+                )
+            })?;
+        let Any::Primitive(ty) = &field.field_type else {
+            return Ok(MetadataAggregate::NeedsScan(format!(
+                "Column {column} is not primitive"
+            )));
+        };
+        let truncate_length = match metrics_mode(metadata, column)? {
+            MetricsMode::None | MetricsMode::Counts => {
+                return Ok(MetadataAggregate::NeedsScan(format!(
+                    "Bounds of column {column} are not collected"
+                )))
+            }
+            MetricsMode::Truncate(length) => Some(length),
+            MetricsMode::Full => None,
+        };
+
+        let aggregator = ColumnAggregator {
+            column,
+            field_id: field.id,
+            ty,
+            truncate_length,
+        };
+        self.fold_data_files(ColumnAggregates::default(), |aggregates, data_file| {
+            aggregator.update(aggregates, data_file)
+        })
+        .await
+    }
+
+    /// Fold live data files of the current snapshot, stops once `f` or a data
+    /// file with deletes needs a scan.
+    async fn fold_data_files<T>(
+        &self,
+        init: T,
+        mut f: impl FnMut(T, &DataFile) -> Result<MetadataAggregate<T>>,
+    ) -> Result<MetadataAggregate<T>> {
+        let Some(snapshot) = self.current_table_metadata().current_snapshot()? else {
+            return Ok(MetadataAggregate::Exact(init));
+        };
+
+        let metrics = Mutex::default();
+        let delete_file_index =
+            DeleteFileIndex::new(TableScan::live_delete_files(self, snapshot, &metrics).await?);
+        let mut data_files = TableScan::live_data_files(self, snapshot, &metrics).await?;
+        let mut acc = init;
+        while let Some((data_file, sequence_number, spec_id)) = data_files.try_next().await? {
+            let (position_deletes, equality_deletes) =
+                delete_file_index.for_data_file(&data_file, sequence_number, spec_id);
+            if !position_deletes.is_empty() || !equality_deletes.is_empty() {
+                return Ok(MetadataAggregate::NeedsScan(format!(
+                    "Delete files apply to data file {}",
+                    data_file.file_path
+                )));
+            }
+            acc = match f(acc, &data_file)? {
+                MetadataAggregate::Exact(acc) => acc,
+                needs_scan => return Ok(needs_scan),
+            };
+        }
+        Ok(MetadataAggregate::Exact(acc))
+    }
+}
+
+/// Resolve the column through structs, names of nested fields are joined
+/// with dots.
+fn resolve_column<'a>(fields: &'a [FieldRef], name: &str) -> Option<&'a FieldRef> {
+    // Names of root fields may contain dots.
+    if let Some(field) = fields.iter().find(|f| f.name == name) {
+        return Some(field);
+    }
+
+    let mut parts = name.split('.');
+    let root = parts.next()?;
+    let mut field = fields.iter().find(|f| f.name == root)?;
+    for part in parts {
+        let Any::Struct(s) = &field.field_type else {
+            return None;
+        };
+        field = s.lookup_field_by_name(part)?;
+    }
+    Some(field)
+}
+
+/// Metrics mode of a column, see
+/// [Write properties](https://iceberg.apache.org/docs/latest/configuration/#write-properties).
+#[derive(Debug, PartialEq, Eq)]
+enum MetricsMode {
+    None,
+    Counts,
+    Truncate(usize),
+    Full,
+}
+
+fn metrics_mode(metadata: &TableMetadata, column: &str) -> Result<MetricsMode> {
+    let properties = metadata.properties.as_ref();
+    let property = |key: &str| properties.and_then(|p: &HashMap<String, String>| p.get(key));
+    let mode = property(&format!("{METRICS_MODE_COLUMN_PREFIX}{column}"))
+        .or_else(|| property(DEFAULT_METRICS_MODE))
+        .map_or(DEFAULT_METRICS_MODE_DEFAULT, |v| v.as_str());
+
+    let invalid = || {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Invalid metrics mode: {mode}"),
+        )
+    };
+    match mode.to_lowercase().as_str() {
+        "none" => Ok(MetricsMode::None),
+        "counts" => Ok(MetricsMode::Counts),
+        "full" => Ok(MetricsMode::Full),
+        mode => {
+            let length = mode
+                .strip_prefix("truncate(")
+                .and_then(|m| m.strip_suffix(')'))
+                .ok_or_else(invalid)?
+                .parse::<usize>()
+                .map_err(|e| invalid().set_source(e))?;
+            Ok(MetricsMode::Truncate(length))
+        }
+    }
+}
+
+/// Aggregates a primitive column over data files.
+struct ColumnAggregator<'a> {
+    column: &'a str,
+    field_id: i32,
+    ty: &'a Primitive,
+    /// Length bounds of strings and binaries are truncated to, if set.
+    truncate_length: Option<usize>,
+}
+
+impl ColumnAggregator<'_> {
+    fn update(
+        &self,
+        mut aggregates: ColumnAggregates,
+        data_file: &DataFile,
+    ) -> Result<MetadataAggregate<ColumnAggregates>> {
+        let needs_scan = |reason: &str| {
+            Ok(MetadataAggregate::NeedsScan(format!(
+                "Data file {} {reason} of column {}",
+                data_file.file_path, self.column
+            )))
+        };
+        let metric = |metrics: &Option<HashMap<i32, i64>>| {
+            metrics
+                .as_ref()
+                .and_then(|m| m.get(&self.field_id))
+                .copied()
+        };
+
+        let (Some(value_count), Some(null_count)) = (
+            metric(&data_file.value_counts),
+            metric(&data_file.null_value_counts),
+        ) else {
+            return needs_scan("has no value counts");
+        };
+        aggregates.null_count += null_count;
+        if value_count == null_count {
+            return Ok(MetadataAggregate::Exact(aggregates));
+        }
+
+        // NaN are not counted in bounds.
+        if matches!(self.ty, Primitive::Float | Primitive::Double)
+            && metric(&data_file.nan_value_counts) != Some(0)
+        {
+            return needs_scan("may contain NaN");
+        }
+        let (Some(lower), Some(upper)) = (
+            bound(&data_file.lower_bounds, self.field_id),
+            bound(&data_file.upper_bounds, self.field_id),
+        ) else {
+            return needs_scan("has no bounds");
+        };
+        if self.is_truncated(lower) || self.is_truncated(upper) {
+            return needs_scan("may have truncated bounds");
+        }
+
+        let lower = PrimitiveValue::try_from_bytes(self.ty, lower)?;
+        let upper = PrimitiveValue::try_from_bytes(self.ty, upper)?;
+        aggregates.min = Some(match aggregates.min {
+            Some(min) if compare(&min, &lower)? != Ordering::Greater => min,
+            _ => lower,
+        });
+        aggregates.max = Some(match aggregates.max {
+            Some(max) if compare(&max, &upper)? != Ordering::Less => max,
+            _ => upper,
+        });
+        Ok(MetadataAggregate::Exact(aggregates))
+    }
+
+    /// Bounds of strings and binaries may be truncated if they reach the
+    /// truncate length, strings are truncated by unicode characters.
+    fn is_truncated(&self, bound: &[u8]) -> bool {
+        let Some(truncate_length) = self.truncate_length else {
+            return false;
+        };
+        match self.ty {
+            Primitive::String => String::from_utf8_lossy(bound).chars().count() >= truncate_length,
+            Primitive::Binary => bound.len() >= truncate_length,
+            _ => false,
+        }
+    }
+}
+
+/// Serialized bound of the field in lower or upper bounds of a data file.
+fn bound(bounds: &Option<HashMap<i32, Vec<u8>>>, field_id: i32) -> Option<&[u8]> {
+    bounds
+        .as_ref()
+        .and_then(|b| b.get(&field_id))
+        .map(|b| b.as_slice())
+}
+
+/// Compare values of the same type, following the sort order of Iceberg.
+fn compare(left: &PrimitiveValue, right: &PrimitiveValue) -> Result<Ordering> {
+    left.partial_cmp(right).ok_or_else(|| {
+        Error::new(
+            ErrorKind::Unexpected,
+            format!("Can't compare {:?} with {:?}", left, right),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::types::{DataContentType, DataFileFormat, Field, Struct};
+
+    fn data_file(path: &str, counts: (i64, i64), bounds: Option<(&[u8], &[u8])>) -> DataFile {
+        let mut data_file =
+            DataFile::new(DataContentType::Data, path, DataFileFormat::Parquet, 1, 1);
+        data_file.value_counts = Some(HashMap::from([(1, counts.0)]));
+        data_file.null_value_counts = Some(HashMap::from([(1, counts.1)]));
+        if let Some((lower, upper)) = bounds {
+            data_file.lower_bounds = Some(HashMap::from([(1, lower.to_vec())]));
+            data_file.upper_bounds = Some(HashMap::from([(1, upper.to_vec())]));
+        }
+        data_file
+    }
+
+    fn aggregate(
+        aggregator: &ColumnAggregator,
+        data_files: &[DataFile],
+    ) -> MetadataAggregate<ColumnAggregates> {
+        let mut aggregates = ColumnAggregates::default();
+        for data_file in data_files {
+            aggregates = match aggregator.update(aggregates, data_file).unwrap() {
+                MetadataAggregate::Exact(aggregates) => aggregates,
+                needs_scan => return needs_scan,
+            };
+        }
+        MetadataAggregate::Exact(aggregates)
+    }
+
+    #[test]
+    fn test_int_column_aggregates() {
+        let aggregator = ColumnAggregator {
+            column: "id",
+            field_id: 1,
+            ty: &Primitive::Int,
+            truncate_length: Some(16),
+        };
+
+        let data_files = [
+            data_file(
+                "a",
+                (3, 1),
+                Some((&5i32.to_le_bytes(), &9i32.to_le_bytes())),
+            ),
+            data_file("b", (2, 2), None),
+            data_file(
+                "c",
+                (2, 0),
+                Some((&(-1i32).to_le_bytes(), &7i32.to_le_bytes())),
+            ),
+        ];
+        assert_eq!(
+            aggregate(&aggregator, &data_files),
+            MetadataAggregate::Exact(ColumnAggregates {
+                min: Some(PrimitiveValue::Int(-1)),
+                max: Some(PrimitiveValue::Int(9)),
+                null_count: 3,
+            })
+        );
+
+        let data_files = [data_file("d", (2, 0), None)];
+        assert_eq!(
+            aggregate(&aggregator, &data_files),
+            MetadataAggregate::NeedsScan("Data file d has no bounds of column id".to_string())
+        );
+    }
+
+    #[test]
+    fn test_string_column_aggregates() {
+        let aggregator = ColumnAggregator {
+            column: "name",
+            field_id: 1,
+            ty: &Primitive::String,
+            truncate_length: Some(4),
+        };
+
+        let data_files = [
+            data_file("a", (1, 0), Some((b"abc", b"abc"))),
+            data_file("b", (1, 0), Some((b"ab", b"b"))),
+        ];
+        assert_eq!(
+            aggregate(&aggregator, &data_files),
+            MetadataAggregate::Exact(ColumnAggregates {
+                min: Some(PrimitiveValue::String("ab".to_string())),
+                max: Some(PrimitiveValue::String("b".to_string())),
+                null_count: 0,
+            })
+        );
+
+        let data_files = [data_file("c", (1, 0), Some((b"abcd", b"abce")))];
+        assert_eq!(
+            aggregate(&aggregator, &data_files),
+            MetadataAggregate::NeedsScan(
+                "Data file c may have truncated bounds of column name".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_column() {
+        let fields: Vec<FieldRef> = vec![
+            Arc::new(Field::required(1, "a.b", Any::Primitive(Primitive::Int))),
+            Arc::new(Field::required(
+                2,
+                "c",
+                Any::Struct(Arc::new(Struct::new(vec![Arc::new(Field::optional(
+                    3,
+                    "d",
+                    Any::Primitive(Primitive::Long),
+                ))]))),
+            )),
+        ];
+
+        assert_eq!(resolve_column(&fields, "a.b").map(|f| f.id), Some(1));
+        assert_eq!(resolve_column(&fields, "c.d").map(|f| f.id), Some(3));
+        assert_eq!(resolve_column(&fields, "c.e").map(|f| f.id), None);
+    }
+}
@@ -0,0 +1,628 @@
+//! Reader of avro data files. Records are decoded into arrow batches by the
+//! table schema, in reverse of the conversion in `types::to_avro`.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::ops::Range;
+use std::sync::Arc;
+
+use apache_avro::schema::RecordField as AvroRecordField;
+use apache_avro::types::Value;
+use apache_avro::{Reader, Schema as AvroSchema};
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array, FixedSizeBinaryArray,
+    Float32Array, Float64Array, Int32Array, Int64Array, LargeBinaryArray, ListArray, MapArray,
+    RecordBatch, RecordBatchOptions, StringArray, StructArray, Time64MicrosecondArray,
+    TimestampMicrosecondArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{Field as ArrowField, Schema as ArrowSchema};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use opendal::Operator;
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+
+use super::ReadObserverRef;
+use crate::types::{Any, FieldRef, Primitive};
+use crate::{Error, ErrorKind, Result};
+
+/// Reads rows of an avro data file into arrow batches.
+///
+/// Fields of avro records are matched with table fields by the `field-id`
+/// attribute, or by name if the file is written without field ids. Fields
+/// missing in the file are left out of batches, so that they are filled by
+/// the projection. Columns carry field ids in their metadata like columns
+/// read from parquet files.
+pub(crate) struct AvroFileReader {
+    op: Operator,
+    // Relative file path of operator root, it must be an avro file.
+    path: String,
+    // Table fields to read, pruned to the selected nested fields.
+    fields: Vec<FieldRef>,
+    observer: Option<ReadObserverRef>,
+}
+
+impl AvroFileReader {
+    /// Create a reader of `fields` from the file at relative path `path`.
+    pub(crate) fn new(op: Operator, path: &str, fields: Vec<FieldRef>) -> Self {
+        Self {
+            op,
+            path: path.to_string(),
+            fields,
+            observer: None,
+        }
+    }
+
+    /// Notify the observer of reading the file.
+    pub(crate) fn with_observer(mut self, observer: Option<ReadObserverRef>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Read rows from position `start`, in batches of at most `batch_size`
+    /// rows. Each batch comes with the positions in data file of its rows.
+    pub(crate) async fn read(
+        self,
+        start: u64,
+        batch_size: usize,
+    ) -> Result<BoxStream<'static, Result<(RecordBatch, Vec<Range<u64>>)>>> {
+        let content = self.op.read(&self.path).await?;
+        if let Some(observer) = &self.observer {
+            observer.range_read(content.len());
+        }
+        let mut reader = Reader::new(Cursor::new(content))?;
+        let schema = reader.writer_schema().clone();
+        let fields = self.fields;
+
+        let mut position = 0;
+        let batches = std::iter::from_fn(move || {
+            let mut records = Vec::with_capacity(batch_size);
+            for record in reader.by_ref() {
+                let record = match record {
+                    Ok(record) => record,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                position += 1;
+                if position <= start {
+                    continue;
+                }
+                records.push(record);
+                if records.len() == batch_size {
+                    break;
+                }
+            }
+            if records.is_empty() {
+                return None;
+            }
+            let rows = position - records.len() as u64..position;
+            Some(record_batch(&fields, &schema, &records).map(|batch| (batch, vec![rows])))
+        });
+
+        Ok(futures::stream::iter(batches).boxed())
+    }
+}
+
+/// Build the batch of avro `records` with the fields found in `schema`.
+fn record_batch(
+    fields: &[FieldRef],
+    schema: &AvroSchema,
+    records: &[Value],
+) -> Result<RecordBatch> {
+    let values = records.iter().map(Some).collect::<Vec<_>>();
+    let (arrow_fields, columns) = struct_columns(fields, schema, &values)?;
+
+    RecordBatch::try_new_with_options(
+        Arc::new(ArrowSchema::new(arrow_fields)),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(records.len())),
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+/// Build the columns of `fields` from avro records, `None` values are null
+/// records. Fields missing in the record schema are skipped.
+fn struct_columns(
+    fields: &[FieldRef],
+    schema: &AvroSchema,
+    values: &[Option<&Value>],
+) -> Result<(Vec<ArrowField>, Vec<ArrayRef>)> {
+    let AvroSchema::Record(record_schema) = schema else {
+        return Err(unexpected_schema("record", schema));
+    };
+
+    let mut arrow_fields = vec![];
+    let mut columns = vec![];
+    for field in fields {
+        let idx = record_schema
+            .fields
+            .iter()
+            .position(|f| avro_field_id(f) == Some(field.id))
+            .or_else(|| {
+                record_schema
+                    .fields
+                    .iter()
+                    .position(|f| avro_field_id(f).is_none() && f.name == field.name)
+            });
+        let Some(idx) = idx else {
+            continue;
+        };
+
+        let children = values
+            .iter()
+            .map(|value| match value {
+                Some(Value::Record(record)) => Ok(record.get(idx).and_then(|(_, v)| non_null(v))),
+                Some(value) => Err(unexpected_value("record", value)),
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let array = read_array(
+            &field.field_type,
+            non_null_schema(&record_schema.fields[idx].schema),
+            &children,
+        )?;
+        arrow_fields.push(
+            ArrowField::new(&field.name, array.data_type().clone(), true).with_metadata(
+                HashMap::from([(PARQUET_FIELD_ID_META_KEY.to_string(), field.id.to_string())]),
+            ),
+        );
+        columns.push(array);
+    }
+
+    Ok((arrow_fields, columns))
+}
+
+/// Build the array of avro values with `schema` as `field_type`, `None`
+/// values are nulls.
+fn read_array(
+    field_type: &Any,
+    schema: &AvroSchema,
+    values: &[Option<&Value>],
+) -> Result<ArrayRef> {
+    let nulls = || NullBuffer::from(values.iter().map(Option::is_some).collect::<Vec<_>>());
+
+    match field_type {
+        Any::Primitive(primitive) => primitive_array(primitive, values),
+        Any::Struct(s) => {
+            let (fields, columns) = struct_columns(s.fields(), schema, values)?;
+            let array = if fields.is_empty() {
+                StructArray::new_empty_fields(values.len(), Some(nulls()))
+            } else {
+                StructArray::try_new(fields.into(), columns, Some(nulls()))
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?
+            };
+            Ok(Arc::new(array))
+        }
+        Any::List(list) => {
+            let AvroSchema::Array(element_schema) = schema else {
+                return Err(unexpected_schema("array", schema));
+            };
+            let mut offsets = vec![0];
+            let mut elements = vec![];
+            for value in values {
+                match value {
+                    Some(Value::Array(items)) => elements.extend(items.iter().map(non_null)),
+                    Some(value) => return Err(unexpected_value("array", value)),
+                    None => {}
+                }
+                offsets.push(elements.len() as i32);
+            }
+            let elements = read_array(
+                &list.element_type,
+                non_null_schema(element_schema),
+                &elements,
+            )?;
+            let array = ListArray::try_new(
+                Arc::new(ArrowField::new("item", elements.data_type().clone(), true)),
+                OffsetBuffer::new(offsets.into()),
+                elements,
+                Some(nulls()),
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            Ok(Arc::new(array))
+        }
+        Any::Map(map) => {
+            // Maps with string keys are avro maps, others are arrays of
+            // key-value records.
+            let string_schema = AvroSchema::String;
+            let mut offsets = vec![0];
+            let mut string_keys = vec![];
+            let mut keys = vec![];
+            let mut entries = vec![];
+            let (key_schema, value_schema) = match schema {
+                AvroSchema::Map(value_schema) => {
+                    for value in values {
+                        match value {
+                            Some(Value::Map(map)) => map.iter().for_each(|(k, v)| {
+                                string_keys.push(Value::String(k.clone()));
+                                entries.push(non_null(v));
+                            }),
+                            Some(value) => return Err(unexpected_value("map", value)),
+                            None => {}
+                        }
+                        offsets.push(entries.len() as i32);
+                    }
+                    keys.extend(string_keys.iter().map(Some));
+                    (&string_schema, value_schema.as_ref())
+                }
+                AvroSchema::Array(entry_schema) => {
+                    let AvroSchema::Record(entry_schema) = entry_schema.as_ref() else {
+                        return Err(unexpected_schema("key-value record", entry_schema));
+                    };
+                    if entry_schema.fields.len() != 2 {
+                        return Err(unexpected_schema("key-value record", schema));
+                    }
+                    for value in values {
+                        match value {
+                            Some(Value::Array(items)) => {
+                                for item in items {
+                                    let Value::Record(kv) = item else {
+                                        return Err(unexpected_value("key-value record", item));
+                                    };
+                                    keys.push(kv.first().and_then(|(_, v)| non_null(v)));
+                                    entries.push(kv.get(1).and_then(|(_, v)| non_null(v)));
+                                }
+                            }
+                            Some(value) => return Err(unexpected_value("array", value)),
+                            None => {}
+                        }
+                        offsets.push(entries.len() as i32);
+                    }
+                    (
+                        non_null_schema(&entry_schema.fields[0].schema),
+                        &entry_schema.fields[1].schema,
+                    )
+                }
+                _ => return Err(unexpected_schema("map", schema)),
+            };
+            let keys = read_array(&map.key_type, key_schema, &keys)?;
+            let entries = read_array(&map.value_type, non_null_schema(value_schema), &entries)?;
+            let entries = StructArray::try_new(
+                vec![
+                    ArrowField::new("key", keys.data_type().clone(), false),
+                    ArrowField::new("value", entries.data_type().clone(), true),
+                ]
+                .into(),
+                vec![keys, entries],
+                None,
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            let array = MapArray::try_new(
+                Arc::new(ArrowField::new(
+                    "entries",
+                    entries.data_type().clone(),
+                    false,
+                )),
+                OffsetBuffer::new(offsets.into()),
+                entries,
+                Some(nulls()),
+                false,
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            Ok(Arc::new(array))
+        }
+    }
+}
+
+/// Build the array of avro values of primitive type, avro values of narrower
+/// types are promoted.
+fn primitive_array(primitive: &Primitive, values: &[Option<&Value>]) -> Result<ArrayRef> {
+    // Collect values converted by `f` into array, `f` returns `None` if the
+    // value doesn't match the type.
+    fn collect<'a, T, A: FromIterator<Option<T>>>(
+        primitive: &Primitive,
+        values: &[Option<&'a Value>],
+        f: impl Fn(&'a Value) -> Option<T>,
+    ) -> Result<A> {
+        values
+            .iter()
+            .map(|value| {
+                value
+                    .map(|v| {
+                        f(v).ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!("Unexpected avro value {:?} of type {:?}", v, primitive),
+                            )
+                        })
+                    })
+                    .transpose()
+            })
+            .collect()
+    }
+
+    let array: ArrayRef = match primitive {
+        Primitive::Boolean => Arc::new(collect::<_, BooleanArray>(
+            primitive,
+            values,
+            |v| match v {
+                Value::Boolean(v) => Some(*v),
+                _ => None,
+            },
+        )?),
+        Primitive::Int => Arc::new(collect::<_, Int32Array>(primitive, values, |v| match v {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        })?),
+        Primitive::Long => Arc::new(collect::<_, Int64Array>(primitive, values, |v| match v {
+            Value::Long(v) => Some(*v),
+            Value::Int(v) => Some(*v as i64),
+            _ => None,
+        })?),
+        Primitive::Float => Arc::new(collect::<_, Float32Array>(
+            primitive,
+            values,
+            |v| match v {
+                Value::Float(v) => Some(*v),
+                _ => None,
+            },
+        )?),
+        Primitive::Double => Arc::new(collect::<_, Float64Array>(
+            primitive,
+            values,
+            |v| match v {
+                Value::Double(v) => Some(*v),
+                Value::Float(v) => Some(*v as f64),
+                _ => None,
+            },
+        )?),
+        Primitive::Decimal { precision, scale } => Arc::new(
+            collect::<_, Decimal128Array>(primitive, values, |v| match v {
+                Value::Decimal(v) => Vec::<u8>::try_from(v)
+                    .ok()
+                    .and_then(|bytes| decimal_from_be_bytes(&bytes)),
+                Value::Bytes(bytes) | Value::Fixed(_, bytes) => decimal_from_be_bytes(bytes),
+                _ => None,
+            })?
+            .with_precision_and_scale(*precision, *scale as i8)
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+        ),
+        Primitive::Date => Arc::new(collect::<_, Date32Array>(primitive, values, |v| match v {
+            Value::Date(v) | Value::Int(v) => Some(*v),
+            _ => None,
+        })?),
+        Primitive::Time => Arc::new(collect::<_, Time64MicrosecondArray>(
+            primitive,
+            values,
+            |v| match v {
+                Value::TimeMicros(v) | Value::Long(v) => Some(*v),
+                Value::TimeMillis(v) => Some(*v as i64 * 1000),
+                _ => None,
+            },
+        )?),
+        Primitive::Timestamp | Primitive::Timestampz => {
+            let array = collect::<_, TimestampMicrosecondArray>(primitive, values, |v| match v {
+                Value::TimestampMicros(v) | Value::Long(v) => Some(*v),
+                Value::TimestampMillis(v) => Some(*v * 1000),
+                _ => None,
+            })?;
+            match primitive {
+                // Timestampz always stored as UTC
+                Primitive::Timestampz => Arc::new(array.with_timezone("+00:00")),
+                _ => Arc::new(array),
+            }
+        }
+        Primitive::String => Arc::new(collect::<_, StringArray>(primitive, values, |v| match v {
+            Value::String(v) => Some(v.as_str()),
+            _ => None,
+        })?),
+        Primitive::Uuid => {
+            let values = collect::<_, Vec<_>>(primitive, values, |v| match v {
+                Value::Uuid(v) => Some(v.as_bytes().to_vec()),
+                Value::Fixed(16, v) => Some(v.clone()),
+                _ => None,
+            })?;
+            Arc::new(
+                FixedSizeBinaryArray::try_from_sparse_iter_with_size(values.into_iter(), 16)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+            )
+        }
+        Primitive::Fixed(size) => {
+            let values = collect::<_, Vec<_>>(primitive, values, |v| match v {
+                Value::Fixed(_, v) => Some(v.as_slice()),
+                _ => None,
+            })?;
+            Arc::new(
+                FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                    values.into_iter(),
+                    *size as i32,
+                )
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+            )
+        }
+        Primitive::Binary => Arc::new(collect::<_, LargeBinaryArray>(
+            primitive,
+            values,
+            |v| match v {
+                Value::Bytes(v) | Value::Fixed(_, v) => Some(v.as_slice()),
+                _ => None,
+            },
+        )?),
+    };
+
+    Ok(array)
+}
+
+/// Decode the unscaled value of decimal from big-endian two's complement
+/// bytes.
+fn decimal_from_be_bytes(bytes: &[u8]) -> Option<i128> {
+    if bytes.len() > 16 {
+        return None;
+    }
+    let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+    let mut buf = if negative { [0xff; 16] } else { [0; 16] };
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    Some(i128::from_be_bytes(buf))
+}
+
+/// Field id in the `field-id` attribute of avro record field.
+fn avro_field_id(field: &AvroRecordField) -> Option<i32> {
+    field
+        .custom_attributes
+        .get("field-id")
+        .and_then(|id| id.as_i64())
+        .map(|id| id as i32)
+}
+
+/// Unwrap the value of optional field, returns `None` if it's null.
+fn non_null(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Null => None,
+        Value::Union(_, value) => non_null(value),
+        value => Some(value),
+    }
+}
+
+/// Unwrap the schema of optional field.
+fn non_null_schema(schema: &AvroSchema) -> &AvroSchema {
+    match schema {
+        AvroSchema::Union(union) => union
+            .variants()
+            .iter()
+            .find(|s| !matches!(s, AvroSchema::Null))
+            .unwrap_or(schema),
+        schema => schema,
+    }
+}
+
+fn unexpected_schema(expected: &str, schema: &AvroSchema) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Expect avro {} schema, but got {:?}", expected, schema),
+    )
+}
+
+fn unexpected_value(expected: &str, value: &Value) -> Error {
+    Error::new(
+        ErrorKind::IcebergDataInvalid,
+        format!("Expect avro {} value, but got {:?}", expected, value),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int32Type, Int64Type};
+    use arrow_array::Array;
+
+    use super::*;
+    use crate::types::{to_avro_schema, Field, List, Map, Schema, Struct};
+
+    #[test]
+    fn test_read_avro_records() {
+        let point = Struct::new(vec![
+            Field::required(5, "x", Any::Primitive(Primitive::Long)).into(),
+            Field::optional(6, "y", Any::Primitive(Primitive::Long)).into(),
+        ]);
+        let tags = List {
+            element_id: 7,
+            element_required: false,
+            element_type: Box::new(Any::Primitive(Primitive::String)),
+        };
+        let props = Map {
+            key_id: 8,
+            key_type: Box::new(Any::Primitive(Primitive::String)),
+            value_id: 9,
+            value_required: true,
+            value_type: Box::new(Any::Primitive(Primitive::Int)),
+        };
+        let file_schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::required(1, "id", Any::Primitive(Primitive::Int)).into(),
+                Field::optional(2, "point", Any::Struct(Arc::new(point.clone()))).into(),
+                Field::optional(3, "tags", Any::List(tags.clone())).into(),
+                Field::optional(4, "props", Any::Map(props.clone())).into(),
+            ]),
+        );
+        let avro_schema = to_avro_schema(&file_schema, Some("data")).unwrap();
+
+        let mut writer = apache_avro::Writer::new(&avro_schema, vec![]);
+        for i in 0..3 {
+            let point = Value::Record(vec![
+                ("x".to_string(), Value::Long(i)),
+                ("y".to_string(), Value::Union(0, Box::new(Value::Null))),
+            ]);
+            let tags = Value::Array(vec![
+                Value::Union(1, Box::new(Value::String(format!("t{}", i)))),
+                Value::Union(0, Box::new(Value::Null)),
+            ]);
+            let props = Value::Map(HashMap::from([("k".to_string(), Value::Int(i as i32))]));
+            writer
+                .append(Value::Record(vec![
+                    ("id".to_string(), Value::Int(i as i32)),
+                    ("point".to_string(), Value::Union(1, Box::new(point))),
+                    ("tags".to_string(), Value::Union(1, Box::new(tags))),
+                    ("props".to_string(), Value::Union(1, Box::new(props))),
+                ]))
+                .unwrap();
+        }
+        let content = writer.into_inner().unwrap();
+        // The avro writer drops custom attributes from the schema in file
+        // header, so records are decoded with the schema carrying field ids.
+        let records = Reader::new(&content[..])
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+
+        // Read with the current schema: `id` is promoted to long and renamed,
+        // `point` is pruned to `x`, `missing` is not in the file.
+        let fields = vec![
+            Field::required(1, "renamed", Any::Primitive(Primitive::Long)).into(),
+            Field::optional(
+                2,
+                "point",
+                Any::Struct(Arc::new(Struct::new(vec![point.fields()[0].clone()]))),
+            )
+            .into(),
+            Field::optional(3, "tags", Any::List(tags)).into(),
+            Field::optional(4, "props", Any::Map(props)).into(),
+            Field::optional(10, "missing", Any::Primitive(Primitive::Long)).into(),
+        ];
+        let batch = record_batch(&fields, &avro_schema, &records).unwrap();
+
+        let field_ids = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.metadata()[PARQUET_FIELD_ID_META_KEY].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(field_ids, vec!["1", "2", "3", "4"]);
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![0, 1, 2]
+        );
+        let point = batch.column(1).as_struct();
+        assert_eq!(point.num_columns(), 1);
+        assert_eq!(
+            point
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![0, 1, 2]
+        );
+        let tags = batch.column(2).as_list::<i32>().value(1);
+        let tags = tags.as_string::<i32>();
+        assert_eq!(tags.iter().collect::<Vec<_>>(), vec![Some("t1"), None]);
+        let props = batch.column(3).as_map();
+        assert_eq!(
+            props.values().as_primitive::<Int32Type>().values().to_vec(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(props.keys().as_string::<i32>().value(2), "k");
+        assert_eq!(props.null_count(), 0);
+    }
+
+    #[test]
+    fn test_decimal_from_be_bytes() {
+        assert_eq!(decimal_from_be_bytes(&[0x01, 0x00]), Some(256));
+        assert_eq!(decimal_from_be_bytes(&[0xff, 0x00]), Some(-256));
+        assert_eq!(decimal_from_be_bytes(&[]), Some(0));
+        assert_eq!(decimal_from_be_bytes(&[0; 17]), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! Prune row groups of parquet data files with column bloom filters.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+use parquet::basic::{LogicalType, TimeUnit, Type as PhysicalType};
+use parquet::bloom_filter::Sbbf;
+use parquet::schema::types::ColumnDescriptor;
+
+use super::{ParquetFileReader, Predicate};
+use crate::types::PrimitiveValue;
+use crate::Result;
+
+/// Keep the row groups whose bloom filters can't prove that no row matches
+/// the predicate. Only `Eq` and `In` predicates are checked, and columns
+/// without bloom filters may contain any value.
+pub(crate) async fn prune_row_groups(
+    builder: &mut ParquetRecordBatchStreamBuilder<ParquetFileReader>,
+    row_groups: Vec<usize>,
+    predicate: &Predicate,
+) -> Result<Vec<usize>> {
+    let field_ids = predicate.literal_field_ids();
+    if field_ids.is_empty() {
+        return Ok(row_groups);
+    }
+
+    // Leaf columns of the fields compared with literals.
+    let parquet_schema = builder.parquet_schema();
+    let columns = (0..parquet_schema.num_columns())
+        .filter_map(|idx| {
+            let column = parquet_schema.column(idx);
+            let info = column.self_type().get_basic_info();
+            (info.has_id() && field_ids.contains(&info.id())).then_some((info.id(), idx, column))
+        })
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return Ok(row_groups);
+    }
+
+    let mut kept = Vec::with_capacity(row_groups.len());
+    for row_group in row_groups {
+        let mut bloom_filters = HashMap::new();
+        for (field_id, column_idx, column) in &columns {
+            if let Some(bloom_filter) = builder
+                .get_row_group_column_bloom_filter(row_group, *column_idx)
+                .await?
+            {
+                bloom_filters.insert(*field_id, (bloom_filter, column.clone()));
+            }
+        }
+
+        let may_match = predicate.may_match_literals(&|field_id, literal| {
+            bloom_filters
+                .get(&field_id)
+                .is_none_or(|(bloom_filter, column)| may_contain(bloom_filter, column, literal))
+        });
+        if may_match {
+            kept.push(row_group);
+        }
+    }
+    Ok(kept)
+}
+
+/// Check the literal in the bloom filter, which hashes plain encoded values
+/// of the physical type. Returns `true` if the literal can't be checked.
+///
+/// Floating point values are not checked, since `-0.0` and `NaN` have more
+/// than one encoding.
+fn may_contain(bloom_filter: &Sbbf, column: &ColumnDescriptor, literal: &PrimitiveValue) -> bool {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let micros = match column.logical_type() {
+        Some(LogicalType::Timestamp { unit, .. }) | Some(LogicalType::Time { unit, .. }) => {
+            matches!(unit, TimeUnit::MICROS(_))
+        }
+        _ => false,
+    };
+
+    match (column.physical_type(), literal) {
+        (PhysicalType::INT32, PrimitiveValue::Int(v)) => bloom_filter.check(v),
+        (PhysicalType::INT32, PrimitiveValue::Date(v)) => {
+            bloom_filter.check(&(v.signed_duration_since(epoch).num_days() as i32))
+        }
+        (PhysicalType::INT64, PrimitiveValue::Long(v)) => bloom_filter.check(v),
+        (PhysicalType::INT64, PrimitiveValue::Time(v)) if micros => {
+            bloom_filter.check(&NaiveDateTime::new(epoch, *v).timestamp_micros())
+        }
+        (PhysicalType::INT64, PrimitiveValue::Timestamp(v)) if micros => {
+            bloom_filter.check(&v.timestamp_micros())
+        }
+        (PhysicalType::INT64, PrimitiveValue::Timestampz(v)) if micros => {
+            bloom_filter.check(&v.timestamp_micros())
+        }
+        (PhysicalType::BYTE_ARRAY, PrimitiveValue::String(v)) => bloom_filter.check(&v.as_str()),
+        (PhysicalType::BYTE_ARRAY, PrimitiveValue::Binary(v))
+        | (PhysicalType::FIXED_LEN_BYTE_ARRAY, PrimitiveValue::Fixed(v)) => bloom_filter.check(v),
+        (PhysicalType::FIXED_LEN_BYTE_ARRAY, PrimitiveValue::Uuid(v)) => {
+            bloom_filter.check(&v.as_bytes().to_vec())
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Int64Array, RecordBatch};
+    use arrow_schema::{DataType, Field, Schema};
+    use opendal::services::Memory;
+    use opendal::Operator;
+    use parquet::arrow::{AsyncArrowWriter, PARQUET_FIELD_ID_META_KEY};
+    use parquet::file::properties::WriterProperties;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prune_row_groups() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let id_field = Field::new("id", DataType::Int64, false).with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            "1".to_string(),
+        )]));
+        let schema = Arc::new(Schema::new(vec![id_field]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef],
+        )
+        .unwrap();
+
+        // Two row groups, with ids 1, 2 and 3, 4.
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .set_bloom_filter_enabled(true)
+            .build();
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, schema, 0, Some(props))?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("/data/a.parquet", buf).await?;
+
+        let file_reader = ParquetFileReader::try_new(op, "/t", "/t/data/a.parquet")?;
+        let mut builder = ParquetRecordBatchStreamBuilder::new(file_reader).await?;
+
+        assert_eq!(
+            prune_row_groups(
+                &mut builder,
+                vec![0, 1],
+                &Predicate::Eq(1, PrimitiveValue::Long(3))
+            )
+            .await?,
+            vec![1]
+        );
+        assert_eq!(
+            prune_row_groups(
+                &mut builder,
+                vec![0, 1],
+                &Predicate::In(1, vec![PrimitiveValue::Long(1), PrimitiveValue::Long(4)])
+            )
+            .await?,
+            vec![0, 1]
+        );
+        assert_eq!(
+            prune_row_groups(
+                &mut builder,
+                vec![0, 1],
+                &Predicate::Eq(1, PrimitiveValue::Long(3)).or(Predicate::IsNull(1))
+            )
+            .await?,
+            vec![0, 1]
+        );
+        // Columns without bloom filters may contain any value.
+        assert_eq!(
+            prune_row_groups(
+                &mut builder,
+                vec![0, 1],
+                &Predicate::Eq(2, PrimitiveValue::Long(3))
+            )
+            .await?,
+            vec![0, 1]
+        );
+
+        Ok(())
+    }
+}
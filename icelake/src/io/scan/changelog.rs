@@ -0,0 +1,539 @@
+//! Changelog scan produces the row-level changes of table between
+//! snapshots, like the `changes` view of Spark.
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+
+use crate::types::{DataContentType, DataFile, ManifestStatus};
+use crate::{Error, ErrorKind, Result, Table};
+
+use super::{
+    DeleteFileEntry, DeleteFileIndex, FileScan, FileScanTask, RecordBatchStream, TableScan,
+};
+
+/// Column of change type, its value is `INSERT` or `DELETE`.
+pub const CHANGE_TYPE_COLUMN_NAME: &str = "_change_type";
+/// Column of change ordinal, it's the order of the commit snapshot in the
+/// changelog, starting from 0.
+pub const CHANGE_ORDINAL_COLUMN_NAME: &str = "_change_ordinal";
+/// Column of the id of snapshot which commits the change.
+pub const COMMIT_SNAPSHOT_ID_COLUMN_NAME: &str = "_commit_snapshot_id";
+
+/// Type of row change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeType {
+    /// Rows are inserted.
+    Insert,
+    /// Rows are deleted.
+    Delete,
+}
+
+impl ChangeType {
+    /// Value of the change type in `_change_type` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Insert => "INSERT",
+            ChangeType::Delete => "DELETE",
+        }
+    }
+}
+
+/// A task of changelog scan, rows of the task are changed in the same way
+/// by the same snapshot.
+#[derive(Debug, Clone)]
+pub struct ChangelogScanTask {
+    change_type: ChangeType,
+    change_ordinal: i32,
+    commit_snapshot_id: i64,
+    /// The data file to scan, with the delete files applied to it. They are
+    /// the delete files after the commit for inserted rows, and the delete
+    /// files before the commit for deleted rows.
+    task: FileScanTask,
+    /// Delete files added by the commit snapshot. If it's not empty, only
+    /// rows deleted by them are changed.
+    added_deletes: Vec<DeleteFileEntry>,
+}
+
+impl ChangelogScanTask {
+    /// Type of the change.
+    pub fn change_type(&self) -> ChangeType {
+        self.change_type
+    }
+
+    /// Order of the commit snapshot in the changelog.
+    pub fn change_ordinal(&self) -> i32 {
+        self.change_ordinal
+    }
+
+    /// Id of the snapshot which commits the change.
+    pub fn commit_snapshot_id(&self) -> i64 {
+        self.commit_snapshot_id
+    }
+
+    /// The task to scan the data file.
+    pub fn task(&self) -> &FileScanTask {
+        &self.task
+    }
+
+    /// Delete files added by the commit snapshot to delete rows of the data
+    /// file.
+    pub fn added_deletes(&self) -> impl Iterator<Item = &DataFile> {
+        self.added_deletes.iter().map(|e| &e.delete_file)
+    }
+}
+
+/// Scan of a changelog task.
+pub struct ChangelogFileScan {
+    file_scan: FileScan,
+    change_type: ChangeType,
+    change_ordinal: i32,
+    commit_snapshot_id: i64,
+}
+
+pub type ChangelogScanStream = BoxStream<'static, Result<ChangelogFileScan>>;
+
+impl ChangelogFileScan {
+    /// Scan the changed rows, with `_change_type`, `_change_ordinal` and
+    /// `_commit_snapshot_id` columns appended.
+    pub async fn scan(self) -> Result<RecordBatchStream> {
+        let change_type = self.change_type;
+        let change_ordinal = self.change_ordinal;
+        let commit_snapshot_id = self.commit_snapshot_id;
+        let stream = self.file_scan.scan().await?.map(move |batch| {
+            append_change_columns(batch?, change_type, change_ordinal, commit_snapshot_id)
+        });
+
+        Ok(Box::pin(stream))
+    }
+
+    pub fn path(&self) -> &str {
+        self.file_scan.path()
+    }
+}
+
+impl TableScan {
+    /// Plan the changelog tasks of snapshots after `from_snapshot_id`
+    /// (exclusive) up to the snapshot to scan (inclusive). All ancestors are
+    /// included if `from_snapshot_id` is not set.
+    ///
+    /// Changes of each snapshot are derived from:
+    ///
+    /// - Added data files, whose rows are inserted.
+    /// - Removed data files, whose rows are deleted.
+    /// - Added position and equality delete files, whose deleted rows in
+    ///   existing data files are deleted.
+    ///
+    /// `replace` snapshots are skipped since they don't change table data.
+    pub async fn plan_changelog_tasks(&self, table: &Table) -> Result<Vec<ChangelogScanTask>> {
+        let metadata = table.current_table_metadata();
+        let schema = self.schema(metadata)?;
+        let snapshots =
+            Self::snapshots_between(metadata, self.from_snapshot_id, self.snapshot(metadata)?)?;
+
+        let mut tasks = vec![];
+        let mut change_ordinal = 0;
+        for snapshot in snapshots {
+            if snapshot.operation() == Some("replace") {
+                continue;
+            }
+            let commit_snapshot_id = snapshot.snapshot_id;
+
+            let mut added_data_files = vec![];
+            let mut removed_data_files = vec![];
+            let mut existing_data_files = vec![];
+            let mut added_deletes = vec![];
+            let mut removed_deletes = vec![];
+            let mut existing_deletes = vec![];
+            // Entries neither committed by the snapshot nor alive are never
+            // used, they are filtered out while decoding manifests.
+            let mut entries = table
+                .manifest_entries_of_snapshot(
+                    snapshot,
+                    |_| true,
+                    move |e| e.snapshot_id == Some(commit_snapshot_id) || e.is_alive(),
+                )
+                .await?;
+            while let Some((spec_id, entry)) = entries.try_next().await? {
+                let committed = entry.snapshot_id == Some(commit_snapshot_id);
+                let added = committed && entry.status == ManifestStatus::Added;
+                let removed = committed && entry.status == ManifestStatus::Deleted;
+                let alive = entry.is_alive();
+                let sequence_number = entry.data_sequence_number();
+                match entry.data_file.content {
+                    DataContentType::Data => {
+                        let data_file = (entry.data_file, sequence_number, spec_id);
+                        if added {
+                            added_data_files.push(data_file);
+                        } else if removed {
+                            removed_data_files.push(data_file);
+                        } else if alive {
+                            existing_data_files.push(data_file);
+                        }
+                    }
+                    DataContentType::PostionDeletes | DataContentType::EqualityDeletes => {
+                        let delete_file = DeleteFileEntry {
+                            delete_file: entry.data_file,
+                            sequence_number,
+                            spec_id,
+                        };
+                        if added {
+                            added_deletes.push(delete_file);
+                        } else if removed {
+                            removed_deletes.push(delete_file);
+                        } else if alive {
+                            existing_deletes.push(delete_file);
+                        }
+                    }
+                }
+            }
+
+            // Delete files before and after the commit.
+            let deletes_before = DeleteFileIndex::new(
+                existing_deletes
+                    .iter()
+                    .chain(removed_deletes.iter())
+                    .cloned(),
+            );
+            let deletes_after =
+                DeleteFileIndex::new(existing_deletes.into_iter().chain(added_deletes.clone()));
+
+            for (data_file, sequence_number, spec_id) in removed_data_files {
+                if let Some(task) = self.new_task(
+                    metadata,
+                    schema,
+                    data_file,
+                    sequence_number,
+                    spec_id,
+                    &deletes_before,
+                )? {
+                    tasks.push(ChangelogScanTask {
+                        change_type: ChangeType::Delete,
+                        change_ordinal,
+                        commit_snapshot_id,
+                        task,
+                        added_deletes: vec![],
+                    });
+                }
+            }
+
+            if !added_deletes.is_empty() {
+                let added_deletes = DeleteFileIndex::new(added_deletes);
+                for (data_file, sequence_number, spec_id) in existing_data_files {
+                    let (position_deletes, equality_deletes) =
+                        added_deletes.for_data_file(&data_file, sequence_number, spec_id);
+                    if position_deletes.is_empty() && equality_deletes.is_empty() {
+                        continue;
+                    }
+                    if let Some(task) = self.new_task(
+                        metadata,
+                        schema,
+                        data_file,
+                        sequence_number,
+                        spec_id,
+                        &deletes_before,
+                    )? {
+                        tasks.push(ChangelogScanTask {
+                            change_type: ChangeType::Delete,
+                            change_ordinal,
+                            commit_snapshot_id,
+                            task,
+                            added_deletes: [position_deletes, equality_deletes].concat(),
+                        });
+                    }
+                }
+            }
+
+            for (data_file, sequence_number, spec_id) in added_data_files {
+                if let Some(task) = self.new_task(
+                    metadata,
+                    schema,
+                    data_file,
+                    sequence_number,
+                    spec_id,
+                    &deletes_after,
+                )? {
+                    tasks.push(ChangelogScanTask {
+                        change_type: ChangeType::Insert,
+                        change_ordinal,
+                        commit_snapshot_id,
+                        task,
+                        added_deletes: vec![],
+                    });
+                }
+            }
+
+            change_ordinal += 1;
+        }
+
+        Ok(tasks)
+    }
+
+    /// Scan the row-level changes, see [`TableScan::plan_changelog_tasks`].
+    pub async fn changelog_scan(&self, table: &Table) -> Result<ChangelogScanStream> {
+        let streams = self
+            .plan_changelog_tasks(table)
+            .await?
+            .into_iter()
+            .map(|task| self.open_changelog_task(table, task))
+            .collect::<Vec<_>>();
+
+        Ok(Box::pin(futures::stream::iter(streams)))
+    }
+
+    /// Create a [`ChangelogFileScan`] to execute the task with configurations
+    /// of this scan.
+    pub fn open_changelog_task(
+        &self,
+        table: &Table,
+        task: ChangelogScanTask,
+    ) -> Result<ChangelogFileScan> {
+        let mut file_scan = self.open_task(table, task.task)?;
+        file_scan.deleted_by = task.added_deletes;
+
+        Ok(ChangelogFileScan {
+            file_scan,
+            change_type: task.change_type,
+            change_ordinal: task.change_ordinal,
+            commit_snapshot_id: task.commit_snapshot_id,
+        })
+    }
+}
+
+/// Append `_change_type`, `_change_ordinal` and `_commit_snapshot_id`
+/// columns to the batch.
+fn append_change_columns(
+    batch: RecordBatch,
+    change_type: ChangeType,
+    change_ordinal: i32,
+    commit_snapshot_id: i64,
+) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+    let schema = batch.schema();
+
+    let mut fields = schema.fields().iter().cloned().collect::<Vec<_>>();
+    fields.extend([
+        Arc::new(Field::new(CHANGE_TYPE_COLUMN_NAME, DataType::Utf8, false)),
+        Arc::new(Field::new(
+            CHANGE_ORDINAL_COLUMN_NAME,
+            DataType::Int32,
+            false,
+        )),
+        Arc::new(Field::new(
+            COMMIT_SNAPSHOT_ID_COLUMN_NAME,
+            DataType::Int64,
+            false,
+        )),
+    ]);
+    let mut columns = batch.columns().to_vec();
+    columns.extend([
+        Arc::new(StringArray::from(vec![change_type.as_str(); num_rows])) as ArrayRef,
+        Arc::new(Int32Array::from(vec![change_ordinal; num_rows])),
+        Arc::new(Int64Array::from(vec![commit_snapshot_id; num_rows])),
+    ]);
+
+    RecordBatch::try_new(
+        Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        )),
+        columns,
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{
+        cast::AsArray,
+        types::{Int32Type, Int64Type},
+    };
+
+    use super::*;
+    use crate::test_utils;
+
+    fn file_name(data_file: &DataFile) -> &str {
+        data_file.file_path.rsplit('/').next().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_changelog_scan() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        // Insert data file `a`.
+        let a = test_utils::write_data_file(&table, "a", &[1, 2, 3]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a.clone()], vec![]).await?;
+        // Delete the first row of `a` by position and insert data file `b`.
+        let b = test_utils::write_data_file(&table, "b", &[4, 5]).await?;
+        let pos_delete = test_utils::write_position_delete_file(&table, "pd", &[(&a, 0)]).await?;
+        let s2 = test_utils::commit(&mut table, vec![b.clone()], vec![pos_delete]).await?;
+        // Delete rows of id 5 by equality.
+        let eq_delete = test_utils::write_equality_delete_file(&table, "ed", &[5]).await?;
+        let s3 = test_utils::commit(&mut table, vec![], vec![eq_delete]).await?;
+        // Compact `a` into `c`, which doesn't change table data.
+        let c = test_utils::write_data_file(&table, "c", &[2, 3]).await?;
+        test_utils::rewrite(&mut table, "replace", &[a], vec![c]).await?;
+        // Remove data file `b`.
+        let s5 = test_utils::rewrite(&mut table, "delete", &[b], vec![]).await?;
+
+        let scan = table.new_scan_builder().build().unwrap();
+        let tasks = scan.plan_changelog_tasks(&table).await?;
+        let planned = tasks
+            .iter()
+            .map(|t| {
+                (
+                    t.change_type(),
+                    t.change_ordinal(),
+                    t.commit_snapshot_id(),
+                    file_name(t.task().data_file()),
+                    t.added_deletes().map(file_name).collect::<Vec<_>>(),
+                    t.task()
+                        .position_deletes()
+                        .chain(t.task().equality_deletes())
+                        .map(file_name)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        // Position delete files without bounds of file paths apply to all
+        // data files of the same or older sequence numbers.
+        assert_eq!(
+            planned,
+            vec![
+                (ChangeType::Insert, 0, s1, "a.parquet", vec![], vec![]),
+                // Rows of existing data files deleted by added delete files
+                // are read with the delete files before the commit.
+                (
+                    ChangeType::Delete,
+                    1,
+                    s2,
+                    "a.parquet",
+                    vec!["pd.parquet"],
+                    vec![]
+                ),
+                (
+                    ChangeType::Insert,
+                    1,
+                    s2,
+                    "b.parquet",
+                    vec![],
+                    vec!["pd.parquet"]
+                ),
+                (
+                    ChangeType::Delete,
+                    2,
+                    s3,
+                    "a.parquet",
+                    vec!["ed.parquet"],
+                    vec!["pd.parquet"]
+                ),
+                (
+                    ChangeType::Delete,
+                    2,
+                    s3,
+                    "b.parquet",
+                    vec!["ed.parquet"],
+                    vec!["pd.parquet"]
+                ),
+                // Rows of removed data files are read with the delete files
+                // before the commit, the replace snapshot is skipped.
+                (
+                    ChangeType::Delete,
+                    3,
+                    s5,
+                    "b.parquet",
+                    vec![],
+                    vec!["pd.parquet", "ed.parquet"]
+                ),
+            ]
+        );
+
+        let batches = scan
+            .changelog_scan(&table)
+            .await?
+            .and_then(|file_scan| file_scan.scan())
+            .try_flatten()
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut changes = vec![];
+        for batch in &batches {
+            let ids = batch
+                .column_by_name("id")
+                .unwrap()
+                .as_primitive::<Int64Type>();
+            let change_types = batch
+                .column_by_name(CHANGE_TYPE_COLUMN_NAME)
+                .unwrap()
+                .as_string::<i32>();
+            let ordinals = batch
+                .column_by_name(CHANGE_ORDINAL_COLUMN_NAME)
+                .unwrap()
+                .as_primitive::<Int32Type>();
+            for i in 0..batch.num_rows() {
+                changes.push((change_types.value(i), ordinals.value(i), ids.value(i)));
+            }
+        }
+        assert_eq!(
+            changes,
+            vec![
+                ("INSERT", 0, 1),
+                ("INSERT", 0, 2),
+                ("INSERT", 0, 3),
+                ("DELETE", 1, 1),
+                ("INSERT", 1, 4),
+                ("INSERT", 1, 5),
+                ("DELETE", 2, 5),
+                ("DELETE", 3, 4),
+            ]
+        );
+
+        // Ordinals start from the first snapshot after `from_snapshot_id`.
+        let scan = table
+            .new_scan_builder()
+            .with_from_snapshot_id(s2)
+            .build()
+            .unwrap();
+        let tasks = scan.plan_changelog_tasks(&table).await?;
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|t| (t.change_ordinal(), t.commit_snapshot_id()))
+                .collect::<Vec<_>>(),
+            vec![(0, s3), (0, s3), (1, s5)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_change_columns() {
+        let batch = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+
+        let batch = append_change_columns(batch, ChangeType::Delete, 3, 42).unwrap();
+
+        assert_eq!(batch.num_columns(), 4);
+        assert_eq!(
+            batch
+                .column_by_name(CHANGE_TYPE_COLUMN_NAME)
+                .unwrap()
+                .as_string::<i32>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some("DELETE"), Some("DELETE")]
+        );
+        assert_eq!(
+            batch
+                .column_by_name(COMMIT_SNAPSHOT_ID_COLUMN_NAME)
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![42, 42]
+        );
+    }
+}
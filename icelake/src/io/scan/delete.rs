@@ -0,0 +1,828 @@
+//! This module provides the ability to apply delete files while scanning
+//! data files.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+
+use arrow_arith::boolean::and;
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{new_null_array, ArrayRef, BooleanArray, RecordBatch};
+use arrow_row::{RowConverter, Rows, SortField};
+use arrow_schema::{DataType, Fields, Schema as ArrowSchema};
+use futures::TryStreamExt;
+use opendal::Operator;
+use parquet::arrow::{ParquetRecordBatchStreamBuilder, ProjectionMask, PARQUET_FIELD_ID_META_KEY};
+
+use crate::types::{DataContentType, DataFile, StructValue};
+use crate::{Error, ErrorKind, Result};
+
+use super::ParquetFileReader;
+
+/// Field id of the `file_path` column in position delete files.
+pub(crate) const DELETE_FILE_PATH_FIELD_ID: i32 = 2147483546;
+/// Field id of the `pos` column in position delete files.
+pub(crate) const DELETE_POS_FIELD_ID: i32 = 2147483545;
+
+const DELETE_FILE_PATH_COLUMN_NAME: &str = "file_path";
+const DELETE_POS_COLUMN_NAME: &str = "pos";
+
+/// A live delete file of the snapshot, with the information inherited from
+/// its manifest.
+#[derive(Debug, Clone)]
+pub(crate) struct DeleteFileEntry {
+    pub(crate) delete_file: DataFile,
+    /// Data sequence number of the delete file.
+    pub(crate) sequence_number: i64,
+    /// Partition spec id of the manifest tracking this delete file.
+    pub(crate) spec_id: i32,
+}
+
+impl DeleteFileEntry {
+    /// Equality delete files written with an unpartitioned spec apply to
+    /// data files of all partitions.
+    fn is_global(&self) -> bool {
+        self.delete_file.partition.iter().next().is_none()
+    }
+
+    /// Use the bounds of `file_path` column to check whether this position
+    /// delete file may contain deletes of the data file.
+    fn may_contain_path(&self, path: &str) -> bool {
+        let bound = |bounds: &Option<HashMap<i32, Vec<u8>>>| {
+            bounds
+                .as_ref()
+                .and_then(|b| b.get(&DELETE_FILE_PATH_FIELD_ID))
+                .cloned()
+        };
+
+        if let Some(lower) = bound(&self.delete_file.lower_bounds) {
+            if path.as_bytes() < lower.as_slice() {
+                return false;
+            }
+        }
+        if let Some(upper) = bound(&self.delete_file.upper_bounds) {
+            if path.as_bytes() > upper.as_slice() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Index of the live delete files of a snapshot.
+///
+/// Delete files are grouped by partition spec id and partition, and sorted by
+/// data sequence number in each group, so that the delete files applied to a
+/// data file can be found without checking every delete file.
+///
+/// Reference:
+///
+/// - [Scan Planning](https://iceberg.apache.org/spec/#scan-planning)
+#[derive(Debug, Default)]
+pub(crate) struct DeleteFileIndex {
+    /// Equality delete files written with an unpartitioned spec.
+    global_equality_deletes: Vec<DeleteFileEntry>,
+    position_deletes: HashMap<(i32, StructValue), Vec<DeleteFileEntry>>,
+    equality_deletes: HashMap<(i32, StructValue), Vec<DeleteFileEntry>>,
+}
+
+impl DeleteFileIndex {
+    pub(crate) fn new(delete_files: impl IntoIterator<Item = DeleteFileEntry>) -> Self {
+        let mut index = Self::default();
+        for entry in delete_files {
+            let key = (entry.spec_id, entry.delete_file.partition.clone());
+            match entry.delete_file.content {
+                DataContentType::PostionDeletes => {
+                    index.position_deletes.entry(key).or_default().push(entry)
+                }
+                DataContentType::EqualityDeletes if entry.is_global() => {
+                    index.global_equality_deletes.push(entry)
+                }
+                DataContentType::EqualityDeletes => {
+                    index.equality_deletes.entry(key).or_default().push(entry)
+                }
+                DataContentType::Data => {}
+            }
+        }
+
+        index
+            .global_equality_deletes
+            .sort_by_key(|e| e.sequence_number);
+        for entries in index
+            .position_deletes
+            .values_mut()
+            .chain(index.equality_deletes.values_mut())
+        {
+            entries.sort_by_key(|e| e.sequence_number);
+        }
+        index
+    }
+
+    /// Find the position delete files and equality delete files which must be
+    /// applied to the data file.
+    ///
+    /// - Position delete files apply when their data sequence number is
+    ///   greater than or equal to the data file's, and they are in the same
+    ///   partition.
+    /// - Equality delete files apply when their data sequence number is
+    ///   strictly greater than the data file's, and they are in the same
+    ///   partition or are global.
+    pub(crate) fn for_data_file(
+        &self,
+        data_file: &DataFile,
+        sequence_number: i64,
+        spec_id: i32,
+    ) -> (Vec<DeleteFileEntry>, Vec<DeleteFileEntry>) {
+        let key = (spec_id, data_file.partition.clone());
+
+        let position_deletes = self
+            .position_deletes
+            .get(&key)
+            .map(|entries| {
+                let start = entries.partition_point(|e| e.sequence_number < sequence_number);
+                entries[start..]
+                    .iter()
+                    .filter(|e| e.may_contain_path(&data_file.file_path))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let newer_than = |entries: &[DeleteFileEntry]| {
+            let start = entries.partition_point(|e| e.sequence_number <= sequence_number);
+            entries[start..].to_vec()
+        };
+        let mut equality_deletes = newer_than(&self.global_equality_deletes);
+        if let Some(entries) = self.equality_deletes.get(&key) {
+            equality_deletes.extend(newer_than(entries));
+        }
+
+        (position_deletes, equality_deletes)
+    }
+}
+
+/// Sorted positions of the deleted rows in a data file.
+#[derive(Debug, Default)]
+pub(crate) struct PositionDeletes {
+    positions: Vec<u64>,
+}
+
+impl PositionDeletes {
+    /// Load the positions deleted in `data_file_path` from position delete files.
+    pub(crate) async fn load(
+        op: &Operator,
+        table_location: &str,
+        data_file_path: &str,
+        delete_files: &[DataFile],
+    ) -> Result<Self> {
+        let mut positions = vec![];
+        for delete_file in delete_files {
+            let file_reader =
+                ParquetFileReader::try_new(op.clone(), table_location, &delete_file.file_path)?;
+            let builder = ParquetRecordBatchStreamBuilder::new(file_reader).await?;
+            let column_idx = [DELETE_FILE_PATH_COLUMN_NAME, DELETE_POS_COLUMN_NAME]
+                .iter()
+                .map(|name| builder.schema().index_of(name))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| Error::new(ErrorKind::IcebergDataInvalid, format!("{}", e)))?;
+            let projection = ProjectionMask::roots(builder.parquet_schema(), column_idx);
+
+            let mut stream = builder.with_projection(projection).build()?;
+            while let Some(batch) = stream.try_next().await? {
+                Self::collect_positions(&batch, data_file_path, &mut positions)?;
+            }
+        }
+
+        positions.sort_unstable();
+        positions.dedup();
+        Ok(Self { positions })
+    }
+
+    fn collect_positions(
+        batch: &RecordBatch,
+        data_file_path: &str,
+        positions: &mut Vec<u64>,
+    ) -> Result<()> {
+        let column = |name: &str, data_type: &DataType| {
+            let idx = batch
+                .schema()
+                .index_of(name)
+                .map_err(|e| Error::new(ErrorKind::IcebergDataInvalid, format!("{}", e)))?;
+            arrow_cast::cast(batch.column(idx), data_type)
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+        };
+        let file_paths = column(DELETE_FILE_PATH_COLUMN_NAME, &DataType::Utf8)?;
+        let file_paths = file_paths.as_string::<i32>();
+        let pos = column(DELETE_POS_COLUMN_NAME, &DataType::Int64)?;
+        let pos = pos.as_primitive::<Int64Type>();
+
+        for idx in 0..batch.num_rows() {
+            if file_paths.value(idx) == data_file_path {
+                positions.push(pos.value(idx) as u64);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether there is no deleted row.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Check whether the row at `pos` is deleted.
+    pub(crate) fn contains(&self, pos: u64) -> bool {
+        self.positions.binary_search(&pos).is_ok()
+    }
+
+    /// Check whether any row in `range` is deleted.
+    fn intersects(&self, range: &Range<u64>) -> bool {
+        let idx = self.positions.partition_point(|p| *p < range.start);
+        idx < self.positions.len() && self.positions[idx] < range.end
+    }
+}
+
+/// Keys of the same columns, like keys deleted by equality delete files with
+/// the same equality ids.
+pub(crate) struct EqualityDeleteSet {
+    /// Sorted field ids of the equality columns.
+    field_ids: Vec<i32>,
+    /// Data types of the equality columns, in order of `field_ids`.
+    data_types: Vec<DataType>,
+    converter: RowConverter,
+    /// Deleted keys encoded in row format.
+    keys: HashSet<Box<[u8]>>,
+}
+
+impl EqualityDeleteSet {
+    pub(crate) fn try_new(field_ids: Vec<i32>, data_types: Vec<DataType>) -> Result<Self> {
+        let converter = RowConverter::new(
+            data_types
+                .iter()
+                .map(|data_type| SortField::new(data_type.clone()))
+                .collect(),
+        )
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::ArrowError,
+                format!("Failed to create row converter, error: {}", e),
+            )
+        })?;
+
+        Ok(Self {
+            field_ids,
+            data_types,
+            converter,
+            keys: HashSet::new(),
+        })
+    }
+
+    /// Insert keys of the columns, which are in order of `field_ids`.
+    pub(crate) fn insert(&mut self, columns: &[ArrayRef]) -> Result<()> {
+        let rows = self.converter.convert_columns(columns).map_err(|e| {
+            Error::new(
+                ErrorKind::ArrowError,
+                format!("Failed to convert columns, error: {}", e),
+            )
+        })?;
+        self.keys
+            .extend(rows.iter().map(|row| Box::<[u8]>::from(row.as_ref())));
+        Ok(())
+    }
+
+    /// Whether each row of the batch matches any key.
+    pub(crate) fn contains(
+        &mut self,
+        batch: &RecordBatch,
+        column_of: impl Fn(i32) -> Option<usize>,
+    ) -> Result<BooleanArray> {
+        let rows = self.convert(batch, column_of)?;
+        Ok(rows
+            .iter()
+            .map(|row| Some(self.keys.contains(row.as_ref())))
+            .collect())
+    }
+
+    /// Encode the equality columns into rows, columns missing in the
+    /// batch are treated as nulls.
+    fn convert(
+        &mut self,
+        batch: &RecordBatch,
+        column_of: impl Fn(i32) -> Option<usize>,
+    ) -> Result<Rows> {
+        let columns = self
+            .field_ids
+            .iter()
+            .zip(self.data_types.iter())
+            .map(|(field_id, data_type)| match column_of(*field_id) {
+                Some(idx) if batch.column(idx).data_type() == data_type => {
+                    Ok(batch.column(idx).clone())
+                }
+                Some(idx) => arrow_cast::cast(batch.column(idx), data_type)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e))),
+                None => Ok(new_null_array(data_type, batch.num_rows())),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.converter.convert_columns(&columns).map_err(|e| {
+            Error::new(
+                ErrorKind::ArrowError,
+                format!("Failed to convert columns, error: {}", e),
+            )
+        })
+    }
+}
+
+/// Keys of the rows deleted by equality delete files.
+#[derive(Default)]
+pub(crate) struct EqualityDeletes {
+    sets: Vec<EqualityDeleteSet>,
+}
+
+impl EqualityDeletes {
+    /// Load the deleted keys from equality delete files.
+    pub(crate) async fn load(
+        op: &Operator,
+        table_location: &str,
+        delete_files: &[DataFile],
+    ) -> Result<Self> {
+        let mut deletes = Self::default();
+        for delete_file in delete_files {
+            let mut field_ids = delete_file.equality_ids.clone().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!(
+                        "Equality ids of equality delete file {} are missing",
+                        delete_file.file_path
+                    ),
+                )
+            })?;
+            field_ids.sort_unstable();
+            field_ids.dedup();
+
+            let file_reader =
+                ParquetFileReader::try_new(op.clone(), table_location, &delete_file.file_path)?;
+            let builder = ParquetRecordBatchStreamBuilder::new(file_reader).await?;
+            let file_schema = builder.schema().clone();
+            let column_idx = field_ids
+                .iter()
+                .map(|field_id| {
+                    field_id_index(&file_schema, *field_id).ok_or_else(|| {
+                        // Only top level columns are resolved in data files.
+                        if is_nested_field(file_schema.fields(), *field_id) {
+                            Error::new(
+                                ErrorKind::IcebergFeatureUnsupported,
+                                format!(
+                                    "Nested field {} of equality delete file {} is not supported",
+                                    field_id, delete_file.file_path
+                                ),
+                            )
+                        } else {
+                            Error::new(
+                                ErrorKind::IcebergDataInvalid,
+                                format!(
+                                    "Field {} not found in equality delete file {}",
+                                    field_id, delete_file.file_path
+                                ),
+                            )
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let set_idx = match deletes.sets.iter().position(|s| s.field_ids == field_ids) {
+                Some(idx) => idx,
+                None => {
+                    let data_types = column_idx
+                        .iter()
+                        .map(|idx| file_schema.field(*idx).data_type().clone())
+                        .collect();
+                    deletes
+                        .sets
+                        .push(EqualityDeleteSet::try_new(field_ids, data_types)?);
+                    deletes.sets.len() - 1
+                }
+            };
+            let set = &mut deletes.sets[set_idx];
+
+            let projection = ProjectionMask::roots(builder.parquet_schema(), column_idx);
+            let mut stream = builder.with_projection(projection).build()?;
+            while let Some(batch) = stream.try_next().await? {
+                let schema = batch.schema();
+                let rows = set.convert(&batch, |id| field_id_index(&schema, id))?;
+                set.keys
+                    .extend(rows.iter().map(|row| Box::<[u8]>::from(row.as_ref())));
+            }
+        }
+
+        Ok(deletes)
+    }
+
+    /// Field ids of all equality columns.
+    pub(crate) fn field_ids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.sets.iter().flat_map(|s| s.field_ids.iter().copied())
+    }
+
+    /// Build the selection of rows in batch: rows matching any deleted key
+    /// are filtered out. `column_of` returns the column index of field id.
+    ///
+    /// Returns `None` if all rows are selected.
+    pub(crate) fn row_selection(
+        &mut self,
+        batch: &RecordBatch,
+        column_of: impl Fn(i32) -> Option<usize>,
+    ) -> Result<Option<BooleanArray>> {
+        if self.sets.is_empty() || batch.num_rows() == 0 {
+            return Ok(None);
+        }
+
+        let mut selection = vec![true; batch.num_rows()];
+        for set in self.sets.iter_mut() {
+            let rows = set.convert(batch, &column_of)?;
+            for (idx, row) in rows.iter().enumerate() {
+                if set.keys.contains(row.as_ref()) {
+                    selection[idx] = false;
+                }
+            }
+        }
+
+        if selection.iter().all(|s| *s) {
+            Ok(None)
+        } else {
+            Ok(Some(BooleanArray::from(selection)))
+        }
+    }
+}
+
+/// Position deletes and equality deletes applied to a data file.
+pub(crate) struct DeleteFilter {
+    position_deletes: PositionDeletes,
+    equality_deletes: EqualityDeletes,
+}
+
+impl DeleteFilter {
+    /// Load the deletes of `data_file_path` from delete files.
+    pub(crate) async fn load(
+        op: &Operator,
+        table_location: &str,
+        data_file_path: &str,
+        delete_files: &[DeleteFileEntry],
+    ) -> Result<Self> {
+        let (position_deletes, equality_deletes): (Vec<_>, Vec<_>) = delete_files
+            .iter()
+            .map(|e| e.delete_file.clone())
+            .partition(|f| f.content == DataContentType::PostionDeletes);
+
+        Ok(Self {
+            position_deletes: PositionDeletes::load(
+                op,
+                table_location,
+                data_file_path,
+                &position_deletes,
+            )
+            .await?,
+            equality_deletes: EqualityDeletes::load(op, table_location, &equality_deletes).await?,
+        })
+    }
+
+    /// Field ids of all equality columns.
+    pub(crate) fn field_ids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.equality_deletes.field_ids()
+    }
+
+    /// Build the selection of rows in batch at positions `ranges`: rows
+    /// before `start` or deleted are filtered out. `column_of` returns the
+    /// column index of field id.
+    ///
+    /// Returns `None` if all rows are selected.
+    pub(crate) fn row_selection(
+        &mut self,
+        batch: &RecordBatch,
+        ranges: &[Range<u64>],
+        start: u64,
+        column_of: impl Fn(i32) -> Option<usize>,
+    ) -> Result<Option<BooleanArray>> {
+        let position_selection = row_selection(ranges, start, &self.position_deletes);
+        let equality_selection = self.equality_deletes.row_selection(batch, column_of)?;
+        match (position_selection, equality_selection) {
+            (Some(l), Some(r)) => and(&l, &r)
+                .map(Some)
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e))),
+            (l, r) => Ok(l.or(r)),
+        }
+    }
+}
+
+/// Returns the index of root field with `field_id` in arrow schema read
+/// from parquet file.
+pub(crate) fn field_id_index(schema: &ArrowSchema, field_id: i32) -> Option<usize> {
+    let field_id = field_id.to_string();
+    schema
+        .fields()
+        .iter()
+        .position(|f| f.metadata().get(PARQUET_FIELD_ID_META_KEY) == Some(&field_id))
+}
+
+/// Returns whether the field id belongs to a field nested in the columns of
+/// `fields`, like a struct field.
+fn is_nested_field(fields: &Fields, field_id: i32) -> bool {
+    fn contains(fields: &Fields, field_id: &str) -> bool {
+        fields.iter().any(|f| {
+            f.metadata()
+                .get(PARQUET_FIELD_ID_META_KEY)
+                .map(String::as_str)
+                == Some(field_id)
+                || contains(&children(f.data_type()), field_id)
+        })
+    }
+    fn children(data_type: &DataType) -> Fields {
+        match data_type {
+            DataType::Struct(fields) => fields.clone(),
+            DataType::List(field) | DataType::LargeList(field) | DataType::Map(field, _) => {
+                Fields::from(vec![field.clone()])
+            }
+            _ => Fields::empty(),
+        }
+    }
+
+    let field_id = field_id.to_string();
+    fields
+        .iter()
+        .any(|f| contains(&children(f.data_type()), &field_id))
+}
+
+/// Tracks the positions in data file of rows produced by the parquet
+/// reader.
+pub(crate) struct RowPositions {
+    /// `(first row position, row count)` of the row groups left to read, in
+    /// reading order.
+    row_groups: VecDeque<(u64, u64)>,
+}
+
+impl RowPositions {
+    /// Create a tracker for the row groups to read.
+    pub(crate) fn new(row_groups: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        Self {
+            row_groups: row_groups.into_iter().collect(),
+        }
+    }
+
+    /// Returns the positions of the next `num_rows` rows.
+    pub(crate) fn next_rows(&mut self, mut num_rows: usize) -> Result<Vec<Range<u64>>> {
+        let mut ranges = vec![];
+        while num_rows > 0 {
+            let (start, len) = self.row_groups.front_mut().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Unexpected,
+                    "Parquet reader returns more rows than the row groups contain",
+                )
+            })?;
+            let n = (*len).min(num_rows as u64);
+            ranges.push(*start..*start + n);
+            *start += n;
+            *len -= n;
+            if *len == 0 {
+                self.row_groups.pop_front();
+            }
+            num_rows -= n as usize;
+        }
+        Ok(ranges)
+    }
+}
+
+/// Build the selection of rows at positions `ranges`: rows before `start`
+/// or deleted are filtered out.
+///
+/// Returns `None` if all rows are selected.
+pub(crate) fn row_selection(
+    ranges: &[Range<u64>],
+    start: u64,
+    deletes: &PositionDeletes,
+) -> Option<BooleanArray> {
+    if ranges
+        .iter()
+        .all(|r| r.start >= start && !deletes.intersects(r))
+    {
+        return None;
+    }
+
+    Some(BooleanArray::from(
+        ranges
+            .iter()
+            .flat_map(|r| r.clone())
+            .map(|pos| pos >= start && !deletes.contains(pos))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Int64Array, StringArray};
+    use opendal::services::Memory;
+    use parquet::arrow::AsyncArrowWriter;
+
+    use super::*;
+    use crate::types::DataFileFormat;
+
+    #[tokio::test]
+    async fn test_load_position_deletes() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let file_path = Arc::new(StringArray::from(vec![
+            "/t/data/a.parquet",
+            "/t/data/b.parquet",
+            "/t/data/a.parquet",
+            "/t/data/a.parquet",
+        ])) as ArrayRef;
+        let pos = Arc::new(Int64Array::from(vec![7, 1, 3, 7])) as ArrayRef;
+        let batch = RecordBatch::try_from_iter([("file_path", file_path), ("pos", pos)]).unwrap();
+
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, batch.schema(), 0, None)?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("/data/delete.parquet", buf).await?;
+
+        let delete_file = DataFile::new(
+            DataContentType::PostionDeletes,
+            "/t/data/delete.parquet",
+            DataFileFormat::Parquet,
+            4,
+            0,
+        );
+        let deletes = PositionDeletes::load(&op, "/t", "/t/data/a.parquet", &[delete_file]).await?;
+
+        assert_eq!(deletes.positions, vec![3, 7]);
+        assert!(deletes.contains(3));
+        assert!(!deletes.contains(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_equality_deletes() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let id_field = arrow_schema::Field::new("id", DataType::Int64, true).with_metadata(
+            HashMap::from([(PARQUET_FIELD_ID_META_KEY.to_string(), "1".to_string())]),
+        );
+        let schema = Arc::new(ArrowSchema::new(vec![id_field]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![Some(2), None])) as ArrayRef],
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, schema, 0, None)?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("/data/eq-delete.parquet", buf).await?;
+
+        let mut delete_file = DataFile::new(
+            DataContentType::EqualityDeletes,
+            "/t/data/eq-delete.parquet",
+            DataFileFormat::Parquet,
+            2,
+            0,
+        );
+        delete_file.equality_ids = Some(vec![1]);
+        let mut deletes = EqualityDeletes::load(&op, "/t", &[delete_file]).await?;
+        assert_eq!(deletes.field_ids().collect::<Vec<_>>(), vec![1]);
+
+        // Data file stores the key as int32 before type promotion.
+        let data = RecordBatch::try_from_iter([
+            (
+                "v",
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d"])) as ArrayRef,
+            ),
+            (
+                "id",
+                Arc::new(arrow_array::Int32Array::from(vec![
+                    Some(1),
+                    Some(2),
+                    None,
+                    Some(3),
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            deletes.row_selection(&data, |id| (id == 1).then_some(1))?,
+            Some(BooleanArray::from(vec![true, false, false, true])),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nested_equality_deletes() -> Result<()> {
+        let op = Operator::new(Memory::default())?.finish();
+
+        let field = |name: &str, data_type: DataType, id: &str| {
+            arrow_schema::Field::new(name, data_type, true).with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        let city = field("city", DataType::Utf8, "3");
+        let address = field("address", DataType::Struct(vec![city.clone()].into()), "2");
+        let schema = Arc::new(ArrowSchema::new(vec![address]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(arrow_array::StructArray::from(vec![(
+                Arc::new(city),
+                Arc::new(StringArray::from(vec!["a"])) as ArrayRef,
+            )])) as ArrayRef],
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        let mut w = AsyncArrowWriter::try_new(&mut buf, schema, 0, None)?;
+        w.write(&batch).await?;
+        w.close().await?;
+        op.write("/data/eq-delete.parquet", buf).await?;
+
+        let mut delete_file = DataFile::new(
+            DataContentType::EqualityDeletes,
+            "/t/data/eq-delete.parquet",
+            DataFileFormat::Parquet,
+            1,
+            0,
+        );
+        delete_file.equality_ids = Some(vec![3]);
+        let err = EqualityDeletes::load(&op, "/t", &[delete_file.clone()])
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::IcebergFeatureUnsupported);
+
+        // Missing fields are still invalid.
+        delete_file.equality_ids = Some(vec![4]);
+        let err = EqualityDeletes::load(&op, "/t", &[delete_file])
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::IcebergDataInvalid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_positions() {
+        let mut positions = RowPositions::new([(0, 3), (10, 2)]);
+        assert_eq!(positions.next_rows(2).unwrap(), vec![0..2]);
+        assert_eq!(positions.next_rows(2).unwrap(), vec![2..3, 10..11]);
+        assert_eq!(positions.next_rows(1).unwrap(), vec![11..12]);
+        assert!(positions.next_rows(1).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_row_selection() {
+        let deletes = PositionDeletes {
+            positions: vec![1, 11],
+        };
+        assert!(row_selection(&[2..5], 0, &deletes).is_none());
+        assert_eq!(
+            row_selection(&[0..3, 10..12], 1, &deletes),
+            Some(BooleanArray::from(vec![false, false, true, true, false])),
+        );
+    }
+
+    #[test]
+    fn test_delete_file_index() {
+        let delete_entry = |content, path: &str, sequence_number| DeleteFileEntry {
+            delete_file: DataFile::new(content, path, DataFileFormat::Parquet, 1, 1),
+            sequence_number,
+            spec_id: 0,
+        };
+        let index = DeleteFileIndex::new(vec![
+            delete_entry(DataContentType::PostionDeletes, "pos-1", 1),
+            delete_entry(DataContentType::PostionDeletes, "pos-2", 2),
+            delete_entry(DataContentType::EqualityDeletes, "eq-1", 1),
+            delete_entry(DataContentType::EqualityDeletes, "eq-2", 2),
+        ]);
+        let data_file = DataFile::new(DataContentType::Data, "data", DataFileFormat::Parquet, 1, 1);
+        let paths = |entries: Vec<DeleteFileEntry>| {
+            entries
+                .into_iter()
+                .map(|e| e.delete_file.file_path)
+                .collect::<Vec<_>>()
+        };
+
+        let (position_deletes, equality_deletes) = index.for_data_file(&data_file, 1, 0);
+        assert_eq!(paths(position_deletes), vec!["pos-1", "pos-2"]);
+        assert_eq!(paths(equality_deletes), vec!["eq-2"]);
+
+        let (position_deletes, equality_deletes) = index.for_data_file(&data_file, 2, 0);
+        assert_eq!(paths(position_deletes), vec!["pos-2"]);
+        assert!(equality_deletes.is_empty());
+
+        // Position deletes of other partition specs don't apply.
+        let (position_deletes, equality_deletes) = index.for_data_file(&data_file, 0, 1);
+        assert!(position_deletes.is_empty());
+        assert_eq!(paths(equality_deletes), vec!["eq-1", "eq-2"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Point lookups of rows by identifier fields.
+
+use arrow_schema::DataType;
+use arrow_select::concat::concat;
+use arrow_select::filter::filter_record_batch;
+use futures::{StreamExt, TryStreamExt};
+use itertools::Itertools;
+
+use super::{field_id_index, repeat_value, EqualityDeleteSet, Predicate, RecordBatchStream};
+use crate::types::PrimitiveValue;
+use crate::{Error, ErrorKind, Result, Table};
+
+impl Table {
+    /// Fetch rows of the current snapshot whose identifier fields equal one
+    /// of `keys`. Each key has the values of identifier fields of the
+    /// current schema, in the order of `identifier_field_ids`.
+    ///
+    /// Data files are pruned by partitions and metrics, and row groups are
+    /// pruned by bloom filters, before rows are matched with the keys.
+    pub async fn lookup(&self, keys: &[Vec<PrimitiveValue>]) -> Result<RecordBatchStream> {
+        let metadata = self.current_table_metadata();
+        let schema = metadata.current_schema()?;
+        let field_ids = schema.identifier_field_ids.clone().unwrap_or_default();
+        if field_ids.is_empty() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Table has no identifier fields to lookup",
+            ));
+        }
+        if let Some(key) = keys.iter().find(|key| key.len() != field_ids.len()) {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Key {:?} doesn't match identifier fields {:?}",
+                    key, field_ids
+                ),
+            ));
+        }
+
+        let data_types = field_ids
+            .iter()
+            .map(|field_id| {
+                let field = schema
+                    .fields()
+                    .iter()
+                    .find(|f| f.id == *field_id)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergFeatureUnsupported,
+                            format!(
+                                "Lookup by nested identifier field {field_id} is not supported"
+                            ),
+                        )
+                    })?;
+                DataType::try_from(field.field_type.clone())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() || metadata.current_snapshot()?.is_none() {
+            return Ok(futures::stream::empty().boxed());
+        }
+
+        let mut key_set = EqualityDeleteSet::try_new(field_ids.clone(), data_types.clone())?;
+        let key_columns = data_types
+            .iter()
+            .enumerate()
+            .map(|(idx, data_type)| {
+                let values = keys
+                    .iter()
+                    .map(|key| repeat_value(&key[idx], data_type, 1))
+                    .collect::<Result<Vec<_>>>()?;
+                concat(&values.iter().map(|v| v.as_ref()).collect::<Vec<_>>())
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        key_set.insert(&key_columns)?;
+
+        // Values of each identifier field are checked separately, so that
+        // the filter stays shallow for many keys. Rows are matched with the
+        // whole keys later.
+        let filter = field_ids
+            .iter()
+            .enumerate()
+            .map(|(idx, field_id)| {
+                let values = keys.iter().map(|key| key[idx].clone()).unique().collect();
+                Predicate::In(*field_id, values)
+            })
+            .fold(Predicate::AlwaysTrue, Predicate::and);
+        let scan = self
+            .new_scan_builder()
+            .with_filter(filter)
+            .build()
+            .map_err(|e| Error::new(ErrorKind::Unexpected, format!("{}", e)))?;
+
+        let stream = scan
+            .scan(self)
+            .await?
+            .and_then(|file_scan| file_scan.scan())
+            .try_flatten()
+            .map(move |batch: Result<_>| -> Result<_> {
+                let batch = batch?;
+                let schema = batch.schema();
+                let selection = key_set.contains(&batch, |id| field_id_index(&schema, id))?;
+                filter_record_batch(&batch, &selection)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+            })
+            .try_filter(|batch| futures::future::ready(batch.num_rows() > 0));
+        Ok(stream.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+
+    use super::*;
+    use crate::test_utils;
+
+    /// The table with `id` as identifier field.
+    fn with_identifier(table: &Table) -> Result<Table> {
+        let mut metadata = table.current_table_metadata().clone();
+        metadata.schemas[0].identifier_field_ids = Some(vec![1]);
+        Table::builder_from_catalog(
+            table.operator(),
+            table.catalog(),
+            metadata,
+            table.current_metadata_location().to_string(),
+            table.table_name().clone(),
+        )
+        .build()
+    }
+
+    async fn lookup_ids(table: &Table, keys: &[i64]) -> Result<Vec<i64>> {
+        let keys = keys
+            .iter()
+            .map(|key| vec![PrimitiveValue::Long(*key)])
+            .collect::<Vec<_>>();
+        let batches = table.lookup(&keys).await?.try_collect::<Vec<_>>().await?;
+        let mut ids = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("id")
+                    .unwrap()
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        Ok(ids)
+    }
+
+    #[tokio::test]
+    async fn test_lookup() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let err = lookup_ids(&table, &[1]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergDataInvalid);
+
+        // Empty table.
+        assert!(lookup_ids(&with_identifier(&table)?, &[1])
+            .await?
+            .is_empty());
+
+        let a = test_utils::write_data_file(&table, "a", &[1, 2, 3]).await?;
+        let b = test_utils::write_data_file(&table, "b", &[4, 5, 6]).await?;
+        let deletes = test_utils::write_equality_delete_file(&table, "d", &[5]).await?;
+        test_utils::commit(&mut table, vec![a, b], vec![]).await?;
+        test_utils::commit(&mut table, vec![], vec![deletes]).await?;
+        let table = with_identifier(&table)?;
+
+        // Deleted and missing keys are not found, duplicated keys match once.
+        assert_eq!(lookup_ids(&table, &[2, 5, 6, 6, 9]).await?, vec![2, 6]);
+        assert!(lookup_ids(&table, &[]).await?.is_empty());
+
+        let err = table
+            .lookup(&[vec![PrimitiveValue::Long(1), PrimitiveValue::Long(2)]])
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::IcebergDataInvalid);
+        Ok(())
+    }
+}
@@ -0,0 +1,1614 @@
+//! Metadata tables expose snapshots, manifests and files of table as arrow
+//! record batches, with the schemas of the metadata tables of Spark.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::{
+    new_empty_array, new_null_array, ArrayRef, BooleanArray, Int32Array, Int64Array,
+    LargeBinaryArray, ListArray, MapArray, RecordBatch, StringArray, StructArray,
+    TimestampMicrosecondArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Fields, Schema as ArrowSchema, SchemaRef};
+use arrow_select::concat::concat;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::types::{
+    Any, DataContentType, DataFile, Field, FieldRef, List, ManifestContentType, ManifestEntry,
+    ManifestListEntry, Map, PartitionSpec, Primitive, PrimitiveValue, Schema,
+    SnapshotReferenceType, Struct, StructValue, TableMetadata,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+use super::repeat_value;
+
+/// Max number of rows in a batch of metadata tables listing files.
+const METADATA_TABLE_BATCH_SIZE: usize = 1024;
+
+/// Stream of record batches of a metadata table.
+pub type MetadataTableStream<'a> = BoxStream<'a, Result<RecordBatch>>;
+
+/// Metadata tables of table, named as the metadata tables of Spark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataTableType {
+    /// Valid snapshots of table.
+    Snapshots,
+    /// Changes of the current snapshot of table.
+    History,
+    /// Data files and delete files of the current snapshot.
+    Files,
+    /// Data files of the current snapshot.
+    DataFiles,
+    /// Delete files of the current snapshot.
+    DeleteFiles,
+    /// Manifests of the current snapshot.
+    Manifests,
+    /// Manifests of all valid snapshots.
+    AllManifests,
+    /// Partitions of the current snapshot, with counts of files and records.
+    Partitions,
+    /// Manifest entries of the current snapshot, including deleted entries.
+    Entries,
+    /// Branches and tags of table.
+    Refs,
+}
+
+impl MetadataTableType {
+    /// All metadata tables.
+    pub const ALL: [MetadataTableType; 10] = [
+        MetadataTableType::Snapshots,
+        MetadataTableType::History,
+        MetadataTableType::Files,
+        MetadataTableType::DataFiles,
+        MetadataTableType::DeleteFiles,
+        MetadataTableType::Manifests,
+        MetadataTableType::AllManifests,
+        MetadataTableType::Partitions,
+        MetadataTableType::Entries,
+        MetadataTableType::Refs,
+    ];
+
+    /// Name of the metadata table, like `snapshots`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetadataTableType::Snapshots => "snapshots",
+            MetadataTableType::History => "history",
+            MetadataTableType::Files => "files",
+            MetadataTableType::DataFiles => "data_files",
+            MetadataTableType::DeleteFiles => "delete_files",
+            MetadataTableType::Manifests => "manifests",
+            MetadataTableType::AllManifests => "all_manifests",
+            MetadataTableType::Partitions => "partitions",
+            MetadataTableType::Entries => "entries",
+            MetadataTableType::Refs => "refs",
+        }
+    }
+}
+
+impl FromStr for MetadataTableType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid metadata table type: {s}"),
+                )
+            })
+    }
+}
+
+impl Table {
+    /// Arrow schema of the metadata table. Columns of partition values have
+    /// the fields of all partition specs, and they are omitted if table is
+    /// unpartitioned.
+    pub fn metadata_table_schema(&self, typ: MetadataTableType) -> Result<SchemaRef> {
+        let partition_type = unified_partition_type(self.current_table_metadata())?;
+        arrow_schema(&metadata_table_schema(typ, &partition_type))
+    }
+
+    /// Read the metadata table of the current table metadata.
+    ///
+    /// Tables of files and entries are read from manifests of the current
+    /// snapshot, in batches of at most 1024 rows. Other tables are returned
+    /// in one batch.
+    pub async fn metadata_table(&self, typ: MetadataTableType) -> Result<MetadataTableStream<'_>> {
+        let metadata = self.current_table_metadata();
+        let partition_type = Arc::new(unified_partition_type(metadata)?);
+        let schema = arrow_schema(&metadata_table_schema(typ, &partition_type))?;
+
+        let batch = match typ {
+            MetadataTableType::Snapshots => snapshots_batch(metadata, schema)?,
+            MetadataTableType::History => history_batch(metadata, schema)?,
+            MetadataTableType::Refs => refs_batch(metadata, schema)?,
+            MetadataTableType::Manifests => {
+                let manifests = match metadata.current_snapshot()? {
+                    Some(snapshot) => self
+                        .manifest_list_of_snapshot(snapshot)
+                        .await?
+                        .entries
+                        .iter()
+                        .map(|m| (m.clone(), None))
+                        .collect(),
+                    None => vec![],
+                };
+                manifests_batch(metadata, schema, &manifests)?
+            }
+            MetadataTableType::AllManifests => {
+                let mut manifests = vec![];
+                for snapshot in metadata.snapshots.iter().flatten() {
+                    let manifest_list = self.manifest_list_of_snapshot(snapshot).await?;
+                    manifests.extend(
+                        manifest_list
+                            .entries
+                            .iter()
+                            .map(|m| (m.clone(), Some(snapshot.snapshot_id))),
+                    );
+                }
+                manifests_batch(metadata, schema, &manifests)?
+            }
+            MetadataTableType::Partitions => {
+                let entries = match metadata.current_snapshot()? {
+                    Some(snapshot) => {
+                        self.manifest_entries_of_snapshot(snapshot, |_| true, |e| e.is_alive())
+                            .await?
+                            .try_collect()
+                            .await?
+                    }
+                    None => vec![],
+                };
+                partitions_batch(metadata, schema, &partition_type, &entries)?
+            }
+            MetadataTableType::Files
+            | MetadataTableType::DataFiles
+            | MetadataTableType::DeleteFiles
+            | MetadataTableType::Entries => {
+                let Some(snapshot) = metadata.current_snapshot()? else {
+                    return Ok(futures::stream::empty().boxed());
+                };
+                let manifest_content = match typ {
+                    MetadataTableType::DataFiles => Some(ManifestContentType::Data),
+                    MetadataTableType::DeleteFiles => Some(ManifestContentType::Deletes),
+                    _ => None,
+                };
+                let alive_only = typ != MetadataTableType::Entries;
+                let entries = self
+                    .manifest_entries_of_snapshot(
+                        snapshot,
+                        move |m| manifest_content.is_none_or(|c| m.content == c),
+                        move |e| !alive_only || e.is_alive(),
+                    )
+                    .await?;
+                let stream = entries
+                    .try_chunks(METADATA_TABLE_BATCH_SIZE)
+                    .map(move |entries| {
+                        let entries = entries.map_err(|e| e.1)?;
+                        match typ {
+                            MetadataTableType::Entries => {
+                                entries_batch(schema.clone(), &partition_type, &entries)
+                            }
+                            _ => {
+                                let columns = data_file_columns(
+                                    schema.fields(),
+                                    &partition_type,
+                                    entries.iter().map(|(spec_id, e)| (*spec_id, &e.data_file)),
+                                )?;
+                                record_batch(schema.clone(), columns)
+                            }
+                        }
+                    });
+                return Ok(stream.boxed());
+            }
+        };
+
+        Ok(futures::stream::once(futures::future::ready(Ok(batch))).boxed())
+    }
+}
+
+/// Partition type with fields of all partition specs, which are optional
+/// since files of other specs don't have them.
+pub(crate) fn unified_partition_type(metadata: &TableMetadata) -> Result<Struct> {
+    let mut fields: Vec<FieldRef> = vec![];
+    for spec in &metadata.partition_specs {
+        for field in spec_partition_type(metadata, spec, None)?.fields() {
+            if fields.iter().all(|f| f.id != field.id) {
+                fields.push(Arc::new(Field::optional(
+                    field.id,
+                    field.name.clone(),
+                    field.field_type.clone(),
+                )));
+            }
+        }
+    }
+    Ok(Struct::new(fields))
+}
+
+/// Partition type of the spec. Source fields of the spec may be dropped or
+/// promoted by later schemas, so it's bound to the schema of the snapshot
+/// writing files of the spec if it's known, then to the current schema, then
+/// to the latest schema having the source fields.
+fn spec_partition_type(
+    metadata: &TableMetadata,
+    spec: &PartitionSpec,
+    snapshot_id: Option<i64>,
+) -> Result<Struct> {
+    let snapshot_schema = snapshot_id
+        .and_then(|id| metadata.snapshot(id))
+        .and_then(|snapshot| snapshot.schema_id)
+        .and_then(|id| metadata.schema(id as i32));
+    let schemas = snapshot_schema
+        .into_iter()
+        .chain([metadata.current_schema()?])
+        .chain(metadata.schemas.iter().rev());
+    let mut error = None;
+    for schema in schemas {
+        match spec.partition_type(schema) {
+            Ok(partition_type) => return Ok(partition_type),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    Err(error.unwrap())
+}
+
+fn map_of(key_id: i32, key_type: Primitive, value_id: i32, value_type: Primitive) -> Any {
+    Any::Map(Map {
+        key_id,
+        key_type: Box::new(Any::Primitive(key_type)),
+        value_id,
+        value_required: true,
+        value_type: Box::new(Any::Primitive(value_type)),
+    })
+}
+
+fn list_of(element_id: i32, element_type: Any) -> Any {
+    Any::List(List {
+        element_id,
+        element_required: true,
+        element_type: Box::new(element_type),
+    })
+}
+
+/// Fields of data files, the `partition` field is omitted if the partition
+/// type is empty.
+fn data_file_fields(partition_type: &Struct) -> Vec<Field> {
+    let mut fields = vec![
+        Field::optional(134, "content", Any::Primitive(Primitive::Int)),
+        Field::required(100, "file_path", Any::Primitive(Primitive::String)),
+        Field::required(101, "file_format", Any::Primitive(Primitive::String)),
+        Field::optional(141, "spec_id", Any::Primitive(Primitive::Int)),
+    ];
+    if !partition_type.fields().is_empty() {
+        fields.push(Field::required(
+            102,
+            "partition",
+            Any::Struct(Arc::new(partition_type.clone())),
+        ));
+    }
+    fields.extend([
+        Field::required(103, "record_count", Any::Primitive(Primitive::Long)),
+        Field::required(104, "file_size_in_bytes", Any::Primitive(Primitive::Long)),
+        Field::optional(
+            108,
+            "column_sizes",
+            map_of(117, Primitive::Int, 118, Primitive::Long),
+        ),
+        Field::optional(
+            109,
+            "value_counts",
+            map_of(119, Primitive::Int, 120, Primitive::Long),
+        ),
+        Field::optional(
+            110,
+            "null_value_counts",
+            map_of(121, Primitive::Int, 122, Primitive::Long),
+        ),
+        Field::optional(
+            137,
+            "nan_value_counts",
+            map_of(138, Primitive::Int, 139, Primitive::Long),
+        ),
+        Field::optional(
+            125,
+            "lower_bounds",
+            map_of(126, Primitive::Int, 127, Primitive::Binary),
+        ),
+        Field::optional(
+            128,
+            "upper_bounds",
+            map_of(129, Primitive::Int, 130, Primitive::Binary),
+        ),
+        Field::optional(131, "key_metadata", Any::Primitive(Primitive::Binary)),
+        Field::optional(
+            132,
+            "split_offsets",
+            list_of(133, Any::Primitive(Primitive::Long)),
+        ),
+        Field::optional(
+            135,
+            "equality_ids",
+            list_of(136, Any::Primitive(Primitive::Int)),
+        ),
+        Field::optional(140, "sort_order_id", Any::Primitive(Primitive::Int)),
+    ]);
+    fields
+}
+
+/// Iceberg schema of the metadata table, field ids are the same as Spark.
+fn metadata_table_schema(typ: MetadataTableType, partition_type: &Struct) -> Schema {
+    let partitioned = !partition_type.fields().is_empty();
+    let manifest_fields = || {
+        vec![
+            Field::required(14, "content", Any::Primitive(Primitive::Int)),
+            Field::required(1, "path", Any::Primitive(Primitive::String)),
+            Field::required(2, "length", Any::Primitive(Primitive::Long)),
+            Field::required(3, "partition_spec_id", Any::Primitive(Primitive::Int)),
+            Field::required(4, "added_snapshot_id", Any::Primitive(Primitive::Long)),
+            Field::required(5, "added_data_files_count", Any::Primitive(Primitive::Int)),
+            Field::required(
+                6,
+                "existing_data_files_count",
+                Any::Primitive(Primitive::Int),
+            ),
+            Field::required(
+                7,
+                "deleted_data_files_count",
+                Any::Primitive(Primitive::Int),
+            ),
+            Field::required(
+                15,
+                "added_delete_files_count",
+                Any::Primitive(Primitive::Int),
+            ),
+            Field::required(
+                16,
+                "existing_delete_files_count",
+                Any::Primitive(Primitive::Int),
+            ),
+            Field::required(
+                17,
+                "deleted_delete_files_count",
+                Any::Primitive(Primitive::Int),
+            ),
+            Field::required(
+                8,
+                "partition_summaries",
+                list_of(
+                    9,
+                    Any::Struct(Arc::new(Struct::new(vec![
+                        Field::required(10, "contains_null", Any::Primitive(Primitive::Boolean))
+                            .into(),
+                        Field::optional(11, "contains_nan", Any::Primitive(Primitive::Boolean))
+                            .into(),
+                        Field::optional(12, "lower_bound", Any::Primitive(Primitive::String))
+                            .into(),
+                        Field::optional(13, "upper_bound", Any::Primitive(Primitive::String))
+                            .into(),
+                    ]))),
+                ),
+            ),
+        ]
+    };
+
+    let fields = match typ {
+        MetadataTableType::Snapshots => vec![
+            Field::required(1, "committed_at", Any::Primitive(Primitive::Timestampz)),
+            Field::required(2, "snapshot_id", Any::Primitive(Primitive::Long)),
+            Field::optional(3, "parent_id", Any::Primitive(Primitive::Long)),
+            Field::optional(4, "operation", Any::Primitive(Primitive::String)),
+            Field::optional(5, "manifest_list", Any::Primitive(Primitive::String)),
+            Field::optional(
+                6,
+                "summary",
+                map_of(7, Primitive::String, 8, Primitive::String),
+            ),
+        ],
+        MetadataTableType::History => vec![
+            Field::required(1, "made_current_at", Any::Primitive(Primitive::Timestampz)),
+            Field::required(2, "snapshot_id", Any::Primitive(Primitive::Long)),
+            Field::optional(3, "parent_id", Any::Primitive(Primitive::Long)),
+            Field::required(4, "is_current_ancestor", Any::Primitive(Primitive::Boolean)),
+        ],
+        MetadataTableType::Files
+        | MetadataTableType::DataFiles
+        | MetadataTableType::DeleteFiles => data_file_fields(partition_type),
+        MetadataTableType::Manifests => manifest_fields(),
+        MetadataTableType::AllManifests => {
+            let mut fields = manifest_fields();
+            fields.push(Field::optional(
+                18,
+                "reference_snapshot_id",
+                Any::Primitive(Primitive::Long),
+            ));
+            fields
+        }
+        MetadataTableType::Partitions => {
+            let mut fields = vec![];
+            if partitioned {
+                fields.extend([
+                    Field::required(
+                        1,
+                        "partition",
+                        Any::Struct(Arc::new(partition_type.clone())),
+                    ),
+                    Field::required(4, "spec_id", Any::Primitive(Primitive::Int)),
+                ]);
+            }
+            fields.extend([
+                Field::required(2, "record_count", Any::Primitive(Primitive::Long)),
+                Field::required(3, "file_count", Any::Primitive(Primitive::Int)),
+                Field::required(
+                    11,
+                    "total_data_file_size_in_bytes",
+                    Any::Primitive(Primitive::Long),
+                ),
+                Field::required(
+                    5,
+                    "position_delete_record_count",
+                    Any::Primitive(Primitive::Long),
+                ),
+                Field::required(
+                    6,
+                    "position_delete_file_count",
+                    Any::Primitive(Primitive::Int),
+                ),
+                Field::required(
+                    7,
+                    "equality_delete_record_count",
+                    Any::Primitive(Primitive::Long),
+                ),
+                Field::required(
+                    8,
+                    "equality_delete_file_count",
+                    Any::Primitive(Primitive::Int),
+                ),
+                Field::optional(9, "last_updated_at", Any::Primitive(Primitive::Timestampz)),
+                Field::optional(
+                    10,
+                    "last_updated_snapshot_id",
+                    Any::Primitive(Primitive::Long),
+                ),
+            ]);
+            fields
+        }
+        MetadataTableType::Entries => vec![
+            Field::required(0, "status", Any::Primitive(Primitive::Int)),
+            Field::optional(1, "snapshot_id", Any::Primitive(Primitive::Long)),
+            Field::optional(3, "sequence_number", Any::Primitive(Primitive::Long)),
+            Field::optional(4, "file_sequence_number", Any::Primitive(Primitive::Long)),
+            Field::required(
+                2,
+                "data_file",
+                Any::Struct(Arc::new(Struct::new(
+                    data_file_fields(partition_type)
+                        .into_iter()
+                        .map(Arc::new)
+                        .collect(),
+                ))),
+            ),
+        ],
+        MetadataTableType::Refs => vec![
+            Field::required(1, "name", Any::Primitive(Primitive::String)),
+            Field::required(2, "type", Any::Primitive(Primitive::String)),
+            Field::required(3, "snapshot_id", Any::Primitive(Primitive::Long)),
+            Field::optional(
+                4,
+                "max_reference_age_in_ms",
+                Any::Primitive(Primitive::Long),
+            ),
+            Field::optional(5, "min_snapshots_to_keep", Any::Primitive(Primitive::Int)),
+            Field::optional(6, "max_snapshot_age_in_ms", Any::Primitive(Primitive::Long)),
+        ],
+    };
+
+    Schema::new(
+        0,
+        None,
+        Struct::new(fields.into_iter().map(Arc::new).collect()),
+    )
+}
+
+fn arrow_schema(schema: &Schema) -> Result<SchemaRef> {
+    Ok(Arc::new(ArrowSchema::try_from(schema.clone())?))
+}
+
+fn record_batch(schema: SchemaRef, columns: Vec<ArrayRef>) -> Result<RecordBatch> {
+    RecordBatch::try_new(schema, columns)
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+fn snapshots_batch(metadata: &TableMetadata, schema: SchemaRef) -> Result<RecordBatch> {
+    let snapshots = metadata.snapshots.as_deref().unwrap_or_default();
+    let summaries = snapshots
+        .iter()
+        .map(|s| {
+            // Operation is a column, it's not a part of summary in Spark.
+            let mut summary = s
+                .summary
+                .iter()
+                .filter(|(k, _)| k.as_str() != "operation")
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect::<Vec<_>>();
+            summary.sort();
+            Some(summary)
+        })
+        .collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        timestampz_array(snapshots.iter().map(|s| Some(s.timestamp_ms * 1000))),
+        Arc::new(Int64Array::from_iter_values(
+            snapshots.iter().map(|s| s.snapshot_id),
+        )),
+        Arc::new(Int64Array::from_iter(
+            snapshots.iter().map(|s| s.parent_snapshot_id),
+        )),
+        Arc::new(StringArray::from_iter(
+            snapshots.iter().map(|s| s.operation()),
+        )),
+        Arc::new(StringArray::from_iter(snapshots.iter().map(|s| {
+            (!s.has_inline_manifests()).then_some(s.manifest_list.as_str())
+        }))),
+        map_array(
+            schema.field(5).data_type(),
+            summaries,
+            |keys| Arc::new(StringArray::from(keys)),
+            |values| Arc::new(StringArray::from(values)),
+        )?,
+    ];
+    record_batch(schema, columns)
+}
+
+fn history_batch(metadata: &TableMetadata, schema: SchemaRef) -> Result<RecordBatch> {
+    let logs = metadata.snapshot_log.as_deref().unwrap_or_default();
+    let mut ancestors = HashSet::new();
+    let mut snapshot = metadata.current_snapshot()?;
+    while let Some(s) = snapshot {
+        ancestors.insert(s.snapshot_id);
+        snapshot = s.parent_snapshot_id.and_then(|id| metadata.snapshot(id));
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        timestampz_array(logs.iter().map(|l| Some(l.timestamp_ms * 1000))),
+        Arc::new(Int64Array::from_iter_values(
+            logs.iter().map(|l| l.snapshot_id),
+        )),
+        Arc::new(Int64Array::from_iter(logs.iter().map(|l| {
+            metadata
+                .snapshot(l.snapshot_id)
+                .and_then(|s| s.parent_snapshot_id)
+        }))),
+        Arc::new(BooleanArray::from_iter(
+            logs.iter()
+                .map(|l| Some(ancestors.contains(&l.snapshot_id))),
+        )),
+    ];
+    record_batch(schema, columns)
+}
+
+fn refs_batch(metadata: &TableMetadata, schema: SchemaRef) -> Result<RecordBatch> {
+    let mut refs = metadata
+        .refs
+        .iter()
+        .map(|(name, r)| (name.as_str(), r.clone()))
+        .collect::<Vec<_>>();
+    // There is always a main branch pointing to the current snapshot.
+    if let Some(snapshot) = metadata.current_snapshot()? {
+        if refs
+            .iter()
+            .all(|(name, _)| *name != crate::types::MAIN_BRANCH)
+        {
+            refs.push((
+                crate::types::MAIN_BRANCH,
+                crate::types::SnapshotReference::new(
+                    snapshot.snapshot_id,
+                    SnapshotReferenceType::Branch,
+                ),
+            ));
+        }
+    }
+    refs.sort_by(|a, b| a.0.cmp(b.0));
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(refs.iter().map(|(n, _)| *n))),
+        Arc::new(StringArray::from_iter_values(
+            refs.iter().map(|(_, r)| r.typ.to_string().to_uppercase()),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            refs.iter().map(|(_, r)| r.snapshot_id),
+        )),
+        Arc::new(Int64Array::from_iter(
+            refs.iter().map(|(_, r)| r.max_ref_age_ms),
+        )),
+        Arc::new(Int32Array::from_iter(
+            refs.iter().map(|(_, r)| r.min_snapshots_to_keep),
+        )),
+        Arc::new(Int64Array::from_iter(
+            refs.iter().map(|(_, r)| r.max_snapshot_age_ms),
+        )),
+    ];
+    record_batch(schema, columns)
+}
+
+/// Batch of manifests, each paired with the id of snapshot referencing it
+/// for `all_manifests` table.
+fn manifests_batch(
+    metadata: &TableMetadata,
+    schema: SchemaRef,
+    manifests: &[(ManifestListEntry, Option<i64>)],
+) -> Result<RecordBatch> {
+    let count_of = |content: ManifestContentType, count: fn(&ManifestListEntry) -> i32| {
+        Arc::new(Int32Array::from_iter_values(manifests.iter().map(
+            |(m, _)| {
+                if m.content == content {
+                    count(m)
+                } else {
+                    0
+                }
+            },
+        ))) as ArrayRef
+    };
+
+    let summaries = manifests
+        .iter()
+        .map(|(m, _)| partition_summaries(metadata, m).map(Some))
+        .collect::<Result<Vec<_>>>()?;
+    let summaries_type = schema.field(11).data_type();
+    let DataType::List(summary_field) = summaries_type else {
+        return Err(unexpected_type(summaries_type));
+    };
+    let DataType::Struct(summary_fields) = summary_field.data_type() else {
+        return Err(unexpected_type(summary_field.data_type()));
+    };
+    let summary_fields = summary_fields.clone();
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(Int32Array::from_iter_values(
+            manifests.iter().map(|(m, _)| m.content as i32),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            manifests.iter().map(|(m, _)| m.manifest_path.as_str()),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            manifests.iter().map(|(m, _)| m.manifest_length),
+        )),
+        Arc::new(Int32Array::from_iter_values(
+            manifests.iter().map(|(m, _)| m.partition_spec_id),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            manifests.iter().map(|(m, _)| m.added_snapshot_id),
+        )),
+        count_of(ManifestContentType::Data, |m| m.added_data_files_count),
+        count_of(ManifestContentType::Data, |m| m.existing_data_files_count),
+        count_of(ManifestContentType::Data, |m| m.deleted_data_files_count),
+        count_of(ManifestContentType::Deletes, |m| m.added_data_files_count),
+        count_of(ManifestContentType::Deletes, |m| {
+            m.existing_data_files_count
+        }),
+        count_of(ManifestContentType::Deletes, |m| m.deleted_data_files_count),
+        list_array(summaries_type, summaries, |summaries| {
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(BooleanArray::from_iter(summaries.iter().map(|s| Some(s.0)))),
+                Arc::new(BooleanArray::from_iter(summaries.iter().map(|s| s.1))),
+                Arc::new(StringArray::from_iter(
+                    summaries.iter().map(|s| s.2.clone()),
+                )),
+                Arc::new(StringArray::from_iter(
+                    summaries.iter().map(|s| s.3.clone()),
+                )),
+            ];
+            Ok(Arc::new(
+                StructArray::try_new(summary_fields, columns, None)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+            ))
+        })?,
+    ];
+    if schema.fields().len() > columns.len() {
+        columns.push(Arc::new(Int64Array::from_iter(
+            manifests.iter().map(|(_, snapshot_id)| *snapshot_id),
+        )));
+    }
+    record_batch(schema, columns)
+}
+
+/// Summaries of partition fields of the manifest: whether it contains null,
+/// whether it contains NaN, and the lower and upper bound as strings.
+#[allow(clippy::type_complexity)]
+fn partition_summaries(
+    metadata: &TableMetadata,
+    manifest: &ManifestListEntry,
+) -> Result<Vec<(bool, Option<bool>, Option<String>, Option<String>)>> {
+    let Some(summaries) = &manifest.partitions else {
+        return Ok(vec![]);
+    };
+    let spec = metadata
+        .partition_spec(manifest.partition_spec_id)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Partition spec {} not found!", manifest.partition_spec_id),
+            )
+        })?;
+    // Bounds are encoded by the partition type of the manifest, which was
+    // written with the schema of the snapshot adding it.
+    let partition_type = spec_partition_type(metadata, spec, Some(manifest.added_snapshot_id))?;
+
+    summaries
+        .iter()
+        .enumerate()
+        .map(|(idx, summary)| {
+            let field_type = partition_type.fields().get(idx).map(|f| &f.field_type);
+            let bound = |bound: &Option<Vec<u8>>| match (bound, field_type) {
+                (Some(bytes), Some(Any::Primitive(ty))) => {
+                    PrimitiveValue::try_from_bytes(ty, bytes).map(|v| Some(human_string(&v)))
+                }
+                _ => Ok(None),
+            };
+            Ok((
+                summary.contains_null,
+                summary.contains_nan,
+                bound(&summary.lower_bound)?,
+                bound(&summary.upper_bound)?,
+            ))
+        })
+        .collect()
+}
+
+/// Human readable string of the value, like Spark shows values.
+fn human_string(value: &PrimitiveValue) -> String {
+    match value {
+        PrimitiveValue::Boolean(v) => v.to_string(),
+        PrimitiveValue::Int(v) => v.to_string(),
+        PrimitiveValue::Long(v) => v.to_string(),
+        PrimitiveValue::Float(v) => v.to_string(),
+        PrimitiveValue::Double(v) => v.to_string(),
+        PrimitiveValue::Decimal(v) => v.to_string(),
+        PrimitiveValue::Date(v) => v.format("%Y-%m-%d").to_string(),
+        PrimitiveValue::Time(v) => v.format("%H:%M:%S%.f").to_string(),
+        PrimitiveValue::Timestamp(v) => v.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        PrimitiveValue::Timestampz(v) => v.format("%Y-%m-%dT%H:%M:%S%.f%:z").to_string(),
+        PrimitiveValue::String(v) => v.clone(),
+        PrimitiveValue::Uuid(v) => v.to_string(),
+        PrimitiveValue::Fixed(v) | PrimitiveValue::Binary(v) => faster_hex::hex_string(v),
+    }
+}
+
+/// Partition statistics aggregated from files of the partition.
+#[derive(Default)]
+struct PartitionStats {
+    spec_id: i32,
+    record_count: i64,
+    file_count: i32,
+    total_data_file_size_in_bytes: i64,
+    position_delete_record_count: i64,
+    position_delete_file_count: i32,
+    equality_delete_record_count: i64,
+    equality_delete_file_count: i32,
+    /// `(timestamp in ms, snapshot id)` of the latest snapshot adding files.
+    last_updated: Option<(i64, i64)>,
+}
+
+fn partitions_batch(
+    metadata: &TableMetadata,
+    schema: SchemaRef,
+    partition_type: &Struct,
+    entries: &[(i32, ManifestEntry)],
+) -> Result<RecordBatch> {
+    let mut partitions: Vec<(Vec<Option<PrimitiveValue>>, PartitionStats)> = vec![];
+    let mut index = HashMap::new();
+    for (spec_id, entry) in entries {
+        let file = &entry.data_file;
+        let key = partition_row(partition_type, &file.partition);
+        let idx = *index.entry(key.clone()).or_insert_with(|| {
+            partitions.push((key, PartitionStats::default()));
+            partitions.len() - 1
+        });
+        let stats = &mut partitions[idx].1;
+
+        let snapshot = entry.snapshot_id.and_then(|id| metadata.snapshot(id));
+        if let Some(snapshot) = snapshot {
+            if stats
+                .last_updated
+                .is_none_or(|(ts, _)| snapshot.timestamp_ms > ts)
+            {
+                stats.last_updated = Some((snapshot.timestamp_ms, snapshot.snapshot_id));
+            }
+        }
+        match file.content {
+            DataContentType::Data => {
+                stats.record_count += file.record_count;
+                stats.file_count += 1;
+                stats.total_data_file_size_in_bytes += file.file_size_in_bytes;
+                stats.spec_id = *spec_id;
+            }
+            DataContentType::PostionDeletes => {
+                stats.position_delete_record_count += file.record_count;
+                stats.position_delete_file_count += 1;
+            }
+            DataContentType::EqualityDeletes => {
+                stats.equality_delete_record_count += file.record_count;
+                stats.equality_delete_file_count += 1;
+            }
+        }
+    }
+
+    let stats = partitions.iter().map(|(_, s)| s).collect::<Vec<_>>();
+    let mut columns: Vec<ArrayRef> = vec![];
+    if !partition_type.fields().is_empty() {
+        let rows = partitions
+            .iter()
+            .map(|(p, _)| p.clone())
+            .collect::<Vec<_>>();
+        columns.push(partition_array(schema.field(0).data_type(), &rows)?);
+        columns.push(Arc::new(Int32Array::from_iter_values(
+            stats.iter().map(|s| s.spec_id),
+        )));
+    }
+    columns.extend([
+        Arc::new(Int64Array::from_iter_values(
+            stats.iter().map(|s| s.record_count),
+        )) as ArrayRef,
+        Arc::new(Int32Array::from_iter_values(
+            stats.iter().map(|s| s.file_count),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            stats.iter().map(|s| s.total_data_file_size_in_bytes),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            stats.iter().map(|s| s.position_delete_record_count),
+        )),
+        Arc::new(Int32Array::from_iter_values(
+            stats.iter().map(|s| s.position_delete_file_count),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            stats.iter().map(|s| s.equality_delete_record_count),
+        )),
+        Arc::new(Int32Array::from_iter_values(
+            stats.iter().map(|s| s.equality_delete_file_count),
+        )),
+        timestampz_array(
+            stats
+                .iter()
+                .map(|s| s.last_updated.map(|(ts, _)| ts * 1000)),
+        ),
+        Arc::new(Int64Array::from_iter(
+            stats.iter().map(|s| s.last_updated.map(|(_, id)| id)),
+        )),
+    ]);
+    record_batch(schema, columns)
+}
+
+fn entries_batch(
+    schema: SchemaRef,
+    partition_type: &Struct,
+    entries: &[(i32, ManifestEntry)],
+) -> Result<RecordBatch> {
+    let data_file_type = schema.field(4).data_type();
+    let DataType::Struct(data_file_fields) = data_file_type else {
+        return Err(unexpected_type(data_file_type));
+    };
+    let data_files = data_file_columns(
+        data_file_fields,
+        partition_type,
+        entries.iter().map(|(spec_id, e)| (*spec_id, &e.data_file)),
+    )?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int32Array::from_iter_values(
+            entries.iter().map(|(_, e)| e.status as i32),
+        )),
+        Arc::new(Int64Array::from_iter(
+            entries.iter().map(|(_, e)| e.snapshot_id),
+        )),
+        Arc::new(Int64Array::from_iter(
+            entries.iter().map(|(_, e)| e.sequence_number),
+        )),
+        Arc::new(Int64Array::from_iter(
+            entries.iter().map(|(_, e)| e.file_sequence_number),
+        )),
+        Arc::new(
+            StructArray::try_new(data_file_fields.clone(), data_files, None)
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+        ),
+    ];
+    record_batch(schema, columns)
+}
+
+/// Lower or upper bounds of columns in a data file.
+type FileBounds = Option<HashMap<i32, Vec<u8>>>;
+
+/// Columns of data files with `fields`, files are paired with their
+/// partition spec ids.
+fn data_file_columns<'a>(
+    fields: &Fields,
+    partition_type: &Struct,
+    files: impl Iterator<Item = (i32, &'a DataFile)>,
+) -> Result<Vec<ArrayRef>> {
+    let (spec_ids, files): (Vec<_>, Vec<_>) = files.unzip();
+    let metrics = |metrics: fn(&DataFile) -> &Option<HashMap<i32, i64>>| {
+        files
+            .iter()
+            .map(|f| {
+                metrics(f).as_ref().map(|m| {
+                    sorted_entries(m)
+                        .into_iter()
+                        .map(|(k, v)| (k, *v))
+                        .collect()
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let bounds = |bounds: fn(&DataFile) -> &FileBounds| {
+        files
+            .iter()
+            .map(|f| {
+                bounds(f).as_ref().map(|b| {
+                    sorted_entries(b)
+                        .into_iter()
+                        .map(|(k, v)| (k, v.as_slice()))
+                        .collect()
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let int_keys = |keys: Vec<i32>| Arc::new(Int32Array::from(keys)) as ArrayRef;
+    let long_values = |values: Vec<i64>| Arc::new(Int64Array::from(values)) as ArrayRef;
+    let binary_values =
+        |values: Vec<&[u8]>| Arc::new(LargeBinaryArray::from_vec(values)) as ArrayRef;
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(Int32Array::from_iter_values(
+            files.iter().map(|f| f.content as i32),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            files.iter().map(|f| f.file_path.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            files
+                .iter()
+                .map(|f| f.file_format.to_string().to_uppercase()),
+        )),
+        Arc::new(Int32Array::from_iter_values(spec_ids)),
+    ];
+    if !partition_type.fields().is_empty() {
+        let rows = files
+            .iter()
+            .map(|f| partition_row(partition_type, &f.partition))
+            .collect::<Vec<_>>();
+        columns.push(partition_array(fields[columns.len()].data_type(), &rows)?);
+    }
+    columns.extend([
+        Arc::new(Int64Array::from_iter_values(
+            files.iter().map(|f| f.record_count),
+        )) as ArrayRef,
+        Arc::new(Int64Array::from_iter_values(
+            files.iter().map(|f| f.file_size_in_bytes),
+        )),
+    ]);
+    for values in [
+        metrics(|f| &f.column_sizes),
+        metrics(|f| &f.value_counts),
+        metrics(|f| &f.null_value_counts),
+        metrics(|f| &f.nan_value_counts),
+    ] {
+        let data_type = fields[columns.len()].data_type();
+        columns.push(map_array(data_type, values, int_keys, long_values)?);
+    }
+    for values in [bounds(|f| &f.lower_bounds), bounds(|f| &f.upper_bounds)] {
+        let data_type = fields[columns.len()].data_type();
+        columns.push(map_array(data_type, values, int_keys, binary_values)?);
+    }
+    columns.push(Arc::new(LargeBinaryArray::from_iter(
+        files.iter().map(|f| f.key_metadata.as_deref()),
+    )));
+    columns.push(list_array(
+        fields[columns.len()].data_type(),
+        files.iter().map(|f| f.split_offsets.clone()).collect(),
+        |values| Ok(Arc::new(Int64Array::from(values))),
+    )?);
+    columns.push(list_array(
+        fields[columns.len()].data_type(),
+        files.iter().map(|f| f.equality_ids.clone()).collect(),
+        |values| Ok(Arc::new(Int32Array::from(values))),
+    )?);
+    columns.push(Arc::new(Int32Array::from_iter(
+        files.iter().map(|f| f.sort_order_id),
+    )));
+
+    Ok(columns)
+}
+
+fn sorted_entries<V>(map: &HashMap<i32, V>) -> Vec<(i32, &V)> {
+    let mut entries = map.iter().map(|(k, v)| (*k, v)).collect::<Vec<_>>();
+    entries.sort_by_key(|(k, _)| *k);
+    entries
+}
+
+/// Values of the partition in the order of fields of `partition_type`,
+/// fields not in the partition are null.
+fn partition_row(partition_type: &Struct, partition: &StructValue) -> Vec<Option<PrimitiveValue>> {
+    partition_type
+        .fields()
+        .iter()
+        .map(|field| {
+            partition
+                .iter()
+                .find(|(id, ..)| *id == field.id)
+                .and_then(|(_, value, ..)| match value {
+                    Some(crate::types::AnyValue::Primitive(v)) => Some(v.clone()),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+fn partition_array(data_type: &DataType, rows: &[Vec<Option<PrimitiveValue>>]) -> Result<ArrayRef> {
+    let DataType::Struct(fields) = data_type else {
+        return Err(unexpected_type(data_type));
+    };
+    let columns = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            if rows.is_empty() {
+                return Ok(new_empty_array(field.data_type()));
+            }
+            let values = rows
+                .iter()
+                .map(|row| match &row[idx] {
+                    Some(value) => repeat_value(value, field.data_type(), 1),
+                    None => Ok(new_null_array(field.data_type(), 1)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            concat(&values.iter().map(|v| v.as_ref()).collect::<Vec<_>>())
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(
+        StructArray::try_new(fields.clone(), columns, None)
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+    ))
+}
+
+fn timestampz_array(values: impl Iterator<Item = Option<i64>>) -> ArrayRef {
+    // Timestampz always stored as UTC
+    Arc::new(TimestampMicrosecondArray::from_iter(values).with_timezone("+00:00"))
+}
+
+/// Build the map array of `data_type` from entries of rows, `None` rows are
+/// nulls.
+fn map_array<K, V>(
+    data_type: &DataType,
+    rows: Vec<Option<Vec<(K, V)>>>,
+    keys: impl FnOnce(Vec<K>) -> ArrayRef,
+    values: impl FnOnce(Vec<V>) -> ArrayRef,
+) -> Result<ArrayRef> {
+    let DataType::Map(entries_field, sorted) = data_type else {
+        return Err(unexpected_type(data_type));
+    };
+    let DataType::Struct(entry_fields) = entries_field.data_type() else {
+        return Err(unexpected_type(entries_field.data_type()));
+    };
+    let nulls = NullBuffer::from(rows.iter().map(Option::is_some).collect::<Vec<_>>());
+    let mut offsets = vec![0];
+    let (mut all_keys, mut all_values) = (vec![], vec![]);
+    for row in rows {
+        for (k, v) in row.into_iter().flatten() {
+            all_keys.push(k);
+            all_values.push(v);
+        }
+        offsets.push(all_keys.len() as i32);
+    }
+
+    let entries = StructArray::try_new(
+        entry_fields.clone(),
+        vec![keys(all_keys), values(all_values)],
+        None,
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    Ok(Arc::new(
+        MapArray::try_new(
+            entries_field.clone(),
+            OffsetBuffer::new(offsets.into()),
+            entries,
+            Some(nulls),
+            *sorted,
+        )
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+    ))
+}
+
+/// Build the list array of `data_type` from elements of rows, `None` rows
+/// are nulls.
+fn list_array<T>(
+    data_type: &DataType,
+    rows: Vec<Option<Vec<T>>>,
+    values: impl FnOnce(Vec<T>) -> Result<ArrayRef>,
+) -> Result<ArrayRef> {
+    let DataType::List(field) = data_type else {
+        return Err(unexpected_type(data_type));
+    };
+    let nulls = NullBuffer::from(rows.iter().map(Option::is_some).collect::<Vec<_>>());
+    let mut offsets = vec![0];
+    let mut elements = vec![];
+    for row in rows {
+        elements.extend(row.into_iter().flatten());
+        offsets.push(elements.len() as i32);
+    }
+
+    Ok(Arc::new(
+        ListArray::try_new(
+            field.clone(),
+            OffsetBuffer::new(offsets.into()),
+            values(elements)?,
+            Some(nulls),
+        )
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+    ))
+}
+
+fn unexpected_type(data_type: &DataType) -> Error {
+    Error::new(
+        ErrorKind::Unexpected,
+        format!("Unexpected data type {} of metadata table", data_type),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int32Type, Int64Type};
+    use arrow_array::Array;
+    use arrow_select::concat::concat_batches;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{
+        parse_table_metadata, AnyValue, FieldSummary, PartitionField, StructValueBuilder, Transform,
+    };
+
+    fn table_metadata() -> TableMetadata {
+        let path = format!(
+            "{}/../testdata/no_hint_table/metadata/00005-032145b7-6a0c-4a53-bc3d-b7b571ccab3b.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        parse_table_metadata(&fs::read(path).unwrap()).unwrap()
+    }
+
+    fn batch_of(metadata: &TableMetadata, typ: MetadataTableType) -> RecordBatch {
+        let partition_type = unified_partition_type(metadata).unwrap();
+        let schema = arrow_schema(&metadata_table_schema(typ, &partition_type)).unwrap();
+        match typ {
+            MetadataTableType::Snapshots => snapshots_batch(metadata, schema),
+            MetadataTableType::History => history_batch(metadata, schema),
+            MetadataTableType::Refs => refs_batch(metadata, schema),
+            _ => unreachable!(),
+        }
+        .unwrap()
+    }
+
+    #[test]
+    fn test_metadata_table_type_from_str() {
+        for typ in MetadataTableType::ALL {
+            assert_eq!(typ.name().parse::<MetadataTableType>().unwrap(), typ);
+        }
+        assert!("unknown".parse::<MetadataTableType>().is_err());
+    }
+
+    #[test]
+    fn test_snapshots_and_history() {
+        let mut metadata = table_metadata();
+
+        let snapshots = batch_of(&metadata, MetadataTableType::Snapshots);
+        assert_eq!(snapshots.num_rows(), 4);
+        assert_eq!(
+            snapshots
+                .column(3)
+                .as_string::<i32>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![
+                Some("append"),
+                Some("append"),
+                Some("overwrite"),
+                Some("append")
+            ]
+        );
+
+        // Roll back to the second snapshot, later snapshots are not ancestors.
+        metadata.current_snapshot_id = Some(402819370943546960);
+        let history = batch_of(&metadata, MetadataTableType::History);
+        assert_eq!(
+            history
+                .column(2)
+                .as_primitive::<Int64Type>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                Some(8380191719297762539),
+                Some(402819370943546960),
+                Some(2966623707104393227)
+            ]
+        );
+        assert_eq!(
+            history.column(3).as_boolean().iter().collect::<Vec<_>>(),
+            vec![Some(true), Some(true), Some(false), Some(false)]
+        );
+    }
+
+    #[test]
+    fn test_refs() {
+        let mut metadata = table_metadata();
+        metadata.refs.clear();
+        metadata.refs.insert(
+            "v1".to_string(),
+            crate::types::SnapshotReference::new(8380191719297762539, SnapshotReferenceType::Tag),
+        );
+
+        let refs = batch_of(&metadata, MetadataTableType::Refs);
+        assert_eq!(
+            refs.column(0).as_string::<i32>().iter().collect::<Vec<_>>(),
+            vec![Some("main"), Some("v1")]
+        );
+        assert_eq!(
+            refs.column(1).as_string::<i32>().iter().collect::<Vec<_>>(),
+            vec![Some("BRANCH"), Some("TAG")]
+        );
+        assert_eq!(
+            refs.column(2).as_primitive::<Int64Type>().values().to_vec(),
+            vec![6788296308394418127, 8380191719297762539]
+        );
+    }
+
+    #[test]
+    fn test_human_string() {
+        assert_eq!(
+            human_string(&PrimitiveValue::try_from_bytes(&Primitive::Date, &[0, 0, 0, 0]).unwrap()),
+            "1970-01-01"
+        );
+        assert_eq!(
+            human_string(&PrimitiveValue::Binary(vec![0xab, 0x01])),
+            "ab01"
+        );
+    }
+
+    /// Rows of the metadata table of table in one batch.
+    async fn read(table: &Table, typ: MetadataTableType) -> Result<RecordBatch> {
+        let batches = table
+            .metadata_table(typ)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        concat_batches(&table.metadata_table_schema(typ)?, &batches)
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+    }
+
+    fn strings(batch: &RecordBatch, name: &str) -> Vec<Option<String>> {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_string::<i32>()
+            .iter()
+            .map(|v| v.map(str::to_string))
+            .collect()
+    }
+
+    fn ints(batch: &RecordBatch, name: &str) -> Vec<i32> {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_primitive::<Int32Type>()
+            .values()
+            .to_vec()
+    }
+
+    fn longs(batch: &RecordBatch, name: &str) -> Vec<i64> {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec()
+    }
+
+    /// File names of paths.
+    fn file_names(paths: Vec<Option<String>>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|p| p.unwrap().rsplit('/').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_files_and_manifests() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        a.value_counts = Some(HashMap::from([(2, 2), (1, 2)]));
+        a.lower_bounds = Some(HashMap::from([(1, 1i64.to_le_bytes().to_vec())]));
+        a.split_offsets = Some(vec![4]);
+        let b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a.clone(), b.clone()], vec![]).await?;
+        let position_deletes =
+            test_utils::write_position_delete_file(&table, "pd", &[(&a, 0)]).await?;
+        let equality_deletes = test_utils::write_equality_delete_file(&table, "ed", &[3]).await?;
+        let s2 = test_utils::commit(&mut table, vec![], vec![position_deletes, equality_deletes])
+            .await?;
+
+        let files = read(&table, MetadataTableType::Files).await?;
+        assert_eq!(
+            file_names(strings(&files, "file_path")),
+            vec!["a.parquet", "b.parquet", "pd.parquet", "ed.parquet"]
+        );
+        assert_eq!(ints(&files, "content"), vec![0, 0, 1, 2]);
+        assert_eq!(ints(&files, "spec_id"), vec![0, 0, 0, 0]);
+        assert_eq!(longs(&files, "record_count"), vec![2, 1, 1, 1]);
+        assert_eq!(
+            strings(&files, "file_format"),
+            vec![Some("PARQUET".to_string()); 4]
+        );
+        // Entries of maps are sorted by keys.
+        let value_counts = files.column_by_name("value_counts").unwrap().as_map();
+        assert_eq!(
+            value_counts.nulls().unwrap().iter().collect::<Vec<_>>(),
+            vec![true, false, false, false]
+        );
+        assert_eq!(
+            value_counts
+                .keys()
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            value_counts
+                .values()
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![2, 2]
+        );
+        let lower_bounds = files.column_by_name("lower_bounds").unwrap().as_map();
+        assert_eq!(lower_bounds.value_offsets(), &[0, 1, 1, 1, 1]);
+        assert_eq!(
+            lower_bounds.values().as_binary::<i64>().value(0),
+            1i64.to_le_bytes()
+        );
+        let split_offsets = files
+            .column_by_name("split_offsets")
+            .unwrap()
+            .as_list::<i32>();
+        assert!(split_offsets.is_valid(0) && split_offsets.is_null(1));
+        assert_eq!(
+            split_offsets
+                .value(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![4]
+        );
+        let equality_ids = files
+            .column_by_name("equality_ids")
+            .unwrap()
+            .as_list::<i32>();
+        assert_eq!(
+            equality_ids.nulls().unwrap().iter().collect::<Vec<_>>(),
+            vec![false, false, false, true]
+        );
+        assert_eq!(
+            equality_ids
+                .value(3)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![1]
+        );
+
+        let data_files = read(&table, MetadataTableType::DataFiles).await?;
+        assert_eq!(
+            file_names(strings(&data_files, "file_path")),
+            vec!["a.parquet", "b.parquet"]
+        );
+        let delete_files = read(&table, MetadataTableType::DeleteFiles).await?;
+        assert_eq!(
+            file_names(strings(&delete_files, "file_path")),
+            vec!["pd.parquet", "ed.parquet"]
+        );
+
+        // The data manifest of the first snapshot is kept by the second one.
+        let manifests = read(&table, MetadataTableType::Manifests).await?;
+        assert_eq!(ints(&manifests, "content"), vec![0, 1]);
+        assert_eq!(longs(&manifests, "added_snapshot_id"), vec![s1, s2]);
+        assert_eq!(ints(&manifests, "added_data_files_count"), vec![2, 0]);
+        assert_eq!(ints(&manifests, "added_delete_files_count"), vec![0, 2]);
+        let all_manifests = read(&table, MetadataTableType::AllManifests).await?;
+        assert_eq!(
+            longs(&all_manifests, "reference_snapshot_id"),
+            vec![s1, s2, s2]
+        );
+        assert_eq!(longs(&all_manifests, "added_snapshot_id"), vec![s1, s1, s2]);
+
+        let partitions = read(&table, MetadataTableType::Partitions).await?;
+        assert_eq!(longs(&partitions, "record_count"), vec![3]);
+        assert_eq!(ints(&partitions, "file_count"), vec![2]);
+        assert_eq!(ints(&partitions, "position_delete_file_count"), vec![1]);
+        assert_eq!(longs(&partitions, "equality_delete_record_count"), vec![1]);
+        assert_eq!(longs(&partitions, "last_updated_snapshot_id"), vec![s2]);
+
+        // Entries of deleted files are kept.
+        let s3 = test_utils::rewrite(&mut table, "delete", &[b], vec![]).await?;
+        let entries = read(&table, MetadataTableType::Entries).await?;
+        assert_eq!(ints(&entries, "status"), vec![0, 2, 1, 1]);
+        assert_eq!(longs(&entries, "snapshot_id"), vec![s1, s3, s2, s2]);
+        let data_file = entries.column_by_name("data_file").unwrap().as_struct();
+        assert_eq!(
+            file_names(
+                data_file
+                    .column_by_name("file_path")
+                    .unwrap()
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.map(str::to_string))
+                    .collect()
+            ),
+            vec!["a.parquet", "b.parquet", "pd.parquet", "ed.parquet"]
+        );
+        let files = read(&table, MetadataTableType::DataFiles).await?;
+        assert_eq!(file_names(strings(&files, "file_path")), vec!["a.parquet"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_partitions_of_dropped_source_field() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        // Partition by `v`, which is dropped later.
+        test_utils::update_metadata(&mut table, |metadata| {
+            metadata.partition_specs.push(PartitionSpec {
+                spec_id: 1,
+                fields: vec![PartitionField {
+                    source_column_id: 2,
+                    partition_field_id: 1000,
+                    transform: Transform::Identity,
+                    name: "v".to_string(),
+                }],
+            });
+            metadata.default_spec_id = 1;
+            metadata.last_partition_id = 1000;
+        })
+        .await?;
+        let partition_type = Arc::new(
+            table
+                .current_table_metadata()
+                .current_partition_spec()?
+                .partition_type(table.current_table_metadata().current_schema()?)?,
+        );
+        let partition = |v: &str| {
+            let mut builder = StructValueBuilder::new(partition_type.clone());
+            builder
+                .add_field(
+                    1000,
+                    Some(AnyValue::Primitive(PrimitiveValue::String(v.to_string()))),
+                )
+                .unwrap();
+            builder.build().unwrap()
+        };
+        let mut a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        a.partition = partition("x");
+        let mut b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        b.partition = partition("y");
+        test_utils::commit(&mut table, vec![a, b.clone()], vec![]).await?;
+        let mut c = test_utils::write_data_file(&table, "c", &[4]).await?;
+        c.partition = partition("x");
+        let mut deletes = test_utils::write_position_delete_file(&table, "pd", &[(&b, 0)]).await?;
+        deletes.partition = partition("y");
+        let s2 = test_utils::commit(&mut table, vec![c], vec![deletes]).await?;
+
+        test_utils::update_metadata(&mut table, |metadata| {
+            let mut schema = metadata.current_schema().unwrap().clone();
+            schema.schema_id = 1;
+            schema = Schema::new(
+                1,
+                None,
+                Struct::new(
+                    schema
+                        .fields()
+                        .iter()
+                        .filter(|f| f.id != 2)
+                        .cloned()
+                        .collect(),
+                ),
+            );
+            metadata.schemas.push(schema);
+            metadata.current_schema_id = 1;
+            metadata.default_spec_id = 0;
+        })
+        .await?;
+
+        let partitions = read(&table, MetadataTableType::Partitions).await?;
+        let partition_values = partitions
+            .column_by_name("partition")
+            .unwrap()
+            .as_struct()
+            .column(0)
+            .as_string::<i32>()
+            .iter()
+            .map(|v| v.map(str::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            partition_values,
+            vec![Some("x".to_string()), Some("y".to_string())]
+        );
+        assert_eq!(ints(&partitions, "spec_id"), vec![1, 1]);
+        assert_eq!(longs(&partitions, "record_count"), vec![3, 1]);
+        assert_eq!(ints(&partitions, "file_count"), vec![2, 1]);
+        assert_eq!(ints(&partitions, "position_delete_file_count"), vec![0, 1]);
+        // Delete files update the partition too.
+        assert_eq!(longs(&partitions, "last_updated_snapshot_id"), vec![s2, s2]);
+
+        let files = read(&table, MetadataTableType::DataFiles).await?;
+        let partition_values = files
+            .column_by_name("partition")
+            .unwrap()
+            .as_struct()
+            .column(0)
+            .as_string::<i32>()
+            .iter()
+            .map(|v| v.map(str::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            partition_values,
+            ["x", "y", "x"]
+                .into_iter()
+                .map(|v| Some(v.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        // Bounds of partition summaries are decoded by the partition type of
+        // the manifest, not the current schema without `v`.
+        let metadata = table.current_table_metadata();
+        let snapshot = metadata.current_snapshot()?.unwrap();
+        let mut manifest = table.manifest_list_of_snapshot(snapshot).await?.entries[0].clone();
+        assert_eq!(manifest.partition_spec_id, 1);
+        manifest.partitions = Some(vec![FieldSummary {
+            contains_null: false,
+            contains_nan: None,
+            lower_bound: Some(b"x".to_vec()),
+            upper_bound: Some(b"y".to_vec()),
+        }]);
+        let schema = table.metadata_table_schema(MetadataTableType::Manifests)?;
+        let manifests = manifests_batch(metadata, schema, &[(manifest, None)])?;
+        let summaries = manifests
+            .column_by_name("partition_summaries")
+            .unwrap()
+            .as_list::<i32>()
+            .value(0);
+        let summary = summaries.as_struct();
+        assert_eq!(
+            summary
+                .column_by_name("lower_bound")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "x"
+        );
+        assert_eq!(
+            summary
+                .column_by_name("upper_bound")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "y"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,1885 @@
+use std::{
+    collections::HashSet,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::{
+    config::ParquetReaderConfig,
+    io::FileCacheRef,
+    types::{
+        DataContentType, DataFile, DataFileFormat, ManifestContentType, ManifestListEntry,
+        ManifestStatus, Schema, Snapshot, StructValue, TableMetadata,
+    },
+    Error, ErrorKind, Result, Table,
+};
+use arrow_arith::boolean::{and, not};
+use arrow_array::{cast::AsArray, types::Int64Type, BooleanArray, Int64Array, RecordBatch};
+use arrow_schema::SchemaRef;
+use arrow_select::filter::{filter, filter_record_batch};
+use derive_builder::Builder;
+use futures::{future::BoxFuture, stream::BoxStream, StreamExt, TryStreamExt};
+use opendal::Operator;
+use parquet::{
+    arrow::{async_reader::AsyncFileReader, ParquetRecordBatchStreamBuilder, ProjectionMask},
+    errors::ParquetError,
+    file::{
+        footer::{decode_footer, decode_metadata},
+        metadata::ParquetMetaData,
+        FOOTER_SIZE,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+mod delete;
+use delete::*;
+mod predicate;
+pub use predicate::*;
+mod task;
+pub use task::*;
+mod changelog;
+pub use changelog::*;
+mod projection;
+pub use projection::*;
+mod metadata_table;
+pub use metadata_table::*;
+mod aggregate;
+pub use aggregate::*;
+mod avro;
+mod bloom_filter;
+mod lookup;
+mod name_mapping;
+pub use name_mapping::DEFAULT_NAME_MAPPING;
+use name_mapping::{root_columns, with_schema, NameMapping};
+mod observer;
+pub use observer::*;
+mod orc;
+use orc::OrcFileReader;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "prometheus")]
+pub use prometheus::*;
+mod report;
+pub use report::*;
+mod split;
+mod tail;
+use avro::AvroFileReader;
+pub use tail::*;
+
+/// Position of a table scan, it's emitted with batches by
+/// [`TableScan::scan_with_cursor`] so that the scan can be resumed from it
+/// after a crash, see [`TableScanBuilder::with_start_from`].
+///
+/// Tasks are planned in the same order for the same snapshot and filter, so
+/// a cursor is only valid for the scan which emitted it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ScanCursor {
+    /// Id of the snapshot scanned.
+    pub snapshot_id: i64,
+    /// Index of the task to resume from, in the planned tasks.
+    pub task_index: usize,
+    /// Position in the data file of the task of the next row to read.
+    pub row: u64,
+}
+
+impl ScanCursor {
+    /// Serialize the cursor to json.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse the cursor from json.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+#[builder(setter(prefix = "with"))]
+pub struct TableScan {
+    // Table operator
+    op: Operator,
+    /// Snapshot to scan, it's ignored if `as_of_timestamp` or `snapshot_ref`
+    /// is set.
+    #[builder(default, setter(strip_option))]
+    snapshot_id: Option<i64>,
+    /// Scan the snapshot which was current at the timestamp in milliseconds.
+    #[builder(default, setter(name = "as_of_timestamp", strip_option))]
+    as_of_timestamp: Option<i64>,
+    /// Scan the snapshot of the branch or tag.
+    #[builder(default, setter(name = "use_ref", into, strip_option))]
+    snapshot_ref: Option<String>,
+    /// Only scan data files appended after this snapshot (exclusive), up to
+    /// the snapshot to scan (inclusive).
+    #[builder(default, setter(strip_option))]
+    from_snapshot_id: Option<i64>,
+    /// Resume the scan from the cursor emitted by the same scan.
+    #[builder(default, setter(strip_option))]
+    start_from: Option<ScanCursor>,
+    #[builder(default)]
+    column_names: Vec<String>,
+    #[builder(default)]
+    partition_value: Option<StructValue>,
+    /// Filter of rows, data files whose partition values can't match it are
+    /// skipped in planning.
+    #[builder(default = "Predicate::AlwaysTrue")]
+    filter: Predicate,
+    /// Max number of rows to scan, planning stops once enough rows are
+    /// covered and reading stops after enough rows are read.
+    ///
+    /// The limit is shared by the file scans of [`TableScan::scan`], which
+    /// return up to `limit` rows in total and stop yielding file scans once
+    /// it's reached. A file scan opened by [`TableScan::open_task`] has a
+    /// limit of its own.
+    #[builder(default, setter(strip_option))]
+    limit: Option<usize>,
+    /// Observer of reading data files, e.g. to collect prometheus metrics.
+    #[builder(default, setter(strip_option))]
+    observer: Option<ReadObserverRef>,
+
+    // Configurations
+    #[builder(default = "1024")]
+    batch_size: usize,
+}
+
+pub struct FileScan {
+    op: Operator,
+    task: FileScanTask,
+    table_location: String,
+    projection: Arc<Projection>,
+    /// Resolves field ids of columns in data files written without them.
+    name_mapping: Arc<NameMapping>,
+    offset: Option<usize>,
+    /// Cursor of the first row of the task, it's set if the task is planned
+    /// by [`TableScan::scan`].
+    cursor: Option<ScanCursor>,
+    batch_size: usize,
+    /// Rows left to return, shared by the file scans of a table scan.
+    limit: Option<RowLimit>,
+    /// Delete files whose deleted rows are the only rows to output.
+    deleted_by: Vec<DeleteFileEntry>,
+    file_cache: Option<FileCacheRef>,
+    reader_config: ParquetReaderConfig,
+    observer: Option<ReadObserverRef>,
+}
+
+pub type FileScanStream = BoxStream<'static, Result<FileScan>>;
+pub type RecordBatchStream = BoxStream<'static, Result<RecordBatch>>;
+pub type CursorRecordBatchStream = BoxStream<'static, Result<(RecordBatch, ScanCursor)>>;
+
+impl TableScan {
+    /// Plan the tasks of this scan.
+    ///
+    /// Delete manifests of the snapshot are read into an index of delete files,
+    /// which is used to find the delete files applied to each data file. Data
+    /// manifests are streamed, so data files filtered out are never collected.
+    ///
+    /// If `limit` is set, planning stops once the record counts of tasks
+    /// without deletes or residual filters cover it.
+    pub async fn plan_tasks(&self, table: &Table) -> Result<Vec<FileScanTask>> {
+        Ok(self.plan_tasks_with_report(table).await?.0)
+    }
+
+    /// Plan the tasks of this scan like [`TableScan::plan_tasks`], with the
+    /// report of planning. The report is sent to the catalog of the table
+    /// too, which doesn't wait for it to be delivered, failures of sending it
+    /// are logged and ignored.
+    pub async fn plan_tasks_with_report(
+        &self,
+        table: &Table,
+    ) -> Result<(Vec<FileScanTask>, ScanReport)> {
+        let planning_start = Instant::now();
+        let metadata = table.current_table_metadata();
+        let snapshot = self.snapshot(metadata)?;
+        let schema = self.schema(metadata)?;
+        let projection = self.projection(metadata)?;
+        let counters = Mutex::new(ScanMetrics::default());
+
+        let (mut data_files, delete_files) = match self.from_snapshot_id {
+            Some(from_snapshot_id) => (
+                Self::appended_data_files(table, from_snapshot_id, snapshot, &counters).await?,
+                vec![],
+            ),
+            None => (
+                Self::live_data_files(table, snapshot, &counters).await?,
+                Self::live_delete_files(table, snapshot, &counters).await?,
+            ),
+        };
+        let mut metrics = counters.into_inner().unwrap();
+        metrics.indexed_delete_files = delete_files.len() as u64;
+        for entry in &delete_files {
+            match entry.delete_file.content {
+                DataContentType::EqualityDeletes => metrics.equality_delete_files += 1,
+                DataContentType::PostionDeletes => metrics.positional_delete_files += 1,
+                DataContentType::Data => {}
+            }
+        }
+        let delete_file_index = DeleteFileIndex::new(delete_files);
+
+        // Tasks before `start_from` are skipped, so they can't cover the
+        // limit.
+        let limit = self.limit.filter(|_| self.start_from.is_none());
+        let mut covered_rows = 0;
+        let mut tasks = vec![];
+        let mut applied_deletes = HashSet::new();
+        while let Some((data_file, sequence_number, spec_id)) = data_files.try_next().await? {
+            if let Some(task) = self.new_task(
+                metadata,
+                schema,
+                data_file,
+                sequence_number,
+                spec_id,
+                &delete_file_index,
+            )? {
+                metrics.result_data_files += 1;
+                metrics.total_file_size_in_bytes += task.data_file.file_size_in_bytes.max(0) as u64;
+                for entry in task.position_deletes.iter().chain(&task.equality_deletes) {
+                    metrics.result_delete_files += 1;
+                    metrics.total_delete_file_size_in_bytes +=
+                        entry.delete_file.file_size_in_bytes.max(0) as u64;
+                    applied_deletes.insert(entry.delete_file.file_path.clone());
+                }
+
+                if task.position_deletes.is_empty()
+                    && task.equality_deletes.is_empty()
+                    && task.residual == Predicate::AlwaysTrue
+                {
+                    covered_rows += task.data_file.record_count.max(0) as usize;
+                }
+                tasks.push(task);
+                if limit.is_some_and(|limit| covered_rows >= limit) {
+                    break;
+                }
+            } else {
+                metrics.skipped_data_files += 1;
+            }
+        }
+        metrics.skipped_delete_files = metrics
+            .indexed_delete_files
+            .saturating_sub(applied_deletes.len() as u64);
+        metrics.total_planning_duration = planning_start.elapsed();
+
+        let report = ScanReport {
+            table_name: table.table_name().clone(),
+            snapshot_id: snapshot.snapshot_id,
+            filter: self.filter.clone(),
+            schema_id: snapshot
+                .schema_id
+                .map_or(metadata.current_schema_id, |id| id as i32),
+            projected_field_ids: projection.fields().iter().map(|f| f.id).collect(),
+            projected_field_names: projection.fields().iter().map(|f| f.name.clone()).collect(),
+            metrics,
+        };
+        if let Err(e) = table.catalog().report_scan(table, &report).await {
+            log::warn!(
+                "Failed to report scan of table {}: {}",
+                report.table_name,
+                e
+            );
+        }
+
+        Ok((tasks, report))
+    }
+
+    /// Plan the tasks and open a [`FileScan`] for each of them, tasks before
+    /// `start_from` are skipped.
+    pub async fn scan(&self, table: &Table) -> Result<FileScanStream> {
+        let snapshot_id = self.snapshot(table.current_table_metadata())?.snapshot_id;
+        let tasks = self.plan_tasks(table).await?;
+
+        let start_from = self.start_from.unwrap_or(ScanCursor {
+            snapshot_id,
+            task_index: 0,
+            row: 0,
+        });
+        if start_from.snapshot_id != snapshot_id || start_from.task_index > tasks.len() {
+            return Err(Error::new(
+                ErrorKind::Unexpected,
+                format!(
+                    "Cursor {:?} doesn't belong to the scan of snapshot {} with {} tasks",
+                    start_from,
+                    snapshot_id,
+                    tasks.len()
+                ),
+            ));
+        }
+
+        let limit = self.limit.map(RowLimit::new);
+        let mut file_scans = Vec::with_capacity(tasks.len() - start_from.task_index);
+        for (task_index, task) in tasks.into_iter().enumerate().skip(start_from.task_index) {
+            let mut file_scan = self.open_task(table, task)?;
+            file_scan.limit = limit.clone();
+            let row = if task_index == start_from.task_index {
+                file_scan.offset = Some(start_from.row as usize);
+                start_from.row
+            } else {
+                0
+            };
+            file_scan.cursor = Some(ScanCursor {
+                snapshot_id,
+                task_index,
+                row,
+            });
+            file_scans.push(file_scan);
+        }
+
+        Ok(file_scan_stream(file_scans, limit))
+    }
+
+    /// Scan the table, each batch comes with the cursor after it. Resuming
+    /// from the cursor with `start_from` continues after the batch.
+    pub async fn scan_with_cursor(&self, table: &Table) -> Result<CursorRecordBatchStream> {
+        let stream = self
+            .scan(table)
+            .await?
+            .and_then(|file_scan| file_scan.scan_with_cursor())
+            .try_flatten();
+        Ok(Box::pin(stream))
+    }
+
+    /// Create the task to scan the data file with delete files in the index.
+    /// Metrics of the data file are evaluated with `schema`, the schema of
+    /// the snapshot to scan.
+    ///
+    /// Returns `None` if the data file is filtered out by partition or
+    /// metrics.
+    fn new_task(
+        &self,
+        metadata: &TableMetadata,
+        schema: &Schema,
+        data_file: DataFile,
+        sequence_number: i64,
+        spec_id: i32,
+        delete_file_index: &DeleteFileIndex,
+    ) -> Result<Option<FileScanTask>> {
+        if let Some(partition_value) = &self.partition_value {
+            if data_file.partition != *partition_value {
+                return Ok(None);
+            }
+        }
+
+        let spec = metadata.partition_spec(spec_id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unexpected,
+                format!("Partition spec id {} not found!", spec_id),
+            )
+        })?;
+        let residual = self.filter.residual(spec, &data_file.partition);
+        if !residual.may_match_metrics(schema, &data_file)? {
+            return Ok(None);
+        }
+
+        let (position_deletes, equality_deletes) =
+            delete_file_index.for_data_file(&data_file, sequence_number, spec_id);
+        Ok(Some(FileScanTask {
+            start: 0,
+            length: data_file.file_size_in_bytes as u64,
+            data_file,
+            sequence_number,
+            spec_id,
+            schema_id: schema.schema_id,
+            position_deletes,
+            equality_deletes,
+            residual,
+        }))
+    }
+
+    /// Stream live data files of the snapshot, with their data sequence
+    /// numbers and partition spec ids.
+    async fn live_data_files<'a>(
+        table: &'a Table,
+        snapshot: &Snapshot,
+        metrics: &Mutex<ScanMetrics>,
+    ) -> Result<BoxStream<'a, Result<(DataFile, i64, i32)>>> {
+        let entries = table
+            .manifest_entries_of_snapshot(
+                snapshot,
+                count_manifests(metrics, ManifestContentType::Data, |_| true),
+                |e| e.is_alive() && e.data_file.content == DataContentType::Data,
+            )
+            .await?;
+
+        Ok(Box::pin(entries.map_ok(|(spec_id, entry)| {
+            let sequence_number = entry.data_sequence_number();
+            (entry.data_file, sequence_number, spec_id)
+        })))
+    }
+
+    /// Collect live delete files of the snapshot.
+    async fn live_delete_files(
+        table: &Table,
+        snapshot: &Snapshot,
+        metrics: &Mutex<ScanMetrics>,
+    ) -> Result<Vec<DeleteFileEntry>> {
+        table
+            .manifest_entries_of_snapshot(
+                snapshot,
+                count_manifests(metrics, ManifestContentType::Deletes, |_| true),
+                |e| e.is_alive(),
+            )
+            .await?
+            .map_ok(|(spec_id, entry)| DeleteFileEntry {
+                sequence_number: entry.data_sequence_number(),
+                delete_file: entry.data_file,
+                spec_id,
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Stream data files appended by snapshots after `from_snapshot_id`
+    /// (exclusive) up to `snapshot` (inclusive), following the parent chain.
+    ///
+    /// Only snapshots appending data files are allowed in between, since
+    /// other operations may remove or delete rows which can't be expressed by
+    /// an append scan. Existing delete files don't apply to newer data files,
+    /// so no deletes are returned.
+    async fn appended_data_files<'a>(
+        table: &'a Table,
+        from_snapshot_id: i64,
+        snapshot: &Snapshot,
+        metrics: &Mutex<ScanMetrics>,
+    ) -> Result<BoxStream<'a, Result<(DataFile, i64, i32)>>> {
+        let snapshots = Self::snapshots_between(
+            table.current_table_metadata(),
+            Some(from_snapshot_id),
+            snapshot,
+        )?;
+        for snapshot in &snapshots {
+            if !Self::is_append_only(table, snapshot).await? {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    format!(
+                        "Incremental append scan doesn't support snapshot {} with operation {:?}, which removes data files or adds delete files",
+                        snapshot.snapshot_id,
+                        snapshot.operation(),
+                    ),
+                ));
+            }
+        }
+
+        let mut streams = Vec::with_capacity(snapshots.len());
+        for snapshot in snapshots {
+            let snapshot_id = snapshot.snapshot_id;
+            let entries = table
+                .manifest_entries_of_snapshot(
+                    snapshot,
+                    count_manifests(metrics, ManifestContentType::Data, |m| {
+                        m.added_snapshot_id == snapshot_id
+                    }),
+                    move |e| {
+                        e.status == ManifestStatus::Added && e.snapshot_id == Some(snapshot_id)
+                    },
+                )
+                .await?;
+            streams.push(entries);
+        }
+
+        Ok(Box::pin(futures::stream::iter(streams).flatten().map_ok(
+            |(spec_id, entry)| {
+                let sequence_number = entry.data_sequence_number();
+                (entry.data_file, sequence_number, spec_id)
+            },
+        )))
+    }
+
+    /// Returns whether the snapshot only added data files, i.e. it removed no
+    /// data files and added no delete files.
+    ///
+    /// The manifests written by the snapshot are checked instead of the
+    /// operation in its summary, which is not always recorded by writers.
+    async fn is_append_only(table: &Table, snapshot: &Snapshot) -> Result<bool> {
+        let snapshot_id = snapshot.snapshot_id;
+        let mut changes = table
+            .manifest_entries_of_snapshot(
+                snapshot,
+                |m| m.added_snapshot_id == snapshot_id,
+                move |e| {
+                    e.snapshot_id == Some(snapshot_id)
+                        && (e.status == ManifestStatus::Deleted
+                            || e.data_file.content != DataContentType::Data)
+                },
+            )
+            .await?;
+        Ok(changes.try_next().await?.is_none())
+    }
+
+    /// Returns snapshots after `from_snapshot_id` (exclusive) up to
+    /// `snapshot` (inclusive) following the parent chain, from the oldest to
+    /// the newest. All ancestors of `snapshot` are returned if
+    /// `from_snapshot_id` is `None`.
+    fn snapshots_between<'a>(
+        metadata: &'a TableMetadata,
+        from_snapshot_id: Option<i64>,
+        snapshot: &'a Snapshot,
+    ) -> Result<Vec<&'a Snapshot>> {
+        let mut snapshots = vec![];
+        let mut current = Some(snapshot);
+        while let Some(s) = current {
+            if Some(s.snapshot_id) == from_snapshot_id {
+                break;
+            }
+            snapshots.push(s);
+            current = match s.parent_snapshot_id {
+                Some(parent_id) => Some(metadata.snapshot(parent_id).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Unexpected,
+                        format!("Snapshot {} not found!", parent_id),
+                    )
+                })?),
+                None => None,
+            };
+        }
+        if let (None, Some(from_snapshot_id)) = (current, from_snapshot_id) {
+            return Err(Error::new(
+                ErrorKind::Unexpected,
+                format!(
+                    "Snapshot {} is not an ancestor of snapshot {}!",
+                    from_snapshot_id, snapshot.snapshot_id
+                ),
+            ));
+        }
+
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// Resolve the snapshot to scan, a reference or a timestamp takes
+    /// precedence over the snapshot id.
+    fn snapshot<'a>(&self, metadata: &'a TableMetadata) -> Result<&'a Snapshot> {
+        let snapshot_id = match (&self.snapshot_ref, self.as_of_timestamp) {
+            (Some(_), Some(_)) => {
+                return Err(Error::new(
+                    ErrorKind::Unexpected,
+                    "Can't scan as of a reference and a timestamp at the same time",
+                ))
+            }
+            (Some(name), None) => {
+                metadata
+                    .snapshot_ref(name)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::Unexpected,
+                            format!("Reference {} not found!", name),
+                        )
+                    })?
+                    .snapshot_id
+            }
+            (None, Some(timestamp_ms)) => {
+                return metadata
+                    .snapshot_as_of_timestamp(timestamp_ms)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::Unexpected,
+                            format!("No snapshot found as of timestamp {}!", timestamp_ms),
+                        )
+                    })
+            }
+            (None, None) => self
+                .snapshot_id
+                .ok_or_else(|| Error::new(ErrorKind::Unexpected, "Snapshot to scan is not set!"))?,
+        };
+
+        metadata.snapshot(snapshot_id).ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unexpected,
+                format!("Snapshot {} not found!", snapshot_id),
+            )
+        })
+    }
+
+    /// Arrow schema of batches of this scan, it's converted from the schema
+    /// of the snapshot to scan, with field ids in metadata of fields.
+    ///
+    /// Batches of all data files have this schema even if they are written
+    /// by other engines, with other timestamp units or dictionary encoding.
+    /// `_partition` column has the fields of all partition specs.
+    pub fn arrow_schema(&self, table: &Table) -> Result<SchemaRef> {
+        Ok(self
+            .projection(table.current_table_metadata())?
+            .arrow_schema()
+            .clone())
+    }
+
+    /// Schema of the snapshot to scan.
+    fn schema<'a>(&self, metadata: &'a TableMetadata) -> Result<&'a Schema> {
+        let snapshot = self.snapshot(metadata)?;
+        snapshot
+            .schema_id
+            .and_then(|id| metadata.schema(id as i32))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Unexpected,
+                    format!("Schema id not found for snapshot {}!", snapshot.snapshot_id),
+                )
+            })
+    }
+
+    fn projection(&self, metadata: &TableMetadata) -> Result<Projection> {
+        let partition_type = unified_partition_type(metadata)?;
+        Projection::try_new(self.schema(metadata)?, &self.column_names, &partition_type)
+    }
+
+    /// Create a [`FileScan`] to execute the task with configurations of this
+    /// scan, the task may be planned by another process.
+    pub fn open_task(&self, table: &Table, task: FileScanTask) -> Result<FileScan> {
+        let metadata = table.current_table_metadata();
+        if metadata.partition_spec(task.spec_id).is_none() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Partition spec {} not found!", task.spec_id),
+            ));
+        }
+        let projection = self.projection(metadata)?;
+
+        Ok(FileScan {
+            op: self.op.clone(),
+            task,
+            table_location: metadata.location.clone(),
+            projection: Arc::new(projection),
+            name_mapping: Arc::new(NameMapping::try_new(metadata)?),
+            offset: None,
+            cursor: None,
+            batch_size: self.batch_size,
+            limit: self.limit.map(RowLimit::new),
+            deleted_by: vec![],
+            file_cache: table.file_cache().cloned(),
+            reader_config: table.table_config().parquet_reader.clone(),
+            observer: self.observer.clone(),
+        })
+    }
+}
+
+impl FileScan {
+    /// Scan the data file, rows deleted by position delete files or
+    /// equality delete files are filtered out. If `_deleted` column is
+    /// projected, deleted rows are kept and flagged by it instead. Parquet,
+    /// avro and orc data files are supported.
+    ///
+    /// `offset` is the position in data file of the first row to read.
+    /// Batches have the schema of [`TableScan::arrow_schema`].
+    pub async fn scan(self) -> Result<RecordBatchStream> {
+        Ok(Box::pin(
+            self.scan_with_positions().await?.map_ok(|(batch, _)| batch),
+        ))
+    }
+
+    /// Scan the data file like [`FileScan::scan`], each batch comes with the
+    /// cursor after it.
+    ///
+    /// Returns error if the task is not planned by [`TableScan::scan`].
+    pub async fn scan_with_cursor(self) -> Result<CursorRecordBatchStream> {
+        let cursor = self.cursor.ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unexpected,
+                format!(
+                    "Scan of data file {} has no cursor",
+                    self.task.data_file.file_path
+                ),
+            )
+        })?;
+        Ok(Box::pin(self.scan_with_positions().await?.map_ok(
+            move |(batch, row)| (batch, ScanCursor { row, ..cursor }),
+        )))
+    }
+
+    /// Scan the data file, each batch comes with the position in data file
+    /// of the next row to read after it.
+    async fn scan_with_positions(self) -> Result<BoxStream<'static, Result<(RecordBatch, u64)>>> {
+        let open_start = Instant::now();
+        let mut deletes = DeleteFilter::load(
+            &self.op,
+            &self.table_location,
+            &self.task.data_file.file_path,
+            &[
+                self.task.position_deletes.as_slice(),
+                self.task.equality_deletes.as_slice(),
+            ]
+            .concat(),
+        )
+        .await?;
+        // Only rows deleted by these delete files are kept, it's used by
+        // changelog scan to find the deleted rows.
+        let mut deleted_by = if self.deleted_by.is_empty() {
+            None
+        } else {
+            Some(
+                DeleteFilter::load(
+                    &self.op,
+                    &self.table_location,
+                    &self.task.data_file.file_path,
+                    &self.deleted_by,
+                )
+                .await?,
+            )
+        };
+
+        // Columns are resolved by field id, columns of equality deletes must
+        // be read even if they are not projected.
+        let field_ids = self
+            .projection
+            .field_ids()
+            .chain(deletes.field_ids())
+            .chain(deleted_by.iter().flat_map(|d| d.field_ids()))
+            .collect::<HashSet<_>>();
+        let start = self.offset.unwrap_or(0) as u64;
+        let batches = match self.task.data_file.file_format {
+            DataFileFormat::Parquet => self.read_parquet(&field_ids, start).await?,
+            DataFileFormat::Avro => {
+                AvroFileReader::new(
+                    self.op.clone(),
+                    relative_path(&self.table_location, &self.task.data_file.file_path)?,
+                    self.projection.read_fields(&field_ids),
+                )
+                .with_observer(self.observer.clone())
+                .read(start, self.batch_size)
+                .await?
+            }
+            DataFileFormat::Orc => {
+                OrcFileReader::new(
+                    self.op.clone(),
+                    relative_path(&self.table_location, &self.task.data_file.file_path)?,
+                    self.task.data_file.file_size_in_bytes as u64,
+                    self.name_mapping.clone(),
+                )
+                .with_observer(self.observer.clone())
+                .read(
+                    &field_ids,
+                    self.task.start..self.task.start + self.task.length,
+                    &self.task.residual,
+                    start,
+                    self.batch_size,
+                )
+                .await?
+            }
+        };
+
+        let observer = self.observer.clone();
+        if let Some(observer) = &observer {
+            observer.file_opened(open_start.elapsed());
+            observer.deletes_applied(
+                self.task.position_deletes.len()
+                    + self.task.equality_deletes.len()
+                    + self.deleted_by.len(),
+            );
+        }
+        // Dropped with the stream, when the scan is finished or abandoned.
+        let timer = observer
+            .clone()
+            .map(|observer| FileScanTimer::new(observer, open_start));
+
+        let projection = self.projection.clone();
+        let with_positions = projection.contains(ROW_POSITION_FIELD_ID);
+        let keep_deleted = projection.contains(IS_DELETED_FIELD_ID);
+        let file_path = self.task.data_file.file_path.clone();
+        let spec_id = self.task.spec_id;
+        let partition = self.task.data_file.partition.clone();
+
+        let limit = self.limit.clone();
+        let stream = batches
+            .map(
+                move |res: Result<(RecordBatch, Vec<Range<u64>>)>| -> Result<(RecordBatch, u64)> {
+                    // Keep the timer alive with the stream.
+                    let _ = &timer;
+                    let (mut batch, mut positions) = res?;
+                    let read_schema = batch.schema();
+                    let column_of = |id: i32| field_id_index(&read_schema, id);
+                    let mut is_deleted = None;
+                    let mut selection = if keep_deleted {
+                        is_deleted = Some(
+                            match deletes.row_selection(&batch, &positions, 0, column_of)? {
+                                Some(kept) => not(&kept).map_err(|e| {
+                                    Error::new(ErrorKind::ArrowError, format!("{}", e))
+                                })?,
+                                None => BooleanArray::from(vec![false; batch.num_rows()]),
+                            },
+                        );
+                        row_selection(&positions, start, &PositionDeletes::default())
+                    } else {
+                        deletes.row_selection(&batch, &positions, start, column_of)?
+                    };
+                    if let Some(deleted_by) = &mut deleted_by {
+                        let deleted =
+                            match deleted_by.row_selection(&batch, &positions, 0, column_of)? {
+                                Some(kept) => not(&kept),
+                                None => Ok(BooleanArray::from(vec![false; batch.num_rows()])),
+                            }
+                            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+                        selection = Some(match selection {
+                            Some(selection) => and(&selection, &deleted)
+                                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+                            None => deleted,
+                        });
+                    }
+                    // Rows after the limit are dropped before the next row
+                    // to read is computed, so that the position after the
+                    // batch doesn't skip them.
+                    if let Some(limit) = &limit {
+                        let selected = selection
+                            .as_ref()
+                            .map_or(batch.num_rows(), |s| s.true_count());
+                        let num_rows = rows_to_limit(
+                            selection.as_ref(),
+                            batch.num_rows(),
+                            limit.take(selected),
+                        );
+                        if num_rows < batch.num_rows() {
+                            batch = batch.slice(0, num_rows);
+                            selection = selection.map(|s| s.slice(0, num_rows));
+                            is_deleted = is_deleted.map(|d| d.slice(0, num_rows));
+                            truncate_positions(&mut positions, num_rows as u64);
+                        }
+                    }
+                    let rows_read = batch.num_rows();
+                    let next_row = positions.last().map_or(start, |r| r.end);
+                    let mut positions = with_positions.then(|| {
+                        Int64Array::from_iter_values(
+                            positions
+                                .iter()
+                                .flat_map(|r| r.clone())
+                                .map(|pos| pos as i64),
+                        )
+                    });
+                    if let Some(selection) = selection {
+                        batch = filter_record_batch(&batch, &selection)
+                            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+                        positions = positions
+                            .map(|array| filter(&array, &selection))
+                            .transpose()
+                            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?
+                            .map(|array| array.as_primitive::<Int64Type>().clone());
+                        is_deleted = is_deleted
+                            .map(|array| filter(&array, &selection))
+                            .transpose()
+                            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?
+                            .map(|array| array.as_boolean().clone());
+                    }
+                    let batch = projection.project(
+                        &batch,
+                        column_of,
+                        &MetadataValues {
+                            file_path: &file_path,
+                            spec_id,
+                            partition: &partition,
+                            positions,
+                            is_deleted,
+                        },
+                    )?;
+                    if let Some(observer) = &observer {
+                        observer.batch_read(rows_read, batch.num_rows());
+                    }
+                    Ok((batch, next_row))
+                },
+            )
+            .try_filter(|(batch, _)| futures::future::ready(batch.num_rows() > 0));
+
+        match self.limit {
+            Some(limit) => Ok(limit_stream(Box::pin(stream), limit)),
+            None => Ok(Box::pin(stream)),
+        }
+    }
+
+    /// Read the parquet data file from position `start`, each batch comes
+    /// with the positions in data file of its rows.
+    async fn read_parquet(
+        &self,
+        field_ids: &HashSet<i32>,
+        start: u64,
+    ) -> Result<BoxStream<'static, Result<(RecordBatch, Vec<Range<u64>>)>>> {
+        let file_reader = ParquetFileReader::try_new(
+            self.op.clone(),
+            &self.table_location,
+            &self.task.data_file.file_path,
+        )?
+        .with_file_cache(self.file_cache.clone())
+        .with_file_size(self.task.data_file.file_size_in_bytes as u64)
+        .with_config(self.reader_config.clone())
+        .with_observer(self.observer.clone());
+        let mut builder = ParquetRecordBatchStreamBuilder::new(file_reader).await?;
+
+        // Columns of data files written without field ids, e.g. imported
+        // from Hive tables, are resolved by the name mapping of table, and
+        // batches get the mapped field ids. Whole root columns of the
+        // selected fields are read then.
+        let has_field_ids = builder
+            .parquet_schema()
+            .root_schema()
+            .get_fields()
+            .iter()
+            .any(|f| f.get_basic_info().has_id());
+        let (projection_mask, mapped_schema) = if has_field_ids {
+            // Only leaf columns of the selected fields are read, so nested
+            // fields are pruned.
+            let mask = ProjectionMask::leaves(
+                builder.parquet_schema(),
+                leaf_columns(
+                    builder.parquet_schema(),
+                    field_ids,
+                    self.projection.null_value_ids(),
+                ),
+            );
+            (mask, None)
+        } else {
+            let mapped = self.name_mapping.apply(builder.schema());
+            let roots = root_columns(&mapped, field_ids);
+            let projected = mapped
+                .project(&roots)
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            let mask = ProjectionMask::roots(builder.parquet_schema(), roots);
+            (mask, Some(Arc::new(projected)))
+        };
+
+        // Skip the row groups before offset or not starting in the split of
+        // task, so that we don't need to read them. Positions of rows are
+        // tracked by row groups to read.
+        let split = self.task.start..self.task.start + self.task.length;
+        let mut row_groups = vec![];
+        let mut row_group_positions = vec![];
+        let mut first_row = 0;
+        for (idx, row_group) in builder.metadata().row_groups().iter().enumerate() {
+            let num_rows = row_group.num_rows() as u64;
+            let (row_group_start, _) = row_group.column(0).byte_range();
+            if first_row + num_rows > start && split.contains(&row_group_start) {
+                row_groups.push(idx);
+            }
+            row_group_positions.push((first_row, num_rows));
+            first_row += num_rows;
+        }
+        // Row groups whose bloom filters prove that no row matches the
+        // residual filter are skipped too.
+        let row_groups =
+            bloom_filter::prune_row_groups(&mut builder, row_groups, &self.task.residual).await?;
+        let mut row_positions =
+            RowPositions::new(row_groups.iter().map(|idx| row_group_positions[*idx]));
+
+        let stream = builder
+            .with_batch_size(self.batch_size)
+            .with_projection(projection_mask)
+            .with_row_groups(row_groups)
+            .build()?
+            .map(
+                move |res: std::result::Result<RecordBatch, ParquetError>| -> Result<_> {
+                    let mut batch = res?;
+                    if let Some(schema) = &mapped_schema {
+                        batch = with_schema(&batch, schema)?;
+                    }
+                    let positions = row_positions.next_rows(batch.num_rows())?;
+                    Ok((batch, positions))
+                },
+            );
+
+        Ok(stream.boxed())
+    }
+
+    pub fn path(&self) -> &str {
+        &self.task.data_file.file_path
+    }
+
+    /// The task executed by this scan.
+    pub fn task(&self) -> &FileScanTask {
+        &self.task
+    }
+}
+
+/// Path of the file relative to table location, which is the root of
+/// operator.
+fn relative_path<'a>(table_location: &str, file_path: &'a str) -> Result<&'a str> {
+    file_path.strip_prefix(table_location).ok_or_else(|| {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!(
+                "File path {} is not under table location {}",
+                file_path, table_location,
+            ),
+        )
+    })
+}
+
+struct ParquetFileReader {
+    op: Operator,
+    // Relative file path of operator root, it must be a parquet file.
+    path: String,
+    // Absolute file path, it's the key of metadata in file cache.
+    file_path: String,
+    file_cache: Option<FileCacheRef>,
+    // Size of the file if it's known, so that we don't need to stat it.
+    file_size: Option<u64>,
+    config: ParquetReaderConfig,
+    observer: Option<ReadObserverRef>,
+}
+
+impl ParquetFileReader {
+    /// Create a reader of the file at `file_path`, which must be under the
+    /// table location.
+    fn try_new(op: Operator, table_location: &str, file_path: &str) -> Result<Self> {
+        let path = relative_path(table_location, file_path)?;
+
+        Ok(Self {
+            op,
+            path: path.to_string(),
+            file_path: file_path.to_string(),
+            file_cache: None,
+            file_size: None,
+            config: ParquetReaderConfig::default(),
+            observer: None,
+        })
+    }
+
+    /// Read metadata of the parquet file from the file cache if set.
+    fn with_file_cache(mut self, file_cache: Option<FileCacheRef>) -> Self {
+        self.file_cache = file_cache;
+        self
+    }
+
+    /// Set size of the file, it's known from manifest for data files.
+    fn with_file_size(mut self, file_size: u64) -> Self {
+        self.file_size = Some(file_size);
+        self
+    }
+
+    fn with_config(mut self, config: ParquetReaderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Notify the observer of each read request.
+    fn with_observer(mut self, observer: Option<ReadObserverRef>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    async fn read_range(&self, range: Range<usize>) -> parquet::errors::Result<bytes::Bytes> {
+        let data = self
+            .op
+            .read_with(&self.path)
+            .range(range.start as u64..range.end as u64)
+            .await
+            .map_err(|e| ParquetError::General(format!("{}", e)))?;
+        if let Some(observer) = &self.observer {
+            observer.range_read(data.len());
+        }
+        Ok(data.into())
+    }
+
+    /// Read the footer of the parquet file, returns the decoded metadata.
+    ///
+    /// The last `footer_prefetch_size` bytes of the file are fetched in one
+    /// request, metadata is read again only if it's larger than them.
+    ///
+    /// Inspired by https://docs.rs/parquet/latest/parquet/file/footer/fn.parse_metadata.html
+    async fn read_metadata(&self) -> parquet::errors::Result<Arc<ParquetMetaData>> {
+        let file_size = match self.file_size {
+            Some(file_size) => file_size,
+            None => self
+                .op
+                .stat(&self.path)
+                .await
+                .map_err(|e| ParquetError::General(format!("{}", e)))?
+                .content_length(),
+        };
+
+        if file_size < (FOOTER_SIZE as u64) {
+            return Err(ParquetError::General(
+                "Invalid Parquet file. Size is smaller than footer".to_string(),
+            ));
+        }
+
+        let prefetch_size = (self.config.footer_prefetch_size as u64)
+            .max(FOOTER_SIZE as u64)
+            .min(file_size);
+        let tail = self
+            .read_range((file_size - prefetch_size) as usize..file_size as usize)
+            .await?;
+        if tail.len() != prefetch_size as usize {
+            return Err(ParquetError::General(format!(
+                "Invalid Parquet file. Expect {} bytes in the tail, but got {} bytes",
+                prefetch_size,
+                tail.len()
+            )));
+        }
+
+        let mut footer: [u8; FOOTER_SIZE] = [0; FOOTER_SIZE];
+        footer.copy_from_slice(&tail[tail.len() - FOOTER_SIZE..]);
+
+        let metadata_len = decode_footer(&footer)?;
+        let footer_metadata_len = FOOTER_SIZE + metadata_len;
+
+        if footer_metadata_len > file_size as usize {
+            return Err(ParquetError::General(format!(
+                "Invalid Parquet file. Reported metadata length of {} + {} byte footer, but file is only {} bytes",
+                metadata_len,
+                FOOTER_SIZE,
+                file_size
+            )));
+        }
+
+        let metadata = if footer_metadata_len <= tail.len() {
+            decode_metadata(&tail[tail.len() - footer_metadata_len..tail.len() - FOOTER_SIZE])?
+        } else {
+            let start = (file_size as usize) - footer_metadata_len;
+            decode_metadata(&self.read_range(start..start + metadata_len).await?)?
+        };
+        Ok(Arc::new(metadata))
+    }
+}
+
+impl AsyncFileReader for ParquetFileReader {
+    fn get_bytes(
+        &mut self,
+        range: Range<usize>,
+    ) -> BoxFuture<'_, parquet::errors::Result<bytes::Bytes>> {
+        Box::pin(self.read_range(range))
+    }
+
+    /// Get the bytes of ranges, close ranges are merged into one request and
+    /// requests are sent concurrently.
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, parquet::errors::Result<Vec<bytes::Bytes>>> {
+        Box::pin(async move {
+            let merged = merge_ranges(
+                &ranges,
+                self.config.range_merge_gap,
+                self.config.range_max_size,
+            );
+            let this = &*self;
+            let fetched = futures::stream::iter(merged.iter().cloned())
+                .map(|range| this.read_range(range))
+                .buffered(self.config.range_concurrency)
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            Ok(ranges
+                .iter()
+                .map(|range| {
+                    let idx = merged.partition_point(|m| m.start <= range.start) - 1;
+                    let start = merged[idx].start;
+                    fetched[idx].slice(range.start - start..range.end - start)
+                })
+                .collect())
+        })
+    }
+
+    /// Get the metadata of the parquet file, from the file cache if set.
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        Box::pin(async {
+            match &self.file_cache {
+                Some(cache) => {
+                    cache
+                        .parquet_metadata(&self.file_path, self.read_metadata())
+                        .await
+                }
+                None => self.read_metadata().await,
+            }
+        })
+    }
+}
+
+/// Wrap the filter of manifests with the content type, manifests with the
+/// content type are counted in `metrics` as scanned or skipped.
+fn count_manifests<'a>(
+    metrics: &'a Mutex<ScanMetrics>,
+    content: ManifestContentType,
+    filter: impl Fn(&ManifestListEntry) -> bool + 'a,
+) -> impl Fn(&ManifestListEntry) -> bool + 'a {
+    move |manifest| {
+        if manifest.content != content {
+            return false;
+        }
+        let scanned = filter(manifest);
+        let metrics = &mut *metrics.lock().unwrap();
+        let (total, scanned_count, skipped_count) = match content {
+            ManifestContentType::Data => (
+                &mut metrics.total_data_manifests,
+                &mut metrics.scanned_data_manifests,
+                &mut metrics.skipped_data_manifests,
+            ),
+            ManifestContentType::Deletes => (
+                &mut metrics.total_delete_manifests,
+                &mut metrics.scanned_delete_manifests,
+                &mut metrics.skipped_delete_manifests,
+            ),
+        };
+        *total += 1;
+        if scanned {
+            *scanned_count += 1;
+        } else {
+            *skipped_count += 1;
+        }
+        scanned
+    }
+}
+
+/// Stream of the file scans sharing `limit`, file scans after the limit is
+/// reached would return no rows so they are not yielded.
+fn file_scan_stream(file_scans: Vec<FileScan>, limit: Option<RowLimit>) -> FileScanStream {
+    Box::pin(
+        futures::stream::iter(file_scans.into_iter().map(Ok)).take_while(move |_| {
+            futures::future::ready(!limit.as_ref().is_some_and(|limit| limit.is_exhausted()))
+        }),
+    )
+}
+
+/// Max number of rows to return, shared by the file scans of a table scan
+/// which may be read concurrently.
+#[derive(Clone, Debug)]
+struct RowLimit(Arc<AtomicUsize>);
+
+impl RowLimit {
+    fn new(limit: usize) -> Self {
+        Self(Arc::new(AtomicUsize::new(limit)))
+    }
+
+    /// Take up to `rows` rows from the limit, returns the number of rows
+    /// taken.
+    fn take(&self, rows: usize) -> usize {
+        match self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |remaining| {
+                Some(remaining - remaining.min(rows))
+            }) {
+            Ok(remaining) | Err(remaining) => remaining.min(rows),
+        }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.0.load(Ordering::Acquire) == 0
+    }
+}
+
+/// End the stream once the rows of `limit` are taken, the inner stream is not
+/// polled any more.
+///
+/// Batches are not truncated here, since the value coming with a batch, like
+/// the position after it, can't be adjusted for the dropped rows. They are
+/// truncated to the limit by the inner stream instead.
+fn limit_stream<T: Send + 'static>(
+    stream: BoxStream<'static, Result<(RecordBatch, T)>>,
+    limit: RowLimit,
+) -> BoxStream<'static, Result<(RecordBatch, T)>> {
+    Box::pin(futures::stream::unfold(
+        (stream, limit, false),
+        |(mut stream, limit, failed)| async move {
+            if failed || limit.is_exhausted() {
+                return None;
+            }
+            match stream.next().await? {
+                Ok(item) => Some((Ok(item), (stream, limit, false))),
+                // Stop after the error.
+                Err(e) => Some((Err(e), (stream, limit, true))),
+            }
+        },
+    ))
+}
+
+/// Returns the number of read rows to keep so that no more than `limit` rows
+/// are selected by `selection`.
+fn rows_to_limit(selection: Option<&BooleanArray>, num_rows: usize, limit: usize) -> usize {
+    match selection {
+        Some(selection) => selection
+            .iter()
+            .enumerate()
+            .filter(|(_, selected)| *selected == Some(true))
+            .nth(limit)
+            .map_or(num_rows, |(idx, _)| idx),
+        None => num_rows.min(limit),
+    }
+}
+
+/// Keep positions of the first `num_rows` rows.
+fn truncate_positions(positions: &mut Vec<Range<u64>>, mut num_rows: u64) {
+    let mut len = 0;
+    for range in positions.iter_mut() {
+        if num_rows == 0 {
+            break;
+        }
+        range.end = range.end.min(range.start + num_rows);
+        num_rows -= range.end - range.start;
+        len += 1;
+    }
+    positions.truncate(len);
+}
+
+/// Merge ranges whose gap is not larger than `max_gap`, unless the merged
+/// range is larger than `max_size`. Returns merged ranges sorted by start,
+/// each range in `ranges` is covered by one of them.
+fn merge_ranges(ranges: &[Range<usize>], max_gap: usize, max_size: usize) -> Vec<Range<usize>> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(sorted.len());
+    for range in sorted {
+        match merged.last_mut() {
+            Some(last)
+                if range.start <= last.end + max_gap
+                    && range.end.max(last.end) - last.start <= max_size =>
+            {
+                last.end = last.end.max(range.end);
+            }
+            // Overlapping ranges must be merged even if it's too large.
+            Some(last) if range.start < last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{Any, Field, Primitive, PrimitiveValue, Struct};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_limit_stream() {
+        let batch = |n: i64| {
+            RecordBatch::try_from_iter([(
+                "id",
+                Arc::new(Int64Array::from_iter_values(0..n)) as arrow_array::ArrayRef,
+            )])
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+        };
+        // Rows are taken from the limit by the inner stream.
+        let limit = RowLimit::new(5);
+        let stream = Box::pin(
+            futures::stream::iter(vec![
+                batch(3).map(|b| (b, 3)),
+                batch(4).map(|b| (b, 5)),
+                Err(Error::new(ErrorKind::Unexpected, "must not be polled")),
+            ])
+            .map({
+                let limit = limit.clone();
+                move |res| {
+                    res.map(|(b, next_row)| {
+                        let num_rows = limit.take(b.num_rows());
+                        (b.slice(0, num_rows), next_row)
+                    })
+                }
+            }),
+        );
+
+        let batches = limit_stream(stream, limit)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches
+                .iter()
+                .map(|(b, next_row)| (b.num_rows(), *next_row))
+                .collect::<Vec<_>>(),
+            vec![(3, 3), (2, 5)]
+        );
+    }
+
+    #[test]
+    fn test_rows_to_limit() {
+        let selection = BooleanArray::from(vec![true, false, true, true, false, true]);
+        assert_eq!(rows_to_limit(Some(&selection), 6, 2), 3);
+        assert_eq!(rows_to_limit(Some(&selection), 6, 3), 5);
+        assert_eq!(rows_to_limit(Some(&selection), 6, 4), 6);
+        assert_eq!(rows_to_limit(None, 6, 4), 4);
+        assert_eq!(rows_to_limit(None, 6, 7), 6);
+
+        let mut positions = vec![0..3, 10..12];
+        truncate_positions(&mut positions, 4);
+        assert_eq!(positions, vec![0..3, 10..11]);
+        truncate_positions(&mut positions, 2);
+        assert_eq!(positions, vec![0..2]);
+    }
+
+    #[tokio::test]
+    async fn test_limit_with_deletes() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &(0..10).collect::<Vec<_>>()).await?;
+        let delete = test_utils::write_position_delete_file(&table, "d", &[(&a, 1)]).await?;
+        test_utils::commit(&mut table, vec![a], vec![delete]).await?;
+
+        // Rows 0, 2, 3 and 4 are kept in the first batch, the cursor is after
+        // row 3 instead of the batch.
+        let scan = table
+            .new_scan_builder()
+            .with_limit(3)
+            .with_batch_size(5)
+            .build()
+            .unwrap();
+        let batches = scan
+            .scan_with_cursor(&table)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(batches.len(), 1);
+        let (batch, cursor) = &batches[0];
+        assert_eq!(
+            batch
+                .column_by_name("id")
+                .unwrap()
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![0, 2, 3]
+        );
+        assert_eq!(cursor.row, 4);
+
+        let scan = table
+            .new_scan_builder()
+            .with_start_from(*cursor)
+            .build()
+            .unwrap();
+        assert_eq!(scan_ids(&table, scan).await?, vec![4, 5, 6, 7, 8, 9]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_limit_of_files() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut data_files = vec![];
+        let mut deletes = vec![];
+        for i in 0..3 {
+            let ids = (i * 5..i * 5 + 5).collect::<Vec<_>>();
+            let data_file = test_utils::write_data_file(&table, &format!("{i}"), &ids).await?;
+            deletes.push(
+                test_utils::write_position_delete_file(
+                    &table,
+                    &format!("d{i}"),
+                    &[(&data_file, 0)],
+                )
+                .await?,
+            );
+            data_files.push(data_file);
+        }
+        test_utils::commit(&mut table, data_files, deletes).await?;
+
+        // Tasks with deletes don't cover the limit in planning, all of them
+        // are planned while rows are only read up to the limit in total.
+        let scan = table.new_scan_builder().with_limit(6).build().unwrap();
+        assert_eq!(scan.plan_tasks(&table).await?.len(), 3);
+        assert_eq!(scan_ids(&table, scan).await?, vec![1, 2, 3, 4, 6, 7]);
+
+        // No file scan is yielded once the limit is reached.
+        let scan = table.new_scan_builder().with_limit(4).build().unwrap();
+        let mut file_scans = scan.scan(&table).await?;
+        let mut ids = vec![];
+        let mut opened = 0;
+        while let Some(file_scan) = file_scans.try_next().await? {
+            opened += 1;
+            for batch in file_scan.scan().await?.try_collect::<Vec<_>>().await? {
+                let values = batch
+                    .column_by_name("id")
+                    .unwrap()
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec();
+                ids.extend(values);
+            }
+        }
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(opened, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_cursor_json() {
+        let cursor = ScanCursor {
+            snapshot_id: 3051729675574597004,
+            task_index: 2,
+            row: 1024,
+        };
+        let json = cursor.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"snapshot-id":3051729675574597004,"task-index":2,"row":1024}"#
+        );
+        assert_eq!(ScanCursor::from_json(&json).unwrap(), cursor);
+        assert!(ScanCursor::from_json(r#"{"snapshot-id":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        let ranges = vec![10..20, 0..5, 22..30, 100..110, 18..25];
+        assert_eq!(merge_ranges(&ranges, 2, 100), vec![0..5, 10..30, 100..110]);
+        assert_eq!(merge_ranges(&ranges, 5, 100), vec![0..30, 100..110]);
+        assert_eq!(merge_ranges(&ranges, 100, 30), vec![0..30, 100..110]);
+        // Overlapping ranges are merged even if the merged one is too large.
+        assert_eq!(merge_ranges(&ranges, 100, 15), vec![0..5, 10..30, 100..110]);
+        assert_eq!(merge_ranges(&[0..10, 12..20], 5, 15), vec![0..10, 12..20]);
+    }
+
+    async fn scan_ids(table: &Table, scan: TableScan) -> Result<Vec<i64>> {
+        let batches = scan
+            .scan(table)
+            .await?
+            .and_then(|file_scan| file_scan.scan())
+            .try_flatten()
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("id")
+                    .unwrap()
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_plan_with_schema_of_snapshot() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        a.lower_bounds = Some(HashMap::from([(1, 1i64.to_le_bytes().to_vec())]));
+        a.upper_bounds = Some(HashMap::from([(1, 2i64.to_le_bytes().to_vec())]));
+        test_utils::commit(&mut table, vec![a], vec![]).await?;
+
+        // `id` is an int in the current schema, bounds of the data file must
+        // be decoded by the long type in the schema of the snapshot.
+        let mut metadata = table.current_table_metadata().clone();
+        let schema = Schema::new(
+            1,
+            None,
+            Struct::new(vec![
+                Arc::new(Field::optional(1, "id", Any::Primitive(Primitive::Int))),
+                Arc::new(Field::optional(2, "v", Any::Primitive(Primitive::String))),
+            ]),
+        );
+        metadata.schemas.push(schema);
+        metadata.current_schema_id = 1;
+        let table = Table::builder_from_catalog(
+            table.operator(),
+            table.catalog(),
+            metadata,
+            table.current_metadata_location().to_string(),
+            table.table_name().clone(),
+        )
+        .build()?;
+
+        let plan = |value: i64| {
+            table
+                .new_scan_builder()
+                .with_filter(Predicate::Eq(1, PrimitiveValue::Long(value)))
+                .build()
+                .unwrap()
+        };
+        assert_eq!(plan(2).plan_tasks(&table).await?.len(), 1);
+        assert!(plan(5).plan_tasks(&table).await?.is_empty());
+        Ok(())
+    }
+
+    /// Values of column `v` of the table, sorted.
+    async fn scan_values(table: &Table) -> Result<Vec<Option<String>>> {
+        let batches = table
+            .new_scan_builder()
+            .with_column_names(vec!["v".to_string()])
+            .build()
+            .unwrap()
+            .scan(table)
+            .await?
+            .and_then(|file_scan| file_scan.scan())
+            .try_flatten()
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut values = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("v")
+                    .unwrap()
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        values.sort();
+        Ok(values)
+    }
+
+    #[tokio::test]
+    async fn test_scan_without_field_ids() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file_without_field_ids(&table, "a", ["id", "v"], &[1, 2])
+            .await?;
+        // `v` was named `value` when the file was written.
+        let b =
+            test_utils::write_data_file_without_field_ids(&table, "b", ["id", "value"], &[3, 4])
+                .await?;
+        test_utils::commit(&mut table, vec![a, b], vec![]).await?;
+
+        // Columns are resolved by the current names of fields by default.
+        let mut ids = scan_ids(&table, table.new_scan_builder().build().unwrap()).await?;
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        let values = scan_values(&table).await?;
+        assert_eq!(
+            values,
+            vec![None, None, Some("v1".to_string()), Some("v2".to_string())]
+        );
+
+        // The name mapping of table resolves the previous name.
+        let mut metadata = table.current_table_metadata().clone();
+        metadata.properties = Some(HashMap::from([(
+            DEFAULT_NAME_MAPPING.to_string(),
+            r#"[{"field-id": 1, "names": ["id"]}, {"field-id": 2, "names": ["v", "value"]}]"#
+                .to_string(),
+        )]));
+        let table = Table::builder_from_catalog(
+            table.operator(),
+            table.catalog(),
+            metadata,
+            table.current_metadata_location().to_string(),
+            table.table_name().clone(),
+        )
+        .build()?;
+        let values = scan_values(&table).await?;
+        assert_eq!(
+            values,
+            ["v1", "v2", "v3", "v4"]
+                .into_iter()
+                .map(|v| Some(v.to_string()))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scan_orc() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_orc_data_file(&table, "a", &[&[1, 2, 3], &[4, 5]], None, true)
+            .await?;
+        // `v` was named `value` when the file was written.
+        let b =
+            test_utils::write_orc_data_file(&table, "b", &[&[6, 7]], Some(["id", "value"]), false)
+                .await?;
+        test_utils::commit(&mut table, vec![a.clone(), b], vec![]).await?;
+        let position_deletes =
+            test_utils::write_position_delete_file(&table, "pd", &[(&a, 3)]).await?;
+        let equality_deletes = test_utils::write_equality_delete_file(&table, "ed", &[2]).await?;
+        test_utils::commit(&mut table, vec![], vec![position_deletes, equality_deletes]).await?;
+
+        let mut ids = scan_ids(&table, table.new_scan_builder().build().unwrap()).await?;
+        ids.sort();
+        assert_eq!(ids, vec![1, 3, 5, 6, 7]);
+        assert_eq!(
+            scan_values(&table).await?,
+            vec![
+                None,
+                None,
+                Some("v1".to_string()),
+                Some("v3".to_string()),
+                Some("v5".to_string())
+            ]
+        );
+
+        // Stripes whose statistics don't match the residual filter are
+        // skipped.
+        let scan = table
+            .new_scan_builder()
+            .with_filter(Predicate::Eq(1, PrimitiveValue::Long(5)))
+            .build()
+            .unwrap();
+        let mut ids = scan_ids(&table, scan).await?;
+        ids.sort();
+        assert_eq!(ids, vec![5]);
+
+        // Only stripes starting in the split of task are read.
+        let scan = table.new_scan_builder().build().unwrap();
+        let mut task = scan
+            .plan_tasks(&table)
+            .await?
+            .into_iter()
+            .find(|task| task.data_file.file_path == a.file_path)
+            .unwrap();
+        let second_stripe = a.split_offsets.as_ref().unwrap()[1] as u64;
+        task.length = second_stripe - task.start;
+        let batches = scan
+            .open_task(&table, task.clone())?
+            .scan()
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        let ids = batches
+            .iter()
+            .flat_map(|b| b.column(0).as_primitive::<Int64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 3]);
+        task.start = second_stripe;
+        task.length = a.file_size_in_bytes as u64 - second_stripe;
+        let batches = scan
+            .open_task(&table, task)?
+            .scan()
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        let ids = batches
+            .iter()
+            .flat_map(|b| b.column(0).as_primitive::<Int64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![5]);
+
+        // The name mapping of table resolves the previous name.
+        let mut metadata = table.current_table_metadata().clone();
+        metadata.properties = Some(HashMap::from([(
+            DEFAULT_NAME_MAPPING.to_string(),
+            r#"[{"field-id": 1, "names": ["id"]}, {"field-id": 2, "names": ["v", "value"]}]"#
+                .to_string(),
+        )]));
+        let table = Table::builder_from_catalog(
+            table.operator(),
+            table.catalog(),
+            metadata,
+            table.current_metadata_location().to_string(),
+            table.table_name().clone(),
+        )
+        .build()?;
+        assert_eq!(
+            scan_values(&table).await?,
+            ["v1", "v3", "v5", "v6", "v7"]
+                .into_iter()
+                .map(|v| Some(v.to_string()))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incremental_append_scan() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a], vec![]).await?;
+        let b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        let s2 = test_utils::commit(&mut table, vec![b], vec![]).await?;
+        let c = test_utils::write_data_file(&table, "c", &[4, 5]).await?;
+        let s3 = test_utils::commit(&mut table, vec![c], vec![]).await?;
+        let metadata = table.current_table_metadata();
+        assert_eq!(metadata.snapshot(s3).unwrap().operation(), Some("append"));
+
+        // Data files appended by snapshots along the parent chain.
+        let scan = |from| {
+            table
+                .new_scan_builder()
+                .with_from_snapshot_id(from)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(scan_ids(&table, scan(s1)).await?, vec![3, 4, 5]);
+        assert_eq!(scan_ids(&table, scan(s2)).await?, vec![4, 5]);
+        assert!(scan_ids(&table, scan(s3)).await?.is_empty());
+        let scan = table
+            .new_scan_builder()
+            .with_snapshot_id(s2)
+            .with_from_snapshot_id(s1)
+            .build()
+            .unwrap();
+        assert_eq!(scan_ids(&table, scan).await?, vec![3]);
+
+        // The from snapshot must be an ancestor of the scanned snapshot.
+        let scan = table
+            .new_scan_builder()
+            .with_snapshot_id(s1)
+            .with_from_snapshot_id(s2)
+            .build()
+            .unwrap();
+        assert!(scan_ids(&table, scan).await.is_err());
+        let scan = table
+            .new_scan_builder()
+            .with_from_snapshot_id(s3 + 100)
+            .build()
+            .unwrap();
+        assert!(scan_ids(&table, scan).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incremental_append_scan_rejects_non_append() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a.clone()], vec![]).await?;
+
+        // Snapshot adding delete files.
+        let delete = test_utils::write_equality_delete_file(&table, "d", &[1]).await?;
+        let s2 = test_utils::commit(&mut table, vec![], vec![delete]).await?;
+        assert_eq!(
+            table
+                .current_table_metadata()
+                .snapshot(s2)
+                .unwrap()
+                .operation(),
+            Some("delete")
+        );
+        let scan = table
+            .new_scan_builder()
+            .with_from_snapshot_id(s1)
+            .build()
+            .unwrap();
+        let err = scan_ids(&table, scan).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergFeatureUnsupported);
+
+        // Snapshot removing data files, whose summary records no operation.
+        let b = test_utils::write_data_file(&table, "b", &[1, 2]).await?;
+        test_utils::rewrite(&mut table, "replace", &[a], vec![b]).await?;
+        let mut metadata = table.current_table_metadata().clone();
+        let snapshot = metadata.snapshots.as_mut().unwrap().last_mut().unwrap();
+        snapshot.summary.clear();
+        let s3 = snapshot.snapshot_id;
+        let table = Table::builder_from_catalog(
+            table.operator(),
+            table.catalog(),
+            metadata,
+            table.current_metadata_location().to_string(),
+            table.table_name().clone(),
+        )
+        .build()?;
+        let scan = table
+            .new_scan_builder()
+            .with_from_snapshot_id(s2)
+            .build()
+            .unwrap();
+        let err = scan_ids(&table, scan).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergFeatureUnsupported);
+        assert!(err.to_string().contains(&format!("snapshot {s3}")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incremental_append_scan_of_added_entries() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a], vec![]).await?;
+        let b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        let s2 = test_utils::commit(&mut table, vec![b], vec![]).await?;
+
+        // An append merging the manifests of earlier snapshots lists their
+        // files as existing, only the entries it added are scanned.
+        let c = test_utils::write_data_file(&table, "c", &[4]).await?;
+        let s3 = test_utils::rewrite(&mut table, "append", &[], vec![c]).await?;
+        let scan = |from| {
+            table
+                .new_scan_builder()
+                .with_from_snapshot_id(from)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(scan_ids(&table, scan(s1)).await?, vec![3, 4]);
+        assert_eq!(scan_ids(&table, scan(s2)).await?, vec![4]);
+        assert!(scan_ids(&table, scan(s3)).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! Name mapping resolves field ids of columns in data files written without
+//! field ids, e.g. files imported from Hive tables.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow_array::{make_array, ArrayRef, RecordBatch, RecordBatchOptions};
+use arrow_data::ArrayData;
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+use serde::Deserialize;
+
+use crate::types::{Any, Field, Schema, TableMetadata};
+use crate::{Error, ErrorKind, Result};
+
+/// Table property of the default name mapping, in json.
+pub const DEFAULT_NAME_MAPPING: &str = "schema.name-mapping.default";
+
+/// A field mapped from names, the names are the current and previous names of
+/// the field.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct MappedField {
+    /// Fields without id are never read.
+    #[serde(default)]
+    field_id: Option<i32>,
+    names: Vec<String>,
+    /// Nested fields, elements of lists are named `element`, keys and
+    /// values of maps are named `key` and `value`.
+    #[serde(default)]
+    fields: Vec<MappedField>,
+}
+
+/// Mapping from column names in data files to field ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NameMapping {
+    fields: Vec<MappedField>,
+}
+
+impl NameMapping {
+    /// The name mapping of table, which is `schema.name-mapping.default` of
+    /// table properties, or the current names of the current schema if it's
+    /// not set.
+    pub(crate) fn try_new(metadata: &TableMetadata) -> Result<Self> {
+        match metadata
+            .properties
+            .as_ref()
+            .and_then(|p| p.get(DEFAULT_NAME_MAPPING))
+        {
+            Some(json) => Ok(Self {
+                fields: serde_json::from_str(json).map_err(|e| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid {}: {}", DEFAULT_NAME_MAPPING, e),
+                    )
+                })?,
+            }),
+            None => Ok(Self::from_schema(metadata.current_schema()?)),
+        }
+    }
+
+    /// Map the current names of fields in the schema.
+    pub(crate) fn from_schema(schema: &Schema) -> Self {
+        Self {
+            fields: schema.fields().iter().map(|f| mapped_field(f)).collect(),
+        }
+    }
+
+    /// Set field ids of the schema read from a data file, columns which are
+    /// not mapped are left without field id.
+    pub(crate) fn apply(&self, schema: &ArrowSchema) -> SchemaRef {
+        Arc::new(ArrowSchema::new_with_metadata(
+            schema
+                .fields()
+                .iter()
+                .map(|f| map_field(f, &self.fields))
+                .collect::<Vec<_>>(),
+            schema.metadata().clone(),
+        ))
+    }
+}
+
+fn mapped_field(field: &Field) -> MappedField {
+    MappedField {
+        field_id: Some(field.id),
+        names: vec![field.name.clone()],
+        fields: mapped_fields(&field.field_type),
+    }
+}
+
+fn mapped_fields(field_type: &Any) -> Vec<MappedField> {
+    match field_type {
+        Any::Struct(s) => s.fields().iter().map(|f| mapped_field(f)).collect(),
+        Any::List(l) => vec![MappedField {
+            field_id: Some(l.element_id),
+            names: vec!["element".to_string()],
+            fields: mapped_fields(&l.element_type),
+        }],
+        Any::Map(m) => vec![
+            MappedField {
+                field_id: Some(m.key_id),
+                names: vec!["key".to_string()],
+                fields: mapped_fields(&m.key_type),
+            },
+            MappedField {
+                field_id: Some(m.value_id),
+                names: vec!["value".to_string()],
+                fields: mapped_fields(&m.value_type),
+            },
+        ],
+        Any::Primitive(_) => vec![],
+    }
+}
+
+/// Set the field id of the field found by `name` in `mapped`, nested fields
+/// are mapped by the nested fields of it.
+fn map_named_field(field: &ArrowField, name: &str, mapped: &[MappedField]) -> ArrowField {
+    let mapped = mapped.iter().find(|m| m.names.iter().any(|n| n == name));
+    let nested = mapped.map_or(&[][..], |m| m.fields.as_slice());
+    let data_type = match field.data_type() {
+        DataType::Struct(fields) => DataType::Struct(
+            fields
+                .iter()
+                .map(|f| map_field(f, nested))
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        DataType::List(element) => {
+            DataType::List(Arc::new(map_named_field(element, "element", nested)))
+        }
+        DataType::LargeList(element) => {
+            DataType::LargeList(Arc::new(map_named_field(element, "element", nested)))
+        }
+        DataType::Map(entries, sorted) => {
+            let DataType::Struct(kv) = entries.data_type() else {
+                return field.clone();
+            };
+            let kv = kv
+                .iter()
+                .zip(["key", "value"])
+                .map(|(f, name)| map_named_field(f, name, nested))
+                .collect::<Vec<_>>();
+            DataType::Map(
+                Arc::new(
+                    entries
+                        .as_ref()
+                        .clone()
+                        .with_data_type(DataType::Struct(kv.into())),
+                ),
+                *sorted,
+            )
+        }
+        data_type => data_type.clone(),
+    };
+
+    let mut metadata = field.metadata().clone();
+    match mapped.and_then(|m| m.field_id) {
+        Some(field_id) => {
+            metadata.insert(PARQUET_FIELD_ID_META_KEY.to_string(), field_id.to_string())
+        }
+        None => metadata.remove(PARQUET_FIELD_ID_META_KEY),
+    };
+    field
+        .clone()
+        .with_data_type(data_type)
+        .with_metadata(metadata)
+}
+
+fn map_field(field: &ArrowField, mapped: &[MappedField]) -> ArrowField {
+    map_named_field(field, field.name(), mapped)
+}
+
+/// Cast the batch to the schema with the same structure, which only differs
+/// in metadata of fields.
+pub(crate) fn with_schema(batch: &RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields().iter())
+        .map(|(column, field)| {
+            Ok(make_array(with_data_type(
+                column.to_data(),
+                field.data_type(),
+            )?))
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+    RecordBatch::try_new_with_options(
+        schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+fn with_data_type(data: ArrayData, data_type: &DataType) -> Result<ArrayData> {
+    let child_types = match data_type {
+        DataType::Struct(fields) => fields.iter().map(|f| f.data_type()).collect(),
+        DataType::List(f) | DataType::LargeList(f) | DataType::Map(f, _) => vec![f.data_type()],
+        _ => vec![],
+    };
+    let child_data = data
+        .child_data()
+        .iter()
+        .zip(child_types)
+        .map(|(child, data_type)| with_data_type(child.clone(), data_type))
+        .collect::<Result<Vec<_>>>()?;
+    data.into_builder()
+        .data_type(data_type.clone())
+        .child_data(child_data)
+        .build()
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+/// Indices of root fields containing any of `field_ids`, either themselves
+/// or their nested fields.
+pub(crate) fn root_columns(schema: &ArrowSchema, field_ids: &HashSet<i32>) -> Vec<usize> {
+    fn contains(field: &ArrowField, field_ids: &HashSet<i32>) -> bool {
+        let selected = field
+            .metadata()
+            .get(PARQUET_FIELD_ID_META_KEY)
+            .and_then(|id| id.parse().ok())
+            .is_some_and(|id| field_ids.contains(&id));
+        selected
+            || match field.data_type() {
+                DataType::Struct(fields) => fields.iter().any(|f| contains(f, field_ids)),
+                DataType::List(f) | DataType::LargeList(f) | DataType::Map(f, _) => {
+                    contains(f, field_ids)
+                }
+                _ => false,
+            }
+    }
+
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| contains(field, field_ids))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{List, Map, Primitive, Struct};
+
+    fn field_id(field: &ArrowField) -> Option<&str> {
+        field
+            .metadata()
+            .get(PARQUET_FIELD_ID_META_KEY)
+            .map(|id| id.as_str())
+    }
+
+    #[test]
+    fn test_apply_nested() {
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Arc::new(Field::optional(1, "id", Any::Primitive(Primitive::Long))),
+                Arc::new(Field::optional(
+                    2,
+                    "tags",
+                    Any::List(List {
+                        element_id: 3,
+                        element_required: false,
+                        element_type: Box::new(Any::Primitive(Primitive::String)),
+                    }),
+                )),
+                Arc::new(Field::optional(
+                    4,
+                    "attrs",
+                    Any::Map(Map {
+                        key_id: 5,
+                        key_type: Box::new(Any::Primitive(Primitive::String)),
+                        value_id: 6,
+                        value_required: false,
+                        value_type: Box::new(Any::Struct(Arc::new(Struct::new(vec![Arc::new(
+                            Field::optional(7, "n", Any::Primitive(Primitive::Int)),
+                        )])))),
+                    }),
+                )),
+            ]),
+        );
+        let mapping = NameMapping::from_schema(&schema);
+
+        // Parquet files name elements of lists `item` or `element`, and
+        // entries of maps `key_value` or `entries`.
+        let file_schema = ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int64, true),
+            ArrowField::new(
+                "tags",
+                DataType::List(Arc::new(ArrowField::new("element", DataType::Utf8, true))),
+                true,
+            ),
+            ArrowField::new(
+                "attrs",
+                DataType::Map(
+                    Arc::new(ArrowField::new(
+                        "key_value",
+                        DataType::Struct(
+                            vec![
+                                ArrowField::new("key", DataType::Utf8, false),
+                                ArrowField::new(
+                                    "value",
+                                    DataType::Struct(
+                                        vec![ArrowField::new("n", DataType::Int32, true)].into(),
+                                    ),
+                                    true,
+                                ),
+                            ]
+                            .into(),
+                        ),
+                        false,
+                    )),
+                    false,
+                ),
+                true,
+            ),
+            ArrowField::new("unknown", DataType::Int64, true),
+        ]);
+        let mapped = mapping.apply(&file_schema);
+
+        let ids = mapped
+            .fields()
+            .iter()
+            .map(|f| field_id(f))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![Some("1"), Some("2"), Some("4"), None]);
+        let DataType::List(element) = mapped.field(1).data_type() else {
+            unreachable!()
+        };
+        assert_eq!(field_id(element), Some("3"));
+        let DataType::Map(entries, _) = mapped.field(2).data_type() else {
+            unreachable!()
+        };
+        let DataType::Struct(kv) = entries.data_type() else {
+            unreachable!()
+        };
+        assert_eq!(field_id(&kv[0]), Some("5"));
+        assert_eq!(field_id(&kv[1]), Some("6"));
+        let DataType::Struct(value) = kv[1].data_type() else {
+            unreachable!()
+        };
+        assert_eq!(field_id(&value[0]), Some("7"));
+
+        assert_eq!(root_columns(&mapped, &HashSet::from([7])), vec![2]);
+        assert_eq!(root_columns(&mapped, &HashSet::from([1, 3])), vec![0, 1]);
+        assert!(root_columns(&mapped, &HashSet::from([8])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_name_mapping() -> Result<()> {
+        let (_dir, table) = test_utils::create_table().await?;
+        let mut metadata = table.current_table_metadata().clone();
+        assert_eq!(
+            NameMapping::try_new(&metadata)?,
+            NameMapping::from_schema(metadata.current_schema()?)
+        );
+
+        metadata.properties = Some(HashMap::from([(
+            DEFAULT_NAME_MAPPING.to_string(),
+            "{".to_string(),
+        )]));
+        assert_eq!(
+            NameMapping::try_new(&metadata).unwrap_err().kind(),
+            ErrorKind::IcebergDataInvalid
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! Observer of the read path, it's notified when data files are read so that
+//! metrics of reads can be collected.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Observer of reading data files by [`super::FileScan`], it's set by
+/// [`super::TableScanBuilder::with_observer`].
+pub trait ReadObserver: Send + Sync {
+    /// A data file is opened, `elapsed` is spent on loading its deletes and
+    /// opening it.
+    fn file_opened(&self, elapsed: Duration);
+    /// `bytes` bytes are read from a data file in one request.
+    fn range_read(&self, bytes: usize);
+    /// Delete files are applied to a data file.
+    fn deletes_applied(&self, delete_files: usize);
+    /// A batch of `rows_read` rows is read from a data file, `rows_produced`
+    /// of them are left after deletes and offset.
+    fn batch_read(&self, rows_read: usize, rows_produced: usize);
+    /// Scan of a data file is finished or dropped, `elapsed` since it's
+    /// started.
+    fn file_scanned(&self, elapsed: Duration);
+}
+
+pub type ReadObserverRef = Arc<dyn ReadObserver>;
+
+/// Timer of a data file scan, [`ReadObserver::file_scanned`] is called when
+/// it's dropped with the stream of the scan.
+pub(crate) struct FileScanTimer {
+    observer: ReadObserverRef,
+    start: Instant,
+}
+
+impl FileScanTimer {
+    pub(crate) fn new(observer: ReadObserverRef, start: Instant) -> Self {
+        Self { observer, start }
+    }
+}
+
+impl Drop for FileScanTimer {
+    fn drop(&mut self) {
+        self.observer.file_scanned(self.start.elapsed());
+    }
+}
@@ -0,0 +1,1704 @@
+//! Reader of orc data files, like data files of tables migrated from Hive.
+//! Stripes are decoded into arrow batches with the columns of the selected
+//! fields, which carry field ids in their metadata like columns read from
+//! parquet files.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Date32Array, Decimal128Array, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, LargeBinaryArray, ListArray, MapArray, RecordBatch,
+    RecordBatchOptions, StringArray, StructArray, TimestampMicrosecondArray, UInt32Array,
+};
+use arrow_buffer::{Buffer, NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use arrow_select::take::take;
+use chrono::NaiveDate;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use opendal::Operator;
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+
+use super::name_mapping::{root_columns, NameMapping};
+use super::{Predicate, ReadObserverRef};
+use crate::types::PrimitiveValue;
+use crate::{Error, ErrorKind, Result};
+
+/// Attribute of orc types written by Iceberg, which is the field id.
+const ICEBERG_ID_ATTRIBUTE: &str = "iceberg.id";
+/// Size of the tail read in the first request, it usually covers the
+/// postscript, the footer and the metadata.
+const TAIL_PREFETCH_SIZE: u64 = 16 * 1024;
+/// Seconds from unix epoch to 2015-01-01 00:00:00, the epoch of orc
+/// timestamps.
+const ORC_EPOCH_SECONDS: i64 = 1_420_070_400;
+
+/// Reads rows of an orc data file into arrow batches.
+///
+/// Columns are resolved by the `iceberg.id` attributes of orc types, or by
+/// the name mapping of table if the file is written without them. Whole root
+/// columns of the selected fields are read, and timestamps are read as UTC.
+pub(crate) struct OrcFileReader {
+    op: Operator,
+    // Relative file path of operator root, it must be an orc file.
+    path: String,
+    file_size: u64,
+    name_mapping: Arc<NameMapping>,
+    observer: Option<ReadObserverRef>,
+}
+
+impl OrcFileReader {
+    /// Create a reader of the file at relative path `path`.
+    pub(crate) fn new(
+        op: Operator,
+        path: &str,
+        file_size: u64,
+        name_mapping: Arc<NameMapping>,
+    ) -> Self {
+        Self {
+            op,
+            path: path.to_string(),
+            file_size,
+            name_mapping,
+            observer: None,
+        }
+    }
+
+    /// Notify the observer of each read request.
+    pub(crate) fn with_observer(mut self, observer: Option<ReadObserverRef>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Read columns of `field_ids` from position `start`, in batches of at
+    /// most `batch_size` rows. Each batch comes with the positions in data
+    /// file of its rows.
+    ///
+    /// Only stripes starting in `split` are read, and stripes whose column
+    /// statistics prove that no row matches `residual` are skipped.
+    pub(crate) async fn read(
+        self,
+        field_ids: &HashSet<i32>,
+        split: Range<u64>,
+        residual: &Predicate,
+        start: u64,
+        batch_size: usize,
+    ) -> Result<BoxStream<'static, Result<(RecordBatch, Vec<Range<u64>>)>>> {
+        let tail = self.read_tail().await?;
+
+        let mut schema = file_schema(&tail.footer.types)?;
+        let has_field_ids = schema
+            .fields()
+            .iter()
+            .any(|f| f.metadata().contains_key(PARQUET_FIELD_ID_META_KEY));
+        if !has_field_ids {
+            schema = self.name_mapping.apply(&schema).as_ref().clone();
+        }
+        let columns_of_fields = column_field_ids(&tail.footer.types, &schema)?;
+        let roots = root_columns(&schema, field_ids);
+        let projected_schema: SchemaRef = Arc::new(
+            schema
+                .project(&roots)
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+        );
+        let root_column_ids = roots
+            .iter()
+            .map(|idx| tail.footer.types[0].subtypes[*idx])
+            .collect::<Vec<_>>();
+
+        // Stripes before `start` or not starting in the split are skipped,
+        // positions of rows are tracked by the first rows of stripes.
+        let literal_columns = residual
+            .literal_field_ids()
+            .into_iter()
+            .filter_map(|field_id| columns_of_fields.get(&field_id).map(|c| (field_id, *c)))
+            .collect::<HashMap<_, _>>();
+        let mut stripes = vec![];
+        let mut first_row = 0;
+        for (idx, stripe) in tail.footer.stripes.iter().enumerate() {
+            let num_rows = stripe.number_of_rows;
+            let may_match = literal_columns.is_empty()
+                || residual.may_match_literals(&|field_id, literal| {
+                    let statistics = tail
+                        .stripe_statistics
+                        .get(idx)
+                        .and_then(|columns| columns.get(literal_columns[&field_id]));
+                    statistics.is_none_or(|s| s.may_contain(literal))
+                });
+            if first_row + num_rows > start && split.contains(&stripe.offset) && may_match {
+                stripes.push((stripe.clone(), first_row));
+            }
+            first_row += num_rows;
+        }
+
+        let op = self.op;
+        let path = self.path;
+        let observer = self.observer;
+        let types = Arc::new(tail.footer.types);
+        let compression = tail.compression;
+        let stream = futures::stream::iter(stripes)
+            .then(move |(stripe, first_row)| {
+                let op = op.clone();
+                let path = path.clone();
+                let observer = observer.clone();
+                let types = types.clone();
+                let schema = projected_schema.clone();
+                let root_column_ids = root_column_ids.clone();
+                async move {
+                    let range = stripe.offset
+                        ..stripe.offset
+                            + stripe.index_length
+                            + stripe.data_length
+                            + stripe.footer_length;
+                    let data = op.read_with(&path).range(range).await?;
+                    if let Some(observer) = &observer {
+                        observer.range_read(data.len());
+                    }
+                    let batch = decode_stripe(
+                        &data,
+                        &stripe,
+                        &types,
+                        compression,
+                        &root_column_ids,
+                        schema,
+                    )?;
+
+                    // Rows before `start` are skipped.
+                    let offset = start.saturating_sub(first_row) as usize;
+                    let batches = (offset..batch.num_rows())
+                        .step_by(batch_size.max(1))
+                        .map(|row| {
+                            let len = batch_size.min(batch.num_rows() - row);
+                            let rows = first_row + row as u64..first_row + (row + len) as u64;
+                            Ok((batch.slice(row, len), vec![rows]))
+                        })
+                        .collect::<Vec<_>>();
+                    Result::Ok(futures::stream::iter(batches))
+                }
+            })
+            .try_flatten();
+
+        Ok(stream.boxed())
+    }
+
+    /// Read the postscript, the footer and the metadata at the end of file.
+    async fn read_tail(&self) -> Result<Tail> {
+        let invalid = |msg: &str| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid orc file {}: {}", self.path, msg),
+            )
+        };
+
+        let prefetch = self.file_size.min(TAIL_PREFETCH_SIZE);
+        let mut tail = self
+            .read_range(self.file_size - prefetch..self.file_size)
+            .await?;
+        let postscript_len = *tail.last().ok_or_else(|| invalid("file is empty"))? as usize;
+        if postscript_len + 1 > tail.len() {
+            return Err(invalid("postscript is truncated"));
+        }
+        let postscript =
+            PostScript::decode(&tail[tail.len() - 1 - postscript_len..tail.len() - 1])?;
+        let compression =
+            Compression::try_new(postscript.compression, postscript.compression_block_size)?;
+
+        let tail_len =
+            1 + postscript_len as u64 + postscript.footer_length + postscript.metadata_length;
+        if tail_len > self.file_size {
+            return Err(invalid("footer is larger than the file"));
+        }
+        if tail_len > tail.len() as u64 {
+            tail = self
+                .read_range(self.file_size - tail_len..self.file_size)
+                .await?;
+        }
+        let footer_end = tail.len() - 1 - postscript_len;
+        let footer_start = footer_end - postscript.footer_length as usize;
+        let metadata_start = footer_start - postscript.metadata_length as usize;
+        let footer = Footer::decode(&compression.decompress(&tail[footer_start..footer_end])?)?;
+        let stripe_statistics =
+            decode_metadata(&compression.decompress(&tail[metadata_start..footer_start])?)?;
+
+        Ok(Tail {
+            compression,
+            footer,
+            stripe_statistics,
+        })
+    }
+
+    async fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        let data = self.op.read_with(&self.path).range(range).await?;
+        if let Some(observer) = &self.observer {
+            observer.range_read(data.len());
+        }
+        Ok(data)
+    }
+}
+
+/// Decoded tail of an orc file.
+struct Tail {
+    compression: Compression,
+    footer: Footer,
+    /// Column statistics of each stripe, indexed by column id.
+    stripe_statistics: Vec<Vec<ColumnStatistics>>,
+}
+
+/// Arrow schema of the root struct of orc types, fields written by Iceberg
+/// carry field ids in their metadata.
+fn file_schema(types: &[OrcType]) -> Result<ArrowSchema> {
+    let root = types
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::IcebergDataInvalid, "Orc file has no types"))?;
+    if root.kind != kind::STRUCT {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Root type of orc file is not a struct",
+        ));
+    }
+    let fields = root
+        .subtypes
+        .iter()
+        .zip(&root.field_names)
+        .map(|(column, name)| orc_field(types, *column, name, true))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ArrowSchema::new(fields))
+}
+
+fn orc_field(types: &[OrcType], column: usize, name: &str, nullable: bool) -> Result<ArrowField> {
+    let ty = orc_type(types, column)?;
+    let child = |idx: usize, name: &str, nullable: bool| {
+        let column = *ty.subtypes.get(idx).ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Orc type of column {} misses subtypes", column),
+            )
+        })?;
+        orc_field(types, column, name, nullable)
+    };
+    let data_type = match ty.kind {
+        kind::BOOLEAN => DataType::Boolean,
+        kind::BYTE => DataType::Int8,
+        kind::SHORT => DataType::Int16,
+        kind::INT => DataType::Int32,
+        kind::LONG => DataType::Int64,
+        kind::FLOAT => DataType::Float32,
+        kind::DOUBLE => DataType::Float64,
+        kind::STRING | kind::VARCHAR | kind::CHAR => DataType::Utf8,
+        kind::BINARY => DataType::LargeBinary,
+        kind::DATE => DataType::Date32,
+        kind::DECIMAL => {
+            let precision = if ty.precision == 0 { 38 } else { ty.precision };
+            DataType::Decimal128(precision as u8, ty.scale as i8)
+        }
+        kind::TIMESTAMP => DataType::Timestamp(TimeUnit::Microsecond, None),
+        kind::TIMESTAMP_INSTANT => {
+            DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+        }
+        kind::LIST => DataType::List(Arc::new(child(0, "element", true)?)),
+        kind::MAP => DataType::Map(
+            Arc::new(ArrowField::new(
+                "key_value",
+                DataType::Struct(vec![child(0, "key", false)?, child(1, "value", true)?].into()),
+                false,
+            )),
+            false,
+        ),
+        kind::STRUCT => DataType::Struct(
+            ty.subtypes
+                .iter()
+                .zip(&ty.field_names)
+                .map(|(column, name)| orc_field(types, *column, name, true))
+                .collect::<Result<Vec<_>>>()?
+                .into(),
+        ),
+        // Unions are never mapped to table fields, they are read as nulls.
+        _ => DataType::Null,
+    };
+
+    let field = ArrowField::new(name, data_type, nullable);
+    Ok(match ty.attributes.get(ICEBERG_ID_ATTRIBUTE) {
+        Some(id) => field.with_metadata(HashMap::from([(
+            PARQUET_FIELD_ID_META_KEY.to_string(),
+            id.clone(),
+        )])),
+        None => field,
+    })
+}
+
+fn orc_type(types: &[OrcType], column: usize) -> Result<&OrcType> {
+    types.get(column).ok_or_else(|| {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Orc type of column {} not found", column),
+        )
+    })
+}
+
+/// Map field ids in `schema` to orc column ids, the schema has the structure
+/// of orc types.
+fn column_field_ids(types: &[OrcType], schema: &ArrowSchema) -> Result<HashMap<i32, usize>> {
+    fn visit(
+        types: &[OrcType],
+        column: usize,
+        field: &ArrowField,
+        ids: &mut HashMap<i32, usize>,
+    ) -> Result<()> {
+        if let Some(id) = field
+            .metadata()
+            .get(PARQUET_FIELD_ID_META_KEY)
+            .and_then(|id| id.parse().ok())
+        {
+            ids.insert(id, column);
+        }
+        let subtypes = &orc_type(types, column)?.subtypes;
+        let children = match field.data_type() {
+            DataType::Struct(fields) => fields.iter().cloned().collect(),
+            DataType::List(element) => vec![element.clone()],
+            DataType::Map(entries, _) => match entries.data_type() {
+                DataType::Struct(kv) => kv.iter().cloned().collect(),
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        for (column, field) in subtypes.iter().zip(children) {
+            visit(types, *column, &field, ids)?;
+        }
+        Ok(())
+    }
+
+    let mut ids = HashMap::new();
+    for (column, field) in types[0].subtypes.iter().zip(schema.fields()) {
+        visit(types, *column, field, &mut ids)?;
+    }
+    Ok(ids)
+}
+
+/// Decode the root columns of the stripe into a batch of `schema`.
+fn decode_stripe(
+    data: &[u8],
+    stripe: &StripeInformation,
+    types: &[OrcType],
+    compression: Compression,
+    root_column_ids: &[usize],
+    schema: SchemaRef,
+) -> Result<RecordBatch> {
+    let footer_start = (stripe.index_length + stripe.data_length) as usize;
+    let footer_end = footer_start + stripe.footer_length as usize;
+    if footer_end > data.len() {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Stripe at offset {} is truncated", stripe.offset),
+        ));
+    }
+    let footer = StripeFooter::decode(&compression.decompress(&data[footer_start..footer_end])?)?;
+
+    // Streams are stored in the order of the stripe footer.
+    let mut streams = HashMap::new();
+    let mut offset = 0;
+    for stream in &footer.streams {
+        let end = offset + stream.length as usize;
+        streams.insert((stream.column, stream.kind), offset..end);
+        offset = end;
+    }
+    let reader = StripeReader {
+        data,
+        streams,
+        encodings: footer.columns,
+        types,
+        compression,
+    };
+
+    let num_rows = stripe.number_of_rows as usize;
+    let columns = root_column_ids
+        .iter()
+        .zip(schema.fields())
+        .map(|(column, field)| reader.decode(*column, field.data_type(), num_rows))
+        .collect::<Result<Vec<_>>>()?;
+    RecordBatch::try_new_with_options(
+        schema,
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+/// Streams of a stripe.
+struct StripeReader<'a> {
+    data: &'a [u8],
+    /// Ranges in stripe of streams, by column id and stream kind.
+    streams: HashMap<(usize, u64), Range<usize>>,
+    encodings: Vec<ColumnEncoding>,
+    types: &'a [OrcType],
+    compression: Compression,
+}
+
+impl StripeReader<'_> {
+    /// Decompressed content of the stream, `None` if it's not written.
+    fn stream(&self, column: usize, kind: u64) -> Result<Option<Vec<u8>>> {
+        match self.streams.get(&(column, kind)) {
+            Some(range) => {
+                let data = self.data.get(range.clone()).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Stream {} of orc column {} is truncated", kind, column),
+                    )
+                })?;
+                Ok(Some(self.compression.decompress(data)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn required_stream(&self, column: usize, kind: u64) -> Result<Vec<u8>> {
+        self.stream(column, kind)?.ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Stream {} of orc column {} is missing", kind, column),
+            )
+        })
+    }
+
+    fn encoding(&self, column: usize) -> ColumnEncoding {
+        self.encodings.get(column).cloned().unwrap_or_default()
+    }
+
+    /// Decode `n` integers of the stream, by the run length encoding of the
+    /// column.
+    fn ints(&self, column: usize, kind: u64, signed: bool, n: usize) -> Result<Vec<i64>> {
+        let data = self.required_stream(column, kind)?;
+        match self.encoding(column).kind {
+            encoding::DIRECT_V2 | encoding::DICTIONARY_V2 => decode_ints_v2(&data, signed, n),
+            _ => decode_ints_v1(&data, signed, n),
+        }
+    }
+
+    /// Decode `n` values of the column, including nulls, into an array of
+    /// `data_type`.
+    fn decode(&self, column: usize, data_type: &DataType, n: usize) -> Result<ArrayRef> {
+        let nulls = self
+            .stream(column, stream::PRESENT)?
+            .map(|data| decode_bools(&data, n))
+            .transpose()?
+            .map(NullBuffer::from)
+            .filter(|nulls| nulls.null_count() > 0);
+        // Only non-null values are stored, including values of children.
+        let values = self.decode_values(
+            column,
+            data_type,
+            nulls.as_ref().map_or(n, |nulls| n - nulls.null_count()),
+        )?;
+        match nulls {
+            Some(nulls) => {
+                let mut next = 0;
+                let indices = nulls
+                    .iter()
+                    .map(|valid| {
+                        valid.then(|| {
+                            next += 1;
+                            next - 1
+                        })
+                    })
+                    .collect::<UInt32Array>();
+                take(&values, &indices, None)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+            }
+            None => Ok(values),
+        }
+    }
+
+    fn decode_values(&self, column: usize, data_type: &DataType, n: usize) -> Result<ArrayRef> {
+        let ty = orc_type(self.types, column)?;
+        let arrow_err =
+            |e: arrow_schema::ArrowError| Error::new(ErrorKind::ArrowError, format!("{}", e));
+        let array: ArrayRef = match (ty.kind, data_type) {
+            (kind::BOOLEAN, _) => Arc::new(BooleanArray::from(decode_bools(
+                &self.required_stream(column, stream::DATA)?,
+                n,
+            )?)),
+            (kind::BYTE, _) => Arc::new(Int8Array::from_iter_values(
+                decode_bytes(&self.required_stream(column, stream::DATA)?, n)?
+                    .into_iter()
+                    .map(|v| v as i8),
+            )),
+            (kind::SHORT, _) => Arc::new(Int16Array::from_iter_values(
+                self.ints(column, stream::DATA, true, n)?
+                    .into_iter()
+                    .map(|v| v as i16),
+            )),
+            (kind::INT, _) => Arc::new(Int32Array::from_iter_values(
+                self.ints(column, stream::DATA, true, n)?
+                    .into_iter()
+                    .map(|v| v as i32),
+            )),
+            (kind::LONG, _) => Arc::new(Int64Array::from(self.ints(
+                column,
+                stream::DATA,
+                true,
+                n,
+            )?)),
+            (kind::FLOAT, _) => {
+                let data = self.required_stream(column, stream::DATA)?;
+                Arc::new(Float32Array::from_iter_values(
+                    fixed_values::<4>(&data, n)?
+                        .into_iter()
+                        .map(f32::from_le_bytes),
+                ))
+            }
+            (kind::DOUBLE, _) => {
+                let data = self.required_stream(column, stream::DATA)?;
+                Arc::new(Float64Array::from_iter_values(
+                    fixed_values::<8>(&data, n)?
+                        .into_iter()
+                        .map(f64::from_le_bytes),
+                ))
+            }
+            (kind::STRING | kind::VARCHAR | kind::CHAR, _) => {
+                let (lengths, data) = self.binary_values(column, n)?;
+                Arc::new(
+                    StringArray::try_new(OffsetBuffer::from_lengths(lengths), data, None)
+                        .map_err(arrow_err)?,
+                )
+            }
+            (kind::BINARY, _) => {
+                let (lengths, data) = self.binary_values(column, n)?;
+                Arc::new(LargeBinaryArray::new(
+                    OffsetBuffer::from_lengths(lengths),
+                    data,
+                    None,
+                ))
+            }
+            (kind::DATE, _) => Arc::new(Date32Array::from_iter_values(
+                self.ints(column, stream::DATA, true, n)?
+                    .into_iter()
+                    .map(|v| v as i32),
+            )),
+            (kind::DECIMAL, DataType::Decimal128(precision, scale)) => {
+                let data = self.required_stream(column, stream::DATA)?;
+                let unscaled = decode_decimals(&data, n)?;
+                let scales = self.ints(column, stream::SECONDARY, true, n)?;
+                let values = unscaled
+                    .into_iter()
+                    .zip(scales)
+                    .map(|(value, value_scale)| rescale(value, value_scale, *scale as i64))
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(
+                    Decimal128Array::from(values)
+                        .with_precision_and_scale(*precision, *scale)
+                        .map_err(arrow_err)?,
+                )
+            }
+            (kind::TIMESTAMP | kind::TIMESTAMP_INSTANT, DataType::Timestamp(_, tz)) => {
+                let seconds = self.ints(column, stream::DATA, true, n)?;
+                let nanos = self.ints(column, stream::SECONDARY, false, n)?;
+                let values = seconds.into_iter().zip(nanos).map(|(seconds, nanos)| {
+                    // Trailing zeros of nanos are encoded in the last 3 bits.
+                    let zeros = nanos & 7;
+                    let mut nanos = nanos >> 3;
+                    if zeros != 0 {
+                        nanos *= 10i64.pow(zeros as u32 + 1);
+                    }
+                    let mut seconds = seconds + ORC_EPOCH_SECONDS;
+                    if seconds < 0 && nanos > 999_999 {
+                        seconds -= 1;
+                    }
+                    seconds * 1_000_000 + nanos / 1_000
+                });
+                Arc::new(
+                    TimestampMicrosecondArray::from_iter_values(values)
+                        .with_timezone_opt(tz.clone()),
+                )
+            }
+            (kind::STRUCT, DataType::Struct(fields)) => {
+                if fields.is_empty() {
+                    Arc::new(StructArray::new_empty_fields(n, None))
+                } else {
+                    let columns = ty
+                        .subtypes
+                        .iter()
+                        .zip(fields.iter())
+                        .map(|(child, field)| self.decode(*child, field.data_type(), n))
+                        .collect::<Result<Vec<_>>>()?;
+                    Arc::new(
+                        StructArray::try_new(fields.clone(), columns, None).map_err(arrow_err)?,
+                    )
+                }
+            }
+            (kind::LIST, DataType::List(element)) => {
+                let lengths = self.lengths(column, n)?;
+                let values = self.decode(
+                    self.child(ty, column, 0)?,
+                    element.data_type(),
+                    lengths.iter().sum(),
+                )?;
+                Arc::new(
+                    ListArray::try_new(
+                        element.clone(),
+                        OffsetBuffer::from_lengths(lengths),
+                        values,
+                        None,
+                    )
+                    .map_err(arrow_err)?,
+                )
+            }
+            (kind::MAP, DataType::Map(entries, sorted)) => {
+                let DataType::Struct(kv) = entries.data_type() else {
+                    return Err(Error::new(
+                        ErrorKind::ArrowError,
+                        format!("Invalid map type {}", data_type),
+                    ));
+                };
+                let lengths = self.lengths(column, n)?;
+                let total = lengths.iter().sum();
+                let keys = self.decode(self.child(ty, column, 0)?, kv[0].data_type(), total)?;
+                let values = self.decode(self.child(ty, column, 1)?, kv[1].data_type(), total)?;
+                let entries_array = StructArray::try_new(kv.clone(), vec![keys, values], None)
+                    .map_err(arrow_err)?;
+                Arc::new(
+                    MapArray::try_new(
+                        entries.clone(),
+                        OffsetBuffer::from_lengths(lengths),
+                        entries_array,
+                        None,
+                        *sorted,
+                    )
+                    .map_err(arrow_err)?,
+                )
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    format!(
+                        "Reading orc type {} of column {} is not supported",
+                        ty.kind, column
+                    ),
+                ))
+            }
+        };
+        Ok(array)
+    }
+
+    fn child(&self, ty: &OrcType, column: usize, idx: usize) -> Result<usize> {
+        ty.subtypes.get(idx).copied().ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Orc type of column {} misses subtypes", column),
+            )
+        })
+    }
+
+    /// Lengths of `n` lists or maps.
+    fn lengths(&self, column: usize, n: usize) -> Result<Vec<usize>> {
+        self.ints(column, stream::LENGTH, false, n)?
+            .into_iter()
+            .map(|len| {
+                usize::try_from(len).map_err(|_| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Invalid length {} of orc column {}", len, column),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Lengths and concatenated bytes of `n` strings or binaries, which are
+    /// encoded directly or by dictionary.
+    fn binary_values(&self, column: usize, n: usize) -> Result<(Vec<usize>, Buffer)> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid binary values of orc column {}", column),
+            )
+        };
+        let encoding = self.encoding(column);
+        match encoding.kind {
+            encoding::DICTIONARY | encoding::DICTIONARY_V2 => {
+                let dictionary = self
+                    .stream(column, stream::DICTIONARY_DATA)?
+                    .unwrap_or_default();
+                let dictionary_lengths =
+                    self.lengths_of(column, encoding.dictionary_size as usize)?;
+                let mut offsets = Vec::with_capacity(dictionary_lengths.len());
+                let mut offset = 0;
+                for len in &dictionary_lengths {
+                    offsets.push(offset..offset + len);
+                    offset += len;
+                }
+                if offset > dictionary.len() {
+                    return Err(invalid());
+                }
+
+                let mut lengths = Vec::with_capacity(n);
+                let mut data = Vec::new();
+                for idx in self.ints(column, stream::DATA, false, n)? {
+                    let range = usize::try_from(idx)
+                        .ok()
+                        .and_then(|idx| offsets.get(idx))
+                        .ok_or_else(invalid)?;
+                    lengths.push(range.len());
+                    data.extend_from_slice(&dictionary[range.clone()]);
+                }
+                Ok((lengths, data.into()))
+            }
+            _ => {
+                let lengths = self.lengths_of(column, n)?;
+                let mut data = self.stream(column, stream::DATA)?.unwrap_or_default();
+                let total = lengths.iter().sum::<usize>();
+                if total > data.len() {
+                    return Err(invalid());
+                }
+                data.truncate(total);
+                Ok((lengths, data.into()))
+            }
+        }
+    }
+
+    fn lengths_of(&self, column: usize, n: usize) -> Result<Vec<usize>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        self.lengths(column, n)
+    }
+}
+
+/// Rescale the unscaled decimal value from `from` scale to `to` scale.
+fn rescale(value: i128, from: i64, to: i64) -> Result<i128> {
+    let overflow = || {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            format!("Decimal value {} of scale {} overflows", value, from),
+        )
+    };
+    if from <= to {
+        10i128
+            .checked_pow((to - from) as u32)
+            .and_then(|factor| value.checked_mul(factor))
+            .ok_or_else(overflow)
+    } else {
+        Ok(10i128
+            .checked_pow((from - to) as u32)
+            .map_or(0, |factor| value / factor))
+    }
+}
+
+fn fixed_values<const N: usize>(data: &[u8], n: usize) -> Result<Vec<[u8; N]>> {
+    if data.len() < n * N {
+        return Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Orc stream of floating point values is truncated",
+        ));
+    }
+    Ok(data[..n * N]
+        .chunks_exact(N)
+        .map(|chunk| <[u8; N]>::try_from(chunk).unwrap())
+        .collect())
+}
+
+fn truncated() -> Error {
+    Error::new(ErrorKind::IcebergDataInvalid, "Orc stream is truncated")
+}
+
+/// Cursor over the bytes of a stream.
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self.data.get(self.pos).ok_or_else(truncated)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Varint of orc stream is too long",
+        ))
+    }
+
+    fn int(&mut self, signed: bool) -> Result<i64> {
+        let value = self.varint()?;
+        Ok(if signed { zigzag(value) } else { value as i64 })
+    }
+
+    /// Big endian integer of `width` bytes.
+    fn be_int(&mut self, width: usize) -> Result<u64> {
+        Ok(self
+            .bytes(width)?
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64))
+    }
+
+    /// Unpack `n` integers of `width` bits, which are packed from the most
+    /// significant bit. Packed integers end at byte boundary.
+    fn packed_ints(&mut self, n: usize, width: usize) -> Result<Vec<u64>> {
+        let mut values = Vec::with_capacity(n);
+        let mut current = 0u64;
+        let mut bits_left = 0;
+        for _ in 0..n {
+            let mut value = 0u64;
+            let mut needed = width;
+            while needed > 0 {
+                if bits_left == 0 {
+                    current = self.byte()? as u64;
+                    bits_left = 8;
+                }
+                let bits = needed.min(bits_left);
+                let shift = bits_left - bits;
+                value = (value << bits) | ((current >> shift) & ((1 << bits) - 1));
+                bits_left -= bits;
+                needed -= bits;
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+fn zigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Decode `n` bytes of byte run length encoding.
+fn decode_bytes(data: &[u8], n: usize) -> Result<Vec<u8>> {
+    let mut reader = ByteReader::new(data);
+    let mut values = Vec::with_capacity(n);
+    while values.len() < n {
+        let control = reader.byte()?;
+        if control < 0x80 {
+            let value = reader.byte()?;
+            values.extend(std::iter::repeat_n(value, control as usize + 3));
+        } else {
+            values.extend_from_slice(reader.bytes(0x100 - control as usize)?);
+        }
+    }
+    values.truncate(n);
+    Ok(values)
+}
+
+/// Decode `n` booleans, which are bits of bytes in byte run length encoding.
+fn decode_bools(data: &[u8], n: usize) -> Result<Vec<bool>> {
+    let bytes = decode_bytes(data, n.div_ceil(8))?;
+    Ok((0..n)
+        .map(|idx| bytes[idx / 8] & (0x80 >> (idx % 8)) != 0)
+        .collect())
+}
+
+/// Decode `n` unbounded zigzag encoded varints of decimals.
+fn decode_decimals(data: &[u8], n: usize) -> Result<Vec<i128>> {
+    let mut reader = ByteReader::new(data);
+    (0..n)
+        .map(|_| {
+            let mut value = 0u128;
+            for shift in (0..128).step_by(7) {
+                let byte = reader.byte()?;
+                value |= ((byte & 0x7f) as u128) << shift;
+                if byte & 0x80 == 0 {
+                    return Ok(((value >> 1) as i128) ^ -((value & 1) as i128));
+                }
+            }
+            Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                "Decimal of orc stream is too long",
+            ))
+        })
+        .collect()
+}
+
+/// Decode `n` integers of run length encoding version 1.
+fn decode_ints_v1(data: &[u8], signed: bool, n: usize) -> Result<Vec<i64>> {
+    let mut reader = ByteReader::new(data);
+    let mut values = Vec::with_capacity(n);
+    while values.len() < n {
+        let control = reader.byte()?;
+        if control < 0x80 {
+            let delta = reader.byte()? as i8 as i64;
+            let base = reader.int(signed)?;
+            values.extend((0..control as i64 + 3).map(|idx| base.wrapping_add(idx * delta)));
+        } else {
+            for _ in 0..0x100 - control as usize {
+                values.push(reader.int(signed)?);
+            }
+        }
+    }
+    values.truncate(n);
+    Ok(values)
+}
+
+/// Bit width of the 5 bits width code of run length encoding version 2.
+fn decode_bit_width(code: u8) -> usize {
+    match code {
+        0..=23 => code as usize + 1,
+        24 => 26,
+        25 => 28,
+        26 => 30,
+        27 => 32,
+        28 => 40,
+        29 => 48,
+        30 => 56,
+        _ => 64,
+    }
+}
+
+/// The smallest bit width of run length encoding version 2 holding `bits`.
+fn closest_fixed_bits(bits: usize) -> usize {
+    match bits {
+        0 => 1,
+        1..=24 => bits,
+        25..=26 => 26,
+        27..=28 => 28,
+        29..=30 => 30,
+        31..=32 => 32,
+        33..=40 => 40,
+        41..=48 => 48,
+        49..=56 => 56,
+        _ => 64,
+    }
+}
+
+/// Decode `n` integers of run length encoding version 2.
+fn decode_ints_v2(data: &[u8], signed: bool, n: usize) -> Result<Vec<i64>> {
+    let mut reader = ByteReader::new(data);
+    let mut values = Vec::with_capacity(n);
+    let sign = |value: u64| if signed { zigzag(value) } else { value as i64 };
+    while values.len() < n {
+        let header = reader.byte()?;
+        match header >> 6 {
+            // Short repeat.
+            0 => {
+                let width = ((header >> 3) & 0x07) as usize + 1;
+                let count = (header & 0x07) as usize + 3;
+                let value = sign(reader.be_int(width)?);
+                values.extend(std::iter::repeat_n(value, count));
+            }
+            // Direct.
+            1 => {
+                let width = decode_bit_width((header >> 1) & 0x1f);
+                let len = (((header & 0x01) as usize) << 8 | reader.byte()? as usize) + 1;
+                values.extend(reader.packed_ints(len, width)?.into_iter().map(sign));
+            }
+            // Patched base.
+            2 => {
+                let width = decode_bit_width((header >> 1) & 0x1f);
+                let len = (((header & 0x01) as usize) << 8 | reader.byte()? as usize) + 1;
+                let third = reader.byte()?;
+                let base_width = ((third >> 5) & 0x07) as usize + 1;
+                let patch_width = decode_bit_width(third & 0x1f);
+                let fourth = reader.byte()?;
+                let gap_width = ((fourth >> 5) & 0x07) as usize + 1;
+                let patch_len = (fourth & 0x1f) as usize;
+                if patch_width + gap_width > 64 {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        "Invalid patch of orc run length encoding",
+                    ));
+                }
+
+                // The most significant bit of base is the sign.
+                let base = reader.be_int(base_width)?;
+                let sign_bit = 1u64 << (base_width * 8 - 1);
+                let base = if base & sign_bit != 0 {
+                    -((base & !sign_bit) as i64)
+                } else {
+                    base as i64
+                };
+                let mut unpacked = reader.packed_ints(len, width)?;
+                let patches =
+                    reader.packed_ints(patch_len, closest_fixed_bits(patch_width + gap_width))?;
+                let patch_mask = if patch_width == 64 {
+                    u64::MAX
+                } else {
+                    (1u64 << patch_width) - 1
+                };
+                // Gaps are relative to the previous patch, gaps larger than
+                // 255 are split into entries of empty patches.
+                let mut idx = 0;
+                for patch in patches {
+                    idx += (patch >> patch_width) as usize;
+                    let value = unpacked.get_mut(idx).ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            "Invalid patch of orc run length encoding",
+                        )
+                    })?;
+                    *value |= (patch & patch_mask) << width;
+                }
+                values.extend(
+                    unpacked
+                        .drain(..)
+                        .map(|value| base.wrapping_add(value as i64)),
+                );
+            }
+            // Delta.
+            _ => {
+                let code = (header >> 1) & 0x1f;
+                let width = if code == 0 { 0 } else { decode_bit_width(code) };
+                let len = (((header & 0x01) as usize) << 8 | reader.byte()? as usize) + 1;
+                let base = reader.int(signed)?;
+                let delta_base = reader.int(true)?;
+                values.push(base);
+                if len > 1 {
+                    let mut previous = base.wrapping_add(delta_base);
+                    values.push(previous);
+                    if width == 0 {
+                        // Fixed delta.
+                        for _ in 2..len {
+                            previous = previous.wrapping_add(delta_base);
+                            values.push(previous);
+                        }
+                    } else {
+                        // Deltas have the sign of the delta base.
+                        for delta in reader.packed_ints(len - 2, width)? {
+                            previous = if delta_base < 0 {
+                                previous.wrapping_sub(delta as i64)
+                            } else {
+                                previous.wrapping_add(delta as i64)
+                            };
+                            values.push(previous);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    values.truncate(n);
+    Ok(values)
+}
+
+/// Compression codec of orc streams.
+#[derive(Debug, Clone, Copy)]
+enum Compression {
+    None,
+    Zlib,
+    Snappy,
+    Lz4 { block_size: usize },
+    Zstd,
+}
+
+impl Compression {
+    fn try_new(kind: u64, block_size: u64) -> Result<Self> {
+        Ok(match kind {
+            0 => Self::None,
+            1 => Self::Zlib,
+            2 => Self::Snappy,
+            4 => Self::Lz4 {
+                block_size: block_size as usize,
+            },
+            5 => Self::Zstd,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::IcebergFeatureUnsupported,
+                    format!("Orc compression {} is not supported", kind),
+                ))
+            }
+        })
+    }
+
+    /// Decompress the stream, which is a sequence of chunks with 3 bytes
+    /// headers of chunk length and whether the chunk is compressed.
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if let Self::None = self {
+            return Ok(data.to_vec());
+        }
+        let invalid = |e: String| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Failed to decompress orc stream: {}", e),
+            )
+        };
+
+        let mut reader = ByteReader::new(data);
+        let mut output = Vec::new();
+        while reader.pos < data.len() {
+            let header = reader.bytes(3)?;
+            let header =
+                header[0] as usize | (header[1] as usize) << 8 | (header[2] as usize) << 16;
+            let chunk = reader.bytes(header >> 1)?;
+            if header & 1 == 1 {
+                output.extend_from_slice(chunk);
+                continue;
+            }
+            match self {
+                Self::None => unreachable!(),
+                Self::Zlib => {
+                    flate2::read::DeflateDecoder::new(chunk)
+                        .read_to_end(&mut output)
+                        .map_err(|e| invalid(e.to_string()))?;
+                }
+                Self::Snappy => output.extend(
+                    snap::raw::Decoder::new()
+                        .decompress_vec(chunk)
+                        .map_err(|e| invalid(e.to_string()))?,
+                ),
+                Self::Lz4 { block_size } => output.extend(
+                    lz4_flex::block::decompress(chunk, *block_size)
+                        .map_err(|e| invalid(e.to_string()))?,
+                ),
+                Self::Zstd => output
+                    .extend(zstd::stream::decode_all(chunk).map_err(|e| invalid(e.to_string()))?),
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Kinds of orc types.
+mod kind {
+    pub(super) const BOOLEAN: u64 = 0;
+    pub(super) const BYTE: u64 = 1;
+    pub(super) const SHORT: u64 = 2;
+    pub(super) const INT: u64 = 3;
+    pub(super) const LONG: u64 = 4;
+    pub(super) const FLOAT: u64 = 5;
+    pub(super) const DOUBLE: u64 = 6;
+    pub(super) const STRING: u64 = 7;
+    pub(super) const BINARY: u64 = 8;
+    pub(super) const TIMESTAMP: u64 = 9;
+    pub(super) const LIST: u64 = 10;
+    pub(super) const MAP: u64 = 11;
+    pub(super) const STRUCT: u64 = 12;
+    pub(super) const DECIMAL: u64 = 14;
+    pub(super) const DATE: u64 = 15;
+    pub(super) const VARCHAR: u64 = 16;
+    pub(super) const CHAR: u64 = 17;
+    pub(super) const TIMESTAMP_INSTANT: u64 = 18;
+}
+
+/// Kinds of orc streams.
+mod stream {
+    pub(super) const PRESENT: u64 = 0;
+    pub(super) const DATA: u64 = 1;
+    pub(super) const LENGTH: u64 = 2;
+    pub(super) const DICTIONARY_DATA: u64 = 3;
+    pub(super) const SECONDARY: u64 = 5;
+}
+
+/// Kinds of orc column encodings.
+mod encoding {
+    pub(super) const DICTIONARY: u64 = 1;
+    pub(super) const DIRECT_V2: u64 = 2;
+    pub(super) const DICTIONARY_V2: u64 = 3;
+}
+
+/// Value of a protobuf field.
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> ProtoValue<'a> {
+    fn invalid() -> Error {
+        Error::new(
+            ErrorKind::IcebergDataInvalid,
+            "Unexpected wire type of orc metadata",
+        )
+    }
+
+    fn varint(&self) -> Result<u64> {
+        match self {
+            Self::Varint(value) => Ok(*value),
+            _ => Err(Self::invalid()),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8]> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(Self::invalid()),
+        }
+    }
+
+    fn string(&self) -> Result<String> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| Self::invalid())
+    }
+
+    fn double(&self) -> Result<f64> {
+        match self {
+            Self::Fixed64(value) => Ok(f64::from_bits(*value)),
+            _ => Err(Self::invalid()),
+        }
+    }
+
+    /// Values of a repeated varint field, which may be packed.
+    fn varints(&self) -> Result<Vec<u64>> {
+        match self {
+            Self::Varint(value) => Ok(vec![*value]),
+            Self::Bytes(bytes) => {
+                let mut reader = ByteReader::new(bytes);
+                let mut values = vec![];
+                while reader.pos < bytes.len() {
+                    values.push(reader.varint()?);
+                }
+                Ok(values)
+            }
+            _ => Err(Self::invalid()),
+        }
+    }
+}
+
+/// Decode the fields of a protobuf message.
+fn proto_fields(data: &[u8]) -> Result<Vec<(u64, ProtoValue<'_>)>> {
+    let mut reader = ByteReader::new(data);
+    let mut fields = vec![];
+    while reader.pos < data.len() {
+        let key = reader.varint()?;
+        let value = match key & 0x07 {
+            0 => ProtoValue::Varint(reader.varint()?),
+            1 => ProtoValue::Fixed64(u64::from_le_bytes(reader.bytes(8)?.try_into().unwrap())),
+            2 => {
+                let len = reader.varint()? as usize;
+                ProtoValue::Bytes(reader.bytes(len)?)
+            }
+            5 => ProtoValue::Fixed32(u32::from_le_bytes(reader.bytes(4)?.try_into().unwrap())),
+            _ => return Err(ProtoValue::invalid()),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+#[derive(Debug, Default)]
+struct PostScript {
+    footer_length: u64,
+    compression: u64,
+    compression_block_size: u64,
+    metadata_length: u64,
+}
+
+impl PostScript {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut postscript = Self {
+            compression_block_size: 256 * 1024,
+            ..Default::default()
+        };
+        for (field, value) in proto_fields(data)? {
+            match field {
+                1 => postscript.footer_length = value.varint()?,
+                2 => postscript.compression = value.varint()?,
+                3 => postscript.compression_block_size = value.varint()?,
+                5 => postscript.metadata_length = value.varint()?,
+                8000 if value.bytes()? != b"ORC" => {
+                    return Err(Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        "Invalid magic of orc file",
+                    ))
+                }
+                _ => {}
+            }
+        }
+        Ok(postscript)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Footer {
+    stripes: Vec<StripeInformation>,
+    types: Vec<OrcType>,
+}
+
+impl Footer {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut footer = Self::default();
+        for (field, value) in proto_fields(data)? {
+            match field {
+                3 => footer
+                    .stripes
+                    .push(StripeInformation::decode(value.bytes()?)?),
+                4 => footer.types.push(OrcType::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(footer)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct StripeInformation {
+    offset: u64,
+    index_length: u64,
+    data_length: u64,
+    footer_length: u64,
+    number_of_rows: u64,
+}
+
+impl StripeInformation {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut stripe = Self::default();
+        for (field, value) in proto_fields(data)? {
+            match field {
+                1 => stripe.offset = value.varint()?,
+                2 => stripe.index_length = value.varint()?,
+                3 => stripe.data_length = value.varint()?,
+                4 => stripe.footer_length = value.varint()?,
+                5 => stripe.number_of_rows = value.varint()?,
+                _ => {}
+            }
+        }
+        Ok(stripe)
+    }
+}
+
+#[derive(Debug, Default)]
+struct OrcType {
+    kind: u64,
+    /// Column ids of nested types.
+    subtypes: Vec<usize>,
+    field_names: Vec<String>,
+    precision: u32,
+    scale: u32,
+    attributes: HashMap<String, String>,
+}
+
+impl OrcType {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut ty = Self::default();
+        for (field, value) in proto_fields(data)? {
+            match field {
+                1 => ty.kind = value.varint()?,
+                2 => ty
+                    .subtypes
+                    .extend(value.varints()?.into_iter().map(|c| c as usize)),
+                3 => ty.field_names.push(value.string()?),
+                5 => ty.precision = value.varint()? as u32,
+                6 => ty.scale = value.varint()? as u32,
+                7 => {
+                    let (mut key, mut attr) = (String::new(), String::new());
+                    for (field, value) in proto_fields(value.bytes()?)? {
+                        match field {
+                            1 => key = value.string()?,
+                            2 => attr = value.string()?,
+                            _ => {}
+                        }
+                    }
+                    ty.attributes.insert(key, attr);
+                }
+                _ => {}
+            }
+        }
+        Ok(ty)
+    }
+}
+
+#[derive(Debug, Default)]
+struct StripeFooter {
+    streams: Vec<OrcStream>,
+    columns: Vec<ColumnEncoding>,
+}
+
+impl StripeFooter {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut footer = Self::default();
+        for (field, value) in proto_fields(data)? {
+            match field {
+                1 => {
+                    let mut stream = OrcStream::default();
+                    for (field, value) in proto_fields(value.bytes()?)? {
+                        match field {
+                            1 => stream.kind = value.varint()?,
+                            2 => stream.column = value.varint()? as usize,
+                            3 => stream.length = value.varint()?,
+                            _ => {}
+                        }
+                    }
+                    footer.streams.push(stream);
+                }
+                2 => {
+                    let mut encoding = ColumnEncoding::default();
+                    for (field, value) in proto_fields(value.bytes()?)? {
+                        match field {
+                            1 => encoding.kind = value.varint()?,
+                            2 => encoding.dictionary_size = value.varint()?,
+                            _ => {}
+                        }
+                    }
+                    footer.columns.push(encoding);
+                }
+                _ => {}
+            }
+        }
+        Ok(footer)
+    }
+}
+
+#[derive(Debug, Default)]
+struct OrcStream {
+    kind: u64,
+    column: usize,
+    length: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+struct ColumnEncoding {
+    kind: u64,
+    dictionary_size: u64,
+}
+
+/// Bounds of values in column statistics.
+#[derive(Debug, Clone, PartialEq)]
+enum ColumnStatistics {
+    Int(i64, i64),
+    Double(f64, f64),
+    String(String, String),
+    Date(i32, i32),
+    Unknown,
+}
+
+impl ColumnStatistics {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut statistics = Self::Unknown;
+        for (field, value) in proto_fields(data)? {
+            if !matches!(field, 2 | 3 | 4 | 7) {
+                continue;
+            }
+            // Only statistics with both bounds are used.
+            statistics = match (field, bounds(value.bytes()?)?) {
+                (2, Some((min, max))) => Self::Int(zigzag(min.varint()?), zigzag(max.varint()?)),
+                (3, Some((min, max))) => Self::Double(min.double()?, max.double()?),
+                (4, Some((min, max))) => Self::String(min.string()?, max.string()?),
+                (7, Some((min, max))) => {
+                    Self::Date(zigzag(min.varint()?) as i32, zigzag(max.varint()?) as i32)
+                }
+                _ => continue,
+            };
+        }
+        Ok(statistics)
+    }
+
+    /// Check whether the column may contain the literal.
+    fn may_contain(&self, literal: &PrimitiveValue) -> bool {
+        match (self, literal) {
+            (Self::Int(min, max), PrimitiveValue::Int(v)) => (*min..=*max).contains(&(*v as i64)),
+            (Self::Int(min, max), PrimitiveValue::Long(v)) => (*min..=*max).contains(v),
+            (Self::Double(min, max), PrimitiveValue::Float(v)) => {
+                v.is_nan() || (*min..=*max).contains(&(v.0 as f64))
+            }
+            (Self::Double(min, max), PrimitiveValue::Double(v)) => {
+                v.is_nan() || (*min..=*max).contains(&v.0)
+            }
+            (Self::String(min, max), PrimitiveValue::String(v)) => {
+                min.as_str() <= v.as_str() && v.as_str() <= max.as_str()
+            }
+            (Self::Date(min, max), PrimitiveValue::Date(v)) => {
+                let days = v
+                    .signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+                    .num_days();
+                (*min as i64..=*max as i64).contains(&days)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Lower and upper bounds of typed statistics, `None` if either is missing.
+fn bounds(data: &[u8]) -> Result<Option<(ProtoValue<'_>, ProtoValue<'_>)>> {
+    let mut min = None;
+    let mut max = None;
+    for (field, value) in proto_fields(data)? {
+        match field {
+            1 => min = Some(value),
+            2 => max = Some(value),
+            _ => {}
+        }
+    }
+    Ok(min.zip(max))
+}
+
+/// Decode the metadata of file, which has column statistics of stripes.
+fn decode_metadata(data: &[u8]) -> Result<Vec<Vec<ColumnStatistics>>> {
+    let mut stripes = vec![];
+    for (field, value) in proto_fields(data)? {
+        if field == 1 {
+            let mut columns = vec![];
+            for (field, value) in proto_fields(value.bytes()?)? {
+                if field == 1 {
+                    columns.push(ColumnStatistics::decode(value.bytes()?)?);
+                }
+            }
+            stripes.push(columns);
+        }
+    }
+    Ok(stripes)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::Array;
+
+    use super::*;
+    use crate::test_utils::{orc_bools, orc_ints};
+
+    #[test]
+    fn test_decode_ints_v2() {
+        // Examples of the orc specification.
+        assert_eq!(
+            decode_ints_v2(&[0x0a, 0x27, 0x10], false, 5).unwrap(),
+            vec![10000; 5]
+        );
+        assert_eq!(
+            decode_ints_v2(
+                &[0x5e, 0x03, 0x5c, 0xa1, 0xab, 0x1e, 0xde, 0xad, 0xbe, 0xef],
+                false,
+                4
+            )
+            .unwrap(),
+            vec![23713, 43806, 57005, 48879]
+        );
+        assert_eq!(
+            decode_ints_v2(
+                &[
+                    0x8e, 0x13, 0x2b, 0x21, 0x07, 0xd0, 0x1e, 0x00, 0x14, 0x70, 0x28, 0x32, 0x3c,
+                    0x46, 0x50, 0x5a, 0x64, 0x6e, 0x78, 0x82, 0x8c, 0x96, 0xa0, 0xaa, 0xb4, 0xbe,
+                    0xfc, 0xe8
+                ],
+                false,
+                20
+            )
+            .unwrap(),
+            vec![
+                2030, 2000, 2020, 1000000, 2040, 2050, 2060, 2070, 2080, 2090, 2100, 2110, 2120,
+                2130, 2140, 2150, 2160, 2170, 2180, 2190
+            ]
+        );
+        assert_eq!(
+            decode_ints_v2(&[0xc6, 0x09, 0x02, 0x02, 0x22, 0x42, 0x42, 0x46], false, 10).unwrap(),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert!(decode_ints_v2(&[0x0a, 0x27], false, 5).is_err());
+    }
+
+    #[test]
+    fn test_decode_runs_v1() {
+        // A run of 5 values from -2 by delta 3, then 2 literals.
+        assert_eq!(
+            decode_ints_v1(&[0x02, 0x03, 0x03, 0xfe, 0x01, 0x04], true, 7).unwrap(),
+            vec![-2, 1, 4, 7, 10, -1, 2]
+        );
+        assert_eq!(
+            decode_bytes(&[0x00, 0x07, 0xfe, 0x01, 0x02], 5).unwrap(),
+            vec![7, 7, 7, 1, 2]
+        );
+        assert_eq!(
+            decode_bools(&[0xff, 0xa0], 3).unwrap(),
+            vec![true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_decode_nested_columns() {
+        let ty = |kind, subtypes: Vec<usize>, field_names: &[&str]| OrcType {
+            kind,
+            subtypes,
+            field_names: field_names.iter().map(|n| n.to_string()).collect(),
+            ..Default::default()
+        };
+        let types = vec![
+            ty(kind::STRUCT, vec![1, 3, 5, 6], &["s", "l", "d", "t"]),
+            ty(kind::STRUCT, vec![2], &["a"]),
+            ty(kind::INT, vec![], &[]),
+            ty(kind::LIST, vec![4], &[]),
+            ty(kind::LONG, vec![], &[]),
+            OrcType {
+                precision: 10,
+                scale: 2,
+                ..ty(kind::DECIMAL, vec![], &[])
+            },
+            ty(kind::TIMESTAMP, vec![], &[]),
+        ];
+        // Rows are `({a: 1}, [10, 20], 1.23, 2015-01-01 00:00:00.000001)`,
+        // `(null, [], -0.5, 2015-01-01 00:00:01)` and
+        // `({a: null}, null, 7, 2014-12-31 23:59:59)`.
+        let decimals = [246u8, 1, 9, 14];
+        let streams = [
+            (1, stream::PRESENT, orc_bools(&[true, false, true])),
+            (2, stream::PRESENT, orc_bools(&[true, false])),
+            (2, stream::DATA, orc_ints(&[1], true)),
+            (3, stream::PRESENT, orc_bools(&[true, true, false])),
+            (3, stream::LENGTH, orc_ints(&[2, 0], false)),
+            (4, stream::DATA, orc_ints(&[10, 20], true)),
+            (5, stream::DATA, decimals.to_vec()),
+            (5, stream::SECONDARY, orc_ints(&[2, 1, 0], true)),
+            (6, stream::DATA, orc_ints(&[0, 1, -1], true)),
+            (6, stream::SECONDARY, orc_ints(&[10, 0, 0], false)),
+        ];
+        let mut data = vec![];
+        let mut ranges = HashMap::new();
+        for (column, kind, stream) in streams {
+            ranges.insert((column, kind), data.len()..data.len() + stream.len());
+            data.extend(stream);
+        }
+        let reader = StripeReader {
+            data: &data,
+            streams: ranges,
+            encodings: vec![],
+            types: &types,
+            compression: Compression::None,
+        };
+        let schema = file_schema(&types).unwrap();
+        let columns = [1, 3, 5, 6]
+            .iter()
+            .zip(schema.fields())
+            .map(|(column, field)| reader.decode(*column, field.data_type(), 3).unwrap())
+            .collect::<Vec<_>>();
+
+        let s = columns[0].as_struct();
+        assert_eq!(
+            s.nulls().unwrap().iter().collect::<Vec<_>>(),
+            vec![true, false, true]
+        );
+        let a = s.column(0).as_primitive::<arrow_array::types::Int32Type>();
+        assert!(a.is_valid(0) && a.is_null(2));
+        assert_eq!(a.value(0), 1);
+
+        let l = columns[1].as_list::<i32>();
+        assert_eq!(l.value_offsets(), &[0, 2, 2, 2]);
+        assert!(l.is_null(2));
+        assert_eq!(
+            l.values().as_primitive::<Int64Type>().values().to_vec(),
+            vec![10, 20]
+        );
+
+        let d = columns[2].as_primitive::<arrow_array::types::Decimal128Type>();
+        assert_eq!(d.values().to_vec(), vec![123, -50, 700]);
+
+        let t = columns[3].as_primitive::<arrow_array::types::TimestampMicrosecondType>();
+        assert_eq!(
+            t.values().to_vec(),
+            vec![
+                1_420_070_400_000_001,
+                1_420_070_401_000_000,
+                1_420_070_399_000_000
+            ]
+        );
+    }
+}
@@ -0,0 +1,461 @@
+//! Filters of table scan.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::ValueSerDe;
+use crate::types::{
+    Any, AnyValue, DataFile, PartitionSpec, PrimitiveValue, Schema, StructValue, Transform,
+};
+use crate::{Error, ErrorKind, Result};
+
+/// A boolean expression on the fields of table schema, fields are referenced
+/// by field id.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// Always true.
+    AlwaysTrue,
+    /// Always false.
+    AlwaysFalse,
+    /// Value of the field is null.
+    IsNull(i32),
+    /// Value of the field is not null.
+    NotNull(i32),
+    /// Value of the field equals to the literal.
+    Eq(i32, PrimitiveValue),
+    /// Value of the field is one of the literals.
+    In(i32, Vec<PrimitiveValue>),
+    /// Both of the predicates are true.
+    And(Box<Predicate>, Box<Predicate>),
+    /// Either of the predicates is true.
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+impl Predicate {
+    /// Combine two predicates with `and`.
+    pub fn and(self, other: Predicate) -> Predicate {
+        match (self, other) {
+            (Predicate::AlwaysFalse, _) | (_, Predicate::AlwaysFalse) => Predicate::AlwaysFalse,
+            (Predicate::AlwaysTrue, p) | (p, Predicate::AlwaysTrue) => p,
+            (l, r) => Predicate::And(Box::new(l), Box::new(r)),
+        }
+    }
+
+    /// Combine two predicates with `or`.
+    pub fn or(self, other: Predicate) -> Predicate {
+        match (self, other) {
+            (Predicate::AlwaysTrue, _) | (_, Predicate::AlwaysTrue) => Predicate::AlwaysTrue,
+            (Predicate::AlwaysFalse, p) | (p, Predicate::AlwaysFalse) => p,
+            (l, r) => Predicate::Or(Box::new(l), Box::new(r)),
+        }
+    }
+
+    /// Return the part of the predicate which still needs to be evaluated on
+    /// rows of data files in the partition.
+    ///
+    /// Predicates on source columns of identity partition fields are decided
+    /// by the partition value, others are kept as they are.
+    pub fn residual(&self, spec: &PartitionSpec, partition: &StructValue) -> Predicate {
+        match self {
+            Predicate::AlwaysTrue | Predicate::AlwaysFalse => self.clone(),
+            Predicate::And(l, r) => l.residual(spec, partition).and(r.residual(spec, partition)),
+            Predicate::Or(l, r) => l.residual(spec, partition).or(r.residual(spec, partition)),
+            Predicate::IsNull(field_id)
+            | Predicate::NotNull(field_id)
+            | Predicate::Eq(field_id, _)
+            | Predicate::In(field_id, _) => {
+                let value = spec
+                    .fields
+                    .iter()
+                    .find(|f| f.source_column_id == *field_id && f.transform == Transform::Identity)
+                    .and_then(|f| {
+                        partition
+                            .iter()
+                            .find(|(id, ..)| *id == f.partition_field_id)
+                            .map(|(_, value, ..)| value)
+                    });
+                match value {
+                    Some(value) if self.eval(value) => Predicate::AlwaysTrue,
+                    Some(_) => Predicate::AlwaysFalse,
+                    None => self.clone(),
+                }
+            }
+        }
+    }
+
+    /// Evaluate a leaf predicate with the value of its field.
+    fn eval(&self, value: Option<&AnyValue>) -> bool {
+        match (self, value) {
+            (Predicate::IsNull(_), value) => value.is_none(),
+            (Predicate::NotNull(_), value) => value.is_some(),
+            (Predicate::Eq(_, literal), Some(AnyValue::Primitive(v))) => v == literal,
+            (Predicate::In(_, literals), Some(AnyValue::Primitive(v))) => literals.contains(v),
+            _ => false,
+        }
+    }
+
+    /// Check whether rows of the data file may match the predicate with its
+    /// null value counts and bounds. Returns `false` only if no row can match.
+    ///
+    /// Truncated bounds are still lower and upper bounds of values, so they
+    /// are safe to prune data files.
+    pub(crate) fn may_match_metrics(&self, schema: &Schema, data_file: &DataFile) -> Result<bool> {
+        let metric = |metrics: &Option<HashMap<i32, i64>>, field_id: i32| {
+            metrics.as_ref().and_then(|m| m.get(&field_id)).copied()
+        };
+        let all_null = |field_id: i32| {
+            let null_count = metric(&data_file.null_value_counts, field_id);
+            null_count.is_some() && null_count == metric(&data_file.value_counts, field_id)
+        };
+        let in_bounds = |field_id: i32, literal: &PrimitiveValue| -> Result<bool> {
+            let Some(Any::Primitive(ty)) =
+                schema.look_up_field_by_id(field_id).map(|f| &f.field_type)
+            else {
+                return Ok(true);
+            };
+            // NaN is not counted in bounds.
+            if matches!(literal, PrimitiveValue::Float(v) if v.is_nan())
+                || matches!(literal, PrimitiveValue::Double(v) if v.is_nan())
+            {
+                return Ok(true);
+            }
+            let bound = |bounds: &Option<HashMap<i32, Vec<u8>>>| {
+                bounds
+                    .as_ref()
+                    .and_then(|b| b.get(&field_id))
+                    .map(|b| PrimitiveValue::try_from_bytes(ty, b))
+                    .transpose()
+            };
+            if let Some(lower) = bound(&data_file.lower_bounds)? {
+                if literal < &lower {
+                    return Ok(false);
+                }
+            }
+            if let Some(upper) = bound(&data_file.upper_bounds)? {
+                if literal > &upper {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        };
+
+        match self {
+            Predicate::AlwaysTrue => Ok(true),
+            Predicate::AlwaysFalse => Ok(false),
+            Predicate::And(l, r) => Ok(l.may_match_metrics(schema, data_file)?
+                && r.may_match_metrics(schema, data_file)?),
+            Predicate::Or(l, r) => Ok(l.may_match_metrics(schema, data_file)?
+                || r.may_match_metrics(schema, data_file)?),
+            Predicate::IsNull(field_id) => {
+                Ok(metric(&data_file.null_value_counts, *field_id) != Some(0))
+            }
+            Predicate::NotNull(field_id) => Ok(!all_null(*field_id)),
+            Predicate::Eq(field_id, literal) => {
+                Ok(!all_null(*field_id) && in_bounds(*field_id, literal)?)
+            }
+            Predicate::In(field_id, literals) => {
+                if all_null(*field_id) {
+                    return Ok(false);
+                }
+                for literal in literals {
+                    if in_bounds(*field_id, literal)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Ids of fields compared with literals by `Eq` and `In`.
+    pub(crate) fn literal_field_ids(&self) -> HashSet<i32> {
+        match self {
+            Predicate::Eq(field_id, _) | Predicate::In(field_id, _) => HashSet::from([*field_id]),
+            Predicate::And(l, r) | Predicate::Or(l, r) => {
+                let mut field_ids = l.literal_field_ids();
+                field_ids.extend(r.literal_field_ids());
+                field_ids
+            }
+            _ => HashSet::new(),
+        }
+    }
+
+    /// Check whether rows may match the predicate, `may_contain` tells
+    /// whether the field may contain the literal, like by bloom filters.
+    /// Returns `false` only if no row can match.
+    pub(crate) fn may_match_literals(
+        &self,
+        may_contain: &impl Fn(i32, &PrimitiveValue) -> bool,
+    ) -> bool {
+        match self {
+            Predicate::AlwaysFalse => false,
+            Predicate::And(l, r) => {
+                l.may_match_literals(may_contain) && r.may_match_literals(may_contain)
+            }
+            Predicate::Or(l, r) => {
+                l.may_match_literals(may_contain) || r.may_match_literals(may_contain)
+            }
+            Predicate::Eq(field_id, literal) => may_contain(*field_id, literal),
+            Predicate::In(field_id, literals) => literals
+                .iter()
+                .any(|literal| may_contain(*field_id, literal)),
+            Predicate::AlwaysTrue | Predicate::IsNull(_) | Predicate::NotNull(_) => true,
+        }
+    }
+
+    /// Serialize the predicate to json value.
+    pub fn to_json(&self) -> Result<serde_json::Value> {
+        serde_json::to_value(PredicateSerDe::from(self.clone())).map_err(|e| {
+            Error::new(
+                ErrorKind::Unexpected,
+                "Failed to serialize predicate to json",
+            )
+            .set_source(e)
+        })
+    }
+
+    /// Parse the predicate from json value, types of literals are resolved
+    /// with `schema`.
+    pub fn from_json(value: serde_json::Value, schema: &Schema) -> Result<Predicate> {
+        serde_json::from_value::<PredicateSerDe>(value)
+            .map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "Failed to parse predicate from json")
+                    .set_source(e)
+            })?
+            .into_memory(schema)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum PredicateSerDe {
+    AlwaysTrue,
+    AlwaysFalse,
+    IsNull {
+        #[serde(rename = "field-id")]
+        field_id: i32,
+    },
+    NotNull {
+        #[serde(rename = "field-id")]
+        field_id: i32,
+    },
+    Eq {
+        #[serde(rename = "field-id")]
+        field_id: i32,
+        value: ValueSerDe,
+    },
+    In {
+        #[serde(rename = "field-id")]
+        field_id: i32,
+        values: Vec<ValueSerDe>,
+    },
+    And {
+        left: Box<PredicateSerDe>,
+        right: Box<PredicateSerDe>,
+    },
+    Or {
+        left: Box<PredicateSerDe>,
+        right: Box<PredicateSerDe>,
+    },
+}
+
+impl From<Predicate> for PredicateSerDe {
+    fn from(v: Predicate) -> Self {
+        let value = |v: PrimitiveValue| ValueSerDe::from(AnyValue::Primitive(v));
+        match v {
+            Predicate::AlwaysTrue => Self::AlwaysTrue,
+            Predicate::AlwaysFalse => Self::AlwaysFalse,
+            Predicate::IsNull(field_id) => Self::IsNull { field_id },
+            Predicate::NotNull(field_id) => Self::NotNull { field_id },
+            Predicate::Eq(field_id, v) => Self::Eq {
+                field_id,
+                value: value(v),
+            },
+            Predicate::In(field_id, v) => Self::In {
+                field_id,
+                values: v.into_iter().map(value).collect(),
+            },
+            Predicate::And(l, r) => Self::And {
+                left: Box::new((*l).into()),
+                right: Box::new((*r).into()),
+            },
+            Predicate::Or(l, r) => Self::Or {
+                left: Box::new((*l).into()),
+                right: Box::new((*r).into()),
+            },
+        }
+    }
+}
+
+impl PredicateSerDe {
+    fn into_memory(self, schema: &Schema) -> Result<Predicate> {
+        let literal = |field_id: i32, value: ValueSerDe| -> Result<PrimitiveValue> {
+            let field = schema.look_up_field_by_id(field_id).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Can't find field id {} in schema", field_id),
+                )
+            })?;
+            match value.into_memory(&field.field_type)? {
+                Some(AnyValue::Primitive(v)) => Ok(v),
+                v => Err(Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Invalid literal {:?} of field {}", v, field_id),
+                )),
+            }
+        };
+
+        Ok(match self {
+            Self::AlwaysTrue => Predicate::AlwaysTrue,
+            Self::AlwaysFalse => Predicate::AlwaysFalse,
+            Self::IsNull { field_id } => Predicate::IsNull(field_id),
+            Self::NotNull { field_id } => Predicate::NotNull(field_id),
+            Self::Eq { field_id, value } => Predicate::Eq(field_id, literal(field_id, value)?),
+            Self::In { field_id, values } => Predicate::In(
+                field_id,
+                values
+                    .into_iter()
+                    .map(|v| literal(field_id, v))
+                    .collect::<Result<_>>()?,
+            ),
+            Self::And { left, right } => Predicate::And(
+                Box::new(left.into_memory(schema)?),
+                Box::new(right.into_memory(schema)?),
+            ),
+            Self::Or { left, right } => Predicate::Or(
+                Box::new(left.into_memory(schema)?),
+                Box::new(right.into_memory(schema)?),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::types::{Any, Field, PartitionField, Primitive, Struct, StructValueBuilder};
+
+    fn schema() -> Schema {
+        Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::required(1, "id", Any::Primitive(Primitive::Long)).into(),
+                Field::optional(2, "name", Any::Primitive(Primitive::String)).into(),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_residual() {
+        let spec = PartitionSpec {
+            spec_id: 1,
+            fields: vec![PartitionField {
+                source_column_id: 2,
+                partition_field_id: 1000,
+                transform: Transform::Identity,
+                name: "name".to_string(),
+            }],
+        };
+        let partition_type = Arc::new(Struct::new(vec![Field::optional(
+            1000,
+            "name",
+            Any::Primitive(Primitive::String),
+        )
+        .into()]));
+        let mut builder = StructValueBuilder::new(partition_type);
+        builder
+            .add_field(
+                1000,
+                Some(AnyValue::Primitive(PrimitiveValue::String("a".to_string()))),
+            )
+            .unwrap();
+        let partition = builder.build().unwrap();
+
+        let id_eq = Predicate::Eq(1, PrimitiveValue::Long(1));
+        let name_eq = |name: &str| Predicate::Eq(2, PrimitiveValue::String(name.to_string()));
+
+        assert_eq!(
+            id_eq.clone().and(name_eq("a")).residual(&spec, &partition),
+            id_eq
+        );
+        assert_eq!(
+            id_eq.clone().and(name_eq("b")).residual(&spec, &partition),
+            Predicate::AlwaysFalse
+        );
+        assert_eq!(
+            id_eq.clone().or(name_eq("a")).residual(&spec, &partition),
+            Predicate::AlwaysTrue
+        );
+        assert_eq!(
+            Predicate::IsNull(2).residual(&spec, &partition),
+            Predicate::AlwaysFalse
+        );
+    }
+
+    #[test]
+    fn test_may_match_metrics() {
+        let mut data_file = DataFile::new(
+            crate::types::DataContentType::Data,
+            "data",
+            crate::types::DataFileFormat::Parquet,
+            10,
+            100,
+        );
+        data_file.value_counts = Some(HashMap::from([(1, 10), (2, 10)]));
+        data_file.null_value_counts = Some(HashMap::from([(1, 0), (2, 10)]));
+        data_file.lower_bounds = Some(HashMap::from([(1, 10i64.to_le_bytes().to_vec())]));
+        data_file.upper_bounds = Some(HashMap::from([(1, 20i64.to_le_bytes().to_vec())]));
+        let schema = schema();
+        let may_match =
+            |predicate: Predicate| predicate.may_match_metrics(&schema, &data_file).unwrap();
+
+        assert!(may_match(Predicate::Eq(1, PrimitiveValue::Long(10))));
+        assert!(!may_match(Predicate::Eq(1, PrimitiveValue::Long(21))));
+        assert!(may_match(Predicate::In(
+            1,
+            vec![PrimitiveValue::Long(1), PrimitiveValue::Long(15)]
+        )));
+        assert!(!may_match(Predicate::In(
+            1,
+            vec![PrimitiveValue::Long(1), PrimitiveValue::Long(25)]
+        )));
+        assert!(!may_match(Predicate::IsNull(1)));
+        assert!(!may_match(Predicate::NotNull(2)));
+        assert!(!may_match(Predicate::Eq(
+            2,
+            PrimitiveValue::String("a".to_string())
+        )));
+        assert!(may_match(
+            Predicate::Eq(1, PrimitiveValue::Long(21)).or(Predicate::IsNull(2))
+        ));
+    }
+
+    #[test]
+    fn test_may_match_literals() {
+        let predicate = Predicate::Eq(1, PrimitiveValue::Long(1)).and(Predicate::In(
+            2,
+            vec![PrimitiveValue::String("a".to_string())],
+        ));
+        assert_eq!(predicate.literal_field_ids(), HashSet::from([1, 2]));
+        assert!(predicate.may_match_literals(&|_, _| true));
+        assert!(!predicate.may_match_literals(&|field_id, _| field_id == 1));
+        assert!(Predicate::IsNull(1).may_match_literals(&|_, _| false));
+    }
+
+    #[test]
+    fn test_predicate_json() {
+        let predicate = Predicate::Eq(1, PrimitiveValue::Long(1)).or(Predicate::In(
+            2,
+            vec![
+                PrimitiveValue::String("a".to_string()),
+                PrimitiveValue::String("b".to_string()),
+            ],
+        ));
+        let value = predicate.to_json().unwrap();
+        assert_eq!(Predicate::from_json(value, &schema()).unwrap(), predicate);
+    }
+}
@@ -0,0 +1,1236 @@
+//! Projection of scan output, resolved by Iceberg field id so that data files
+//! written with old schemas can be read with the current one.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    new_null_array, Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array,
+    FixedSizeBinaryArray, Float32Array, Float64Array, Int32Array, Int64Array, LargeBinaryArray,
+    ListArray, MapArray, RecordBatch, RecordBatchOptions, StringArray, StructArray,
+    Time64MicrosecondArray, TimestampMicrosecondArray, UInt32Array,
+};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef};
+use chrono::{NaiveDate, NaiveTime};
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+use parquet::schema::types::SchemaDescriptor;
+
+use crate::types::{
+    Any, AnyValue, Field, FieldRef, List, Map, Primitive, PrimitiveValue, Schema, Struct,
+    StructValue,
+};
+use crate::{Error, ErrorKind, Result};
+
+/// Name of metadata column of data file path.
+pub const FILE_PATH_COLUMN_NAME: &str = "_file";
+/// Field id of metadata column of data file path.
+pub const FILE_PATH_FIELD_ID: i32 = i32::MAX - 1;
+/// Name of metadata column of row position in data file.
+pub const ROW_POSITION_COLUMN_NAME: &str = "_pos";
+/// Field id of metadata column of row position in data file.
+pub const ROW_POSITION_FIELD_ID: i32 = i32::MAX - 2;
+/// Name of metadata column of whether the row is deleted.
+pub const IS_DELETED_COLUMN_NAME: &str = "_deleted";
+/// Field id of metadata column of whether the row is deleted.
+pub const IS_DELETED_FIELD_ID: i32 = i32::MAX - 3;
+/// Name of metadata column of partition spec id of data file.
+pub const SPEC_ID_COLUMN_NAME: &str = "_spec_id";
+/// Field id of metadata column of partition spec id of data file.
+pub const SPEC_ID_FIELD_ID: i32 = i32::MAX - 4;
+/// Name of metadata column of partition value of data file.
+pub const PARTITION_COLUMN_NAME: &str = "_partition";
+/// Field id of metadata column of partition value of data file.
+pub const PARTITION_FIELD_ID: i32 = i32::MAX - 5;
+
+/// Returns the metadata column with `name`, `_partition` has the partition
+/// type of data file.
+fn metadata_field(name: &str, partition_type: &Struct) -> Option<Field> {
+    let field = match name {
+        FILE_PATH_COLUMN_NAME => {
+            Field::required(FILE_PATH_FIELD_ID, name, Any::Primitive(Primitive::String))
+        }
+        ROW_POSITION_COLUMN_NAME => {
+            Field::required(ROW_POSITION_FIELD_ID, name, Any::Primitive(Primitive::Long))
+        }
+        IS_DELETED_COLUMN_NAME => Field::required(
+            IS_DELETED_FIELD_ID,
+            name,
+            Any::Primitive(Primitive::Boolean),
+        ),
+        SPEC_ID_COLUMN_NAME => {
+            Field::required(SPEC_ID_FIELD_ID, name, Any::Primitive(Primitive::Int))
+        }
+        PARTITION_COLUMN_NAME => Field::optional(
+            PARTITION_FIELD_ID,
+            name,
+            Any::Struct(Arc::new(partition_type.clone())),
+        ),
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Values of metadata columns of rows in a batch.
+pub(crate) struct MetadataValues<'a> {
+    pub(crate) file_path: &'a str,
+    pub(crate) spec_id: i32,
+    pub(crate) partition: &'a StructValue,
+    /// Positions in data file of rows, it's set if `_pos` is projected.
+    pub(crate) positions: Option<Int64Array>,
+    /// Whether rows are deleted, it's set if `_deleted` is projected.
+    pub(crate) is_deleted: Option<BooleanArray>,
+}
+
+/// Projected root fields of the table schema.
+#[derive(Debug, Clone)]
+pub(crate) struct Projection {
+    /// Projected root fields, pruned to the selected nested fields.
+    fields: Vec<FieldRef>,
+    /// Ids of fields to read, they are root fields or nested fields.
+    read_ids: HashSet<i32>,
+    /// Ids of values of maps whose keys are selected without values, the
+    /// values are nulls in output batches.
+    null_value_ids: HashSet<i32>,
+    /// Arrow schema of output batches, converted from `fields`.
+    arrow_schema: SchemaRef,
+    /// Root fields of the table schema.
+    table_fields: Vec<FieldRef>,
+}
+
+impl Projection {
+    /// Resolve the projection of `column_names` in `schema`, all root
+    /// fields are projected if `column_names` is empty.
+    ///
+    /// Nested fields are selected by dotted names like `address.city`,
+    /// elements of lists are named `element`, keys and values of maps are
+    /// named `key` and `value`. Root fields are pruned to the selected
+    /// nested fields. If keys of a map are selected without values, values
+    /// are pruned and become optional, they are nulls in output batches.
+    ///
+    /// Metadata columns like `_file` and `_pos` can be projected by name,
+    /// `partition_type` is the type of `_partition` column.
+    pub(crate) fn try_new(
+        schema: &Schema,
+        column_names: &[String],
+        partition_type: &Struct,
+    ) -> Result<Self> {
+        let mut fields: Vec<FieldRef> = vec![];
+        let mut selected_ids = HashSet::new();
+        if column_names.is_empty() {
+            fields = schema.fields().to_vec();
+            selected_ids.extend(fields.iter().map(|f| f.id));
+        } else {
+            let mut root_ids = vec![];
+            let mut metadata_fields = HashMap::new();
+            for name in column_names {
+                if let Some(field) = metadata_field(name, partition_type)
+                    .filter(|_| schema.fields().iter().all(|f| f.name != *name))
+                {
+                    if !root_ids.contains(&field.id) {
+                        root_ids.push(field.id);
+                    }
+                    selected_ids.insert(field.id);
+                    metadata_fields.insert(field.id, field);
+                    continue;
+                }
+                let (root_id, field_id) = resolve_name(schema.fields(), name).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::IcebergDataInvalid,
+                        format!("Column {} not found in schema", name),
+                    )
+                })?;
+                if !root_ids.contains(&root_id) {
+                    root_ids.push(root_id);
+                }
+                selected_ids.insert(field_id);
+            }
+            // Columns are in the order of `column_names`.
+            for root_id in root_ids {
+                if let Some(field) = metadata_fields.remove(&root_id) {
+                    fields.push(Arc::new(field));
+                } else if let Some(field) = schema.fields().iter().find(|f| f.id == root_id) {
+                    fields.extend(prune_field(field, &selected_ids).map(Arc::new));
+                }
+            }
+        }
+        // Keys of maps must be read with values, even if only some fields of
+        // values are selected.
+        let mut null_value_ids = HashSet::new();
+        fields
+            .iter()
+            .filter(|f| !selected_ids.contains(&f.id))
+            .for_each(|f| unselected_value_ids(&f.field_type, &selected_ids, &mut null_value_ids));
+        let mut read_ids = selected_ids;
+        fields
+            .iter()
+            .for_each(|f| map_key_ids(&f.field_type, &mut read_ids));
+        let arrow_schema = ArrowSchema::new(
+            fields
+                .iter()
+                .map(|f| {
+                    let arrow_field = ArrowField::try_from(f.as_ref().clone())?;
+                    let data_type =
+                        mark_null_values(arrow_field.data_type(), &f.field_type, &null_value_ids);
+                    Ok(arrow_field.with_data_type(data_type))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
+
+        Ok(Self {
+            fields,
+            read_ids,
+            null_value_ids,
+            arrow_schema: Arc::new(arrow_schema),
+            table_fields: schema.fields().to_vec(),
+        })
+    }
+
+    /// Ids of fields to read from data file, all nested fields of them are
+    /// read too. Metadata columns are excluded.
+    pub(crate) fn field_ids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.read_ids
+            .iter()
+            .copied()
+            .filter(|id| !self.is_metadata_field_id(*id))
+    }
+
+    /// Ids of values of maps whose keys are selected without values. Maps
+    /// can't be read from parquet files without values, so one leaf of
+    /// these values is read.
+    pub(crate) fn null_value_ids(&self) -> &HashSet<i32> {
+        &self.null_value_ids
+    }
+
+    /// Root fields of table schema containing `field_ids`, pruned to them.
+    /// They are the fields to read from data files of formats which are
+    /// decoded by table schema, like avro.
+    pub(crate) fn read_fields(&self, field_ids: &HashSet<i32>) -> Vec<FieldRef> {
+        self.table_fields
+            .iter()
+            .filter_map(|f| prune_field(f, field_ids).map(Arc::new))
+            .collect()
+    }
+
+    /// Projected root fields, including metadata columns.
+    pub(crate) fn fields(&self) -> &[FieldRef] {
+        &self.fields
+    }
+
+    /// Check whether the field is projected.
+    pub(crate) fn contains(&self, field_id: i32) -> bool {
+        self.fields.iter().any(|f| f.id == field_id)
+    }
+
+    fn is_metadata_field_id(&self, field_id: i32) -> bool {
+        (PARTITION_FIELD_ID..=FILE_PATH_FIELD_ID).contains(&field_id)
+    }
+
+    /// Arrow schema of output batches.
+    pub(crate) fn arrow_schema(&self) -> &SchemaRef {
+        &self.arrow_schema
+    }
+
+    /// Build the output batch from a batch read from data file, columns are
+    /// found by field id with `column_of`.
+    ///
+    /// Output batches always have the arrow schema converted from table
+    /// schema: columns are renamed to the current names, and casted to the
+    /// current types since files written before type promotion carry the
+    /// narrower types. Columns missing in the data file are filled with the
+    /// initial default of the field, or nulls if there is no default.
+    /// Metadata columns are built from `metadata`.
+    pub(crate) fn project(
+        &self,
+        batch: &RecordBatch,
+        column_of: impl Fn(i32) -> Option<usize>,
+        metadata: &MetadataValues,
+    ) -> Result<RecordBatch> {
+        let num_rows = batch.num_rows();
+        let columns = self
+            .fields
+            .iter()
+            .zip(self.arrow_schema.fields().iter())
+            .map(|(field, arrow_field)| {
+                if self.is_metadata_field_id(field.id) {
+                    return metadata_array(field.id, arrow_field.data_type(), metadata, num_rows);
+                }
+                match column_of(field.id) {
+                    Some(idx) => align_array(
+                        batch.column(idx),
+                        arrow_field.data_type(),
+                        &self.null_value_ids,
+                    ),
+                    None => match &field.initial_default {
+                        Some(AnyValue::Primitive(value)) => {
+                            repeat_value(value, arrow_field.data_type(), num_rows)
+                        }
+                        _ if field.required => Err(Error::new(
+                            ErrorKind::IcebergDataInvalid,
+                            format!(
+                                "Required field {} without initial default is missing in data file",
+                                field.name
+                            ),
+                        )),
+                        _ => Ok(new_null_array(arrow_field.data_type(), num_rows)),
+                    },
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        RecordBatch::try_new_with_options(
+            self.arrow_schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+    }
+}
+
+/// Resolve the dotted `name` in `fields`, returns the id of root field and
+/// the id of the selected field.
+fn resolve_name(fields: &[FieldRef], name: &str) -> Option<(i32, i32)> {
+    // Names of root fields may contain dots.
+    if let Some(field) = fields.iter().find(|f| f.name == name) {
+        return Some((field.id, field.id));
+    }
+
+    let parts = name.split('.').collect::<Vec<_>>();
+    let root = fields.iter().find(|f| f.name == parts[0])?;
+    let mut field_id = root.id;
+    let mut field_type = &root.field_type;
+    for part in &parts[1..] {
+        (field_id, field_type) = match field_type {
+            Any::Struct(s) => {
+                let field = s.lookup_field_by_name(part)?;
+                (field.id, &field.field_type)
+            }
+            Any::List(l) if *part == "element" => (l.element_id, l.element_type.as_ref()),
+            Any::Map(m) if *part == "key" => (m.key_id, m.key_type.as_ref()),
+            Any::Map(m) if *part == "value" => (m.value_id, m.value_type.as_ref()),
+            _ => return None,
+        };
+    }
+
+    Some((root.id, field_id))
+}
+
+/// Prune the field to the selected fields, returns `None` if neither the
+/// field nor its nested fields are selected.
+fn prune_field(field: &Field, selected_ids: &HashSet<i32>) -> Option<Field> {
+    if selected_ids.contains(&field.id) {
+        return Some(field.clone());
+    }
+    prune_type(&field.field_type, selected_ids).map(|field_type| Field {
+        field_type,
+        ..field.clone()
+    })
+}
+
+fn prune_type(field_type: &Any, selected_ids: &HashSet<i32>) -> Option<Any> {
+    match field_type {
+        Any::Primitive(_) => None,
+        Any::Struct(s) => {
+            let fields = s
+                .fields()
+                .iter()
+                .filter_map(|f| prune_field(f, selected_ids).map(Arc::new))
+                .collect::<Vec<_>>();
+            (!fields.is_empty()).then(|| Any::Struct(Arc::new(Struct::new(fields))))
+        }
+        Any::List(l) => {
+            if selected_ids.contains(&l.element_id) {
+                return Some(field_type.clone());
+            }
+            prune_type(&l.element_type, selected_ids).map(|element_type| {
+                Any::List(List {
+                    element_type: Box::new(element_type),
+                    ..l.clone()
+                })
+            })
+        }
+        Any::Map(m) => {
+            // Keys are never pruned, since they identify entries.
+            if selected_ids.contains(&m.value_id) {
+                return Some(field_type.clone());
+            }
+            match prune_type(&m.value_type, selected_ids) {
+                Some(value_type) => Some(Any::Map(Map {
+                    value_type: Box::new(value_type),
+                    ..m.clone()
+                })),
+                // Only keys are selected, values are nulls.
+                None if selected_ids.contains(&m.key_id)
+                    || prune_type(&m.key_type, selected_ids).is_some() =>
+                {
+                    Some(Any::Map(Map {
+                        value_required: false,
+                        ..m.clone()
+                    }))
+                }
+                None => None,
+            }
+        }
+    }
+}
+
+/// Collect ids of values of maps whose keys are selected without values, in
+/// the pruned type.
+fn unselected_value_ids(field_type: &Any, selected_ids: &HashSet<i32>, ids: &mut HashSet<i32>) {
+    match field_type {
+        Any::Primitive(_) => {}
+        Any::Struct(s) => s
+            .fields()
+            .iter()
+            .filter(|f| !selected_ids.contains(&f.id))
+            .for_each(|f| unselected_value_ids(&f.field_type, selected_ids, ids)),
+        Any::List(l) => {
+            if !selected_ids.contains(&l.element_id) {
+                unselected_value_ids(&l.element_type, selected_ids, ids);
+            }
+        }
+        Any::Map(m) => {
+            if selected_ids.contains(&m.value_id) {
+                return;
+            }
+            if prune_type(&m.value_type, selected_ids).is_some() {
+                unselected_value_ids(&m.value_type, selected_ids, ids);
+            } else {
+                ids.insert(m.value_id);
+            }
+        }
+    }
+}
+
+/// Collect ids of keys of maps nested in the type.
+fn map_key_ids(field_type: &Any, ids: &mut HashSet<i32>) {
+    match field_type {
+        Any::Primitive(_) => {}
+        Any::Struct(s) => s
+            .fields()
+            .iter()
+            .for_each(|f| map_key_ids(&f.field_type, ids)),
+        Any::List(l) => map_key_ids(&l.element_type, ids),
+        Any::Map(m) => {
+            ids.insert(m.key_id);
+            map_key_ids(&m.value_type, ids);
+        }
+    }
+}
+
+/// Set field ids of values of maps in `null_value_ids` to their arrow fields,
+/// so that they are found when aligning arrays.
+fn mark_null_values(
+    data_type: &DataType,
+    field_type: &Any,
+    null_value_ids: &HashSet<i32>,
+) -> DataType {
+    let mark = |field: &ArrowField, field_type: &Any| {
+        field.clone().with_data_type(mark_null_values(
+            field.data_type(),
+            field_type,
+            null_value_ids,
+        ))
+    };
+    match (data_type, field_type) {
+        (DataType::Struct(fields), Any::Struct(s)) => DataType::Struct(
+            fields
+                .iter()
+                .zip(s.fields())
+                .map(|(field, f)| mark(field, &f.field_type))
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (DataType::List(element), Any::List(l)) => {
+            DataType::List(Arc::new(mark(element, &l.element_type)))
+        }
+        (DataType::Map(entries, sorted), Any::Map(m)) => {
+            let DataType::Struct(kv) = entries.data_type() else {
+                return data_type.clone();
+            };
+            let mut value = mark(&kv[1], &m.value_type);
+            if null_value_ids.contains(&m.value_id) {
+                let mut metadata = value.metadata().clone();
+                metadata.insert(
+                    PARQUET_FIELD_ID_META_KEY.to_string(),
+                    m.value_id.to_string(),
+                );
+                value = value.with_metadata(metadata);
+            }
+            let kv = vec![mark(&kv[0], &m.key_type), value];
+            DataType::Map(
+                Arc::new(
+                    entries
+                        .as_ref()
+                        .clone()
+                        .with_data_type(DataType::Struct(kv.into())),
+                ),
+                *sorted,
+            )
+        }
+        _ => data_type.clone(),
+    }
+}
+
+/// Returns indices of leaf columns in parquet schema, which are nested in
+/// or are the fields with `field_ids`.
+///
+/// Maps can't be read without values, so the first leaf of each value in
+/// `null_value_ids` is read too if none of its leaves is selected.
+pub(crate) fn leaf_columns(
+    schema: &SchemaDescriptor,
+    field_ids: &HashSet<i32>,
+    null_value_ids: &HashSet<i32>,
+) -> Vec<usize> {
+    // Field ids of nodes in the path of each leaf column.
+    let paths = (0..schema.num_columns())
+        .map(|idx| {
+            let mut node = schema.root_schema();
+            let mut ids = vec![];
+            for name in schema.column(idx).path().parts() {
+                let Some(child) = node.get_fields().iter().find(|f| f.name() == name) else {
+                    break;
+                };
+                node = child.as_ref();
+                let info = child.get_basic_info();
+                if info.has_id() {
+                    ids.push(info.id());
+                }
+            }
+            ids
+        })
+        .collect::<Vec<_>>();
+
+    let mut leaves = (0..paths.len())
+        .filter(|idx| paths[*idx].iter().any(|id| field_ids.contains(id)))
+        .collect::<Vec<_>>();
+    for value_id in null_value_ids {
+        if leaves.iter().any(|idx| paths[*idx].contains(value_id)) {
+            continue;
+        }
+        if let Some(idx) = (0..paths.len()).find(|idx| paths[*idx].contains(value_id)) {
+            leaves.push(idx);
+        }
+    }
+    leaves.sort();
+    leaves
+}
+
+/// Build the array of metadata column.
+fn metadata_array(
+    field_id: i32,
+    data_type: &DataType,
+    metadata: &MetadataValues,
+    num_rows: usize,
+) -> Result<ArrayRef> {
+    let missing = |name: &str| {
+        Error::new(
+            ErrorKind::Unexpected,
+            format!("Values of metadata column {} are not provided", name),
+        )
+    };
+
+    Ok(match field_id {
+        FILE_PATH_FIELD_ID => Arc::new(StringArray::from(vec![metadata.file_path; num_rows])),
+        ROW_POSITION_FIELD_ID => Arc::new(
+            metadata
+                .positions
+                .clone()
+                .ok_or_else(|| missing(ROW_POSITION_COLUMN_NAME))?,
+        ),
+        IS_DELETED_FIELD_ID => Arc::new(
+            metadata
+                .is_deleted
+                .clone()
+                .unwrap_or_else(|| BooleanArray::from(vec![false; num_rows])),
+        ),
+        SPEC_ID_FIELD_ID => Arc::new(Int32Array::from(vec![metadata.spec_id; num_rows])),
+        PARTITION_FIELD_ID => {
+            let DataType::Struct(fields) = data_type else {
+                return Err(missing(PARTITION_COLUMN_NAME));
+            };
+            if fields.is_empty() {
+                return Ok(new_null_array(data_type, num_rows));
+            }
+            let columns = fields
+                .iter()
+                .map(|field| {
+                    let field_id = field
+                        .metadata()
+                        .get(PARQUET_FIELD_ID_META_KEY)
+                        .and_then(|id| id.parse::<i32>().ok());
+                    let value = metadata
+                        .partition
+                        .iter()
+                        .find(|(id, ..)| Some(*id) == field_id)
+                        .and_then(|(_, value, ..)| value);
+                    match value {
+                        Some(AnyValue::Primitive(value)) => {
+                            repeat_value(value, field.data_type(), num_rows)
+                        }
+                        _ => Ok(new_null_array(field.data_type(), num_rows)),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(
+                StructArray::try_new(fields.clone(), columns, None)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+            )
+        }
+        _ => return Err(missing(&field_id.to_string())),
+    })
+}
+
+/// Convert the array read from data file to the data type of table schema.
+///
+/// Fields of structs are matched by field id, so that nested fields can be
+/// renamed, reordered, added or promoted. Lists and maps are aligned by their
+/// elements, keys and values, values of maps in `null_value_ids` become
+/// nulls. Other types are casted.
+fn align_array(
+    array: &ArrayRef,
+    data_type: &DataType,
+    null_value_ids: &HashSet<i32>,
+) -> Result<ArrayRef> {
+    if array.data_type() == data_type && null_value_ids.is_empty() {
+        return Ok(array.clone());
+    }
+
+    match (array.data_type(), data_type) {
+        (DataType::Struct(fields), DataType::Struct(target_fields)) => {
+            let array = array.as_struct();
+            let columns = target_fields
+                .iter()
+                .map(|target_field| {
+                    let field_id = target_field.metadata().get(PARQUET_FIELD_ID_META_KEY);
+                    let idx = fields.iter().position(|f| {
+                        field_id.is_some()
+                            && f.metadata().get(PARQUET_FIELD_ID_META_KEY) == field_id
+                    });
+                    match idx {
+                        Some(idx) => {
+                            align_array(array.column(idx), target_field.data_type(), null_value_ids)
+                        }
+                        None => Ok(new_null_array(target_field.data_type(), array.len())),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let array =
+                StructArray::try_new(target_fields.clone(), columns, array.nulls().cloned())
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            Ok(Arc::new(array))
+        }
+        (DataType::List(_), DataType::List(target_field)) => {
+            let array = array.as_list::<i32>();
+            let values = align_array(array.values(), target_field.data_type(), null_value_ids)?;
+            let array = ListArray::try_new(
+                target_field.clone(),
+                array.offsets().clone(),
+                values,
+                array.nulls().cloned(),
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            Ok(Arc::new(array))
+        }
+        (DataType::Map(_, _), DataType::Map(target_field, ordered)) => {
+            // Keys and values are matched by position since they don't carry
+            // field ids.
+            let array = array.as_map();
+            let DataType::Struct(target_fields) = target_field.data_type() else {
+                return Err(Error::new(
+                    ErrorKind::ArrowError,
+                    format!("Invalid map type {}", data_type),
+                ));
+            };
+            let value_id = target_fields[1]
+                .metadata()
+                .get(PARQUET_FIELD_ID_META_KEY)
+                .and_then(|id| id.parse::<i32>().ok());
+            let values = match value_id {
+                Some(id) if null_value_ids.contains(&id) => {
+                    new_null_array(target_fields[1].data_type(), array.values().len())
+                }
+                _ => align_array(array.values(), target_fields[1].data_type(), null_value_ids)?,
+            };
+            let entries = StructArray::try_new(
+                target_fields.clone(),
+                vec![
+                    align_array(array.keys(), target_fields[0].data_type(), null_value_ids)?,
+                    values,
+                ],
+                array.entries().nulls().cloned(),
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            let array = MapArray::try_new(
+                target_field.clone(),
+                array.offsets().clone(),
+                entries,
+                array.nulls().cloned(),
+                *ordered,
+            )
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+            Ok(Arc::new(array))
+        }
+        _ => arrow_cast::cast(array, data_type)
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e))),
+    }
+}
+
+/// Build an array of `num_rows` copies of `value` with `data_type`.
+pub(crate) fn repeat_value(
+    value: &PrimitiveValue,
+    data_type: &DataType,
+    num_rows: usize,
+) -> Result<ArrayRef> {
+    let array: ArrayRef = match value {
+        PrimitiveValue::Boolean(v) => Arc::new(BooleanArray::from(vec![*v])),
+        PrimitiveValue::Int(v) => Arc::new(Int32Array::from(vec![*v])),
+        PrimitiveValue::Long(v) => Arc::new(Int64Array::from(vec![*v])),
+        PrimitiveValue::Float(v) => Arc::new(Float32Array::from(vec![v.0])),
+        PrimitiveValue::Double(v) => Arc::new(Float64Array::from(vec![v.0])),
+        PrimitiveValue::Decimal(v) => {
+            let (precision, scale) = match data_type {
+                DataType::Decimal128(precision, scale) => (*precision, *scale),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::DataTypeUnsupported,
+                        format!("Decimal value can't be converted to {}", data_type),
+                    ))
+                }
+            };
+            let mut v = *v;
+            v.rescale(scale as u32);
+            Arc::new(
+                Decimal128Array::from(vec![v.mantissa()])
+                    .with_precision_and_scale(precision, scale)
+                    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+            )
+        }
+        PrimitiveValue::Date(v) => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+            Arc::new(Date32Array::from(vec![
+                v.signed_duration_since(epoch).num_days() as i32,
+            ]))
+        }
+        PrimitiveValue::Time(v) => Arc::new(Time64MicrosecondArray::from(vec![v
+            .signed_duration_since(NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+            .num_microseconds()
+            .unwrap_or_default()])),
+        PrimitiveValue::Timestamp(v) => {
+            Arc::new(TimestampMicrosecondArray::from(vec![v.timestamp_micros()]))
+        }
+        PrimitiveValue::Timestampz(v) => Arc::new(
+            TimestampMicrosecondArray::from(vec![v.timestamp_micros()]).with_timezone_utc(),
+        ),
+        PrimitiveValue::String(v) => Arc::new(StringArray::from(vec![v.as_str()])),
+        PrimitiveValue::Uuid(v) => Arc::new(
+            FixedSizeBinaryArray::try_from_iter(std::iter::once(v.as_bytes()))
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+        ),
+        PrimitiveValue::Fixed(v) => Arc::new(
+            FixedSizeBinaryArray::try_from_iter(std::iter::once(v))
+                .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?,
+        ),
+        PrimitiveValue::Binary(v) => Arc::new(LargeBinaryArray::from_vec(vec![v.as_slice()])),
+    };
+
+    let array = if array.data_type() == data_type {
+        array
+    } else {
+        arrow_cast::cast(&array, data_type)
+            .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?
+    };
+    arrow_select::take::take(&array, &UInt32Array::from(vec![0; num_rows]), None)
+        .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow_array::builder::{Int32Builder, MapBuilder, StringBuilder};
+    use arrow_array::types::{
+        Float64Type, Int32Type, Int64Type, Time64MicrosecondType, TimestampMicrosecondType,
+    };
+    use arrow_array::{DictionaryArray, Time64NanosecondArray, TimestampNanosecondArray};
+
+    use parquet::schema::parser::parse_message_type;
+
+    use super::*;
+    use crate::types::{StructValueBuilder, COLUMN_ID_META_KEY};
+
+    #[test]
+    fn test_project_by_field_id() {
+        let mut added = Field::optional(3, "added", Any::Primitive(Primitive::Long));
+        added.initial_default = Some(AnyValue::Primitive(PrimitiveValue::Long(7)));
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::required(2, "renamed", Any::Primitive(Primitive::Long)).into(),
+                Field::required(1, "id", Any::Primitive(Primitive::Long)).into(),
+                added.into(),
+                Field::optional(4, "missing", Any::Primitive(Primitive::String)).into(),
+            ]),
+        );
+        let projection = Projection::try_new(&schema, &[], &Struct::default()).unwrap();
+        let partition = StructValue::default();
+        let metadata = MetadataValues {
+            file_path: "data.parquet",
+            spec_id: 0,
+            partition: &partition,
+            positions: None,
+            is_deleted: None,
+        };
+
+        // Data file written with an old schema: `id`, `name`.
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            ("name", Arc::new(Int64Array::from(vec![3, 4])) as ArrayRef),
+        ])
+        .unwrap();
+        let batch = projection
+            .project(
+                &batch,
+                |id| match id {
+                    1 => Some(0),
+                    2 => Some(1),
+                    _ => None,
+                },
+                &metadata,
+            )
+            .unwrap();
+
+        let names = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["renamed", "id", "added", "missing"]);
+        let values = |idx: usize| {
+            batch
+                .column(idx)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec()
+        };
+        assert_eq!(values(0), vec![3, 4]);
+        assert_eq!(values(1), vec![1, 2]);
+        assert_eq!(values(2), vec![7, 7]);
+        assert_eq!(batch.column(3).null_count(), 2);
+
+        assert!(Projection::try_new(&schema, &["name".to_string()], &Struct::default()).is_err());
+    }
+
+    #[test]
+    fn test_type_promotion() {
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::optional(1, "id", Any::Primitive(Primitive::Long)).into(),
+                Field::optional(
+                    2,
+                    "point",
+                    Any::Struct(
+                        Struct::new(vec![
+                            Field::optional(4, "y", Any::Primitive(Primitive::Double)).into(),
+                            Field::optional(3, "x", Any::Primitive(Primitive::Double)).into(),
+                        ])
+                        .into(),
+                    ),
+                )
+                .into(),
+            ]),
+        );
+        let projection = Projection::try_new(&schema, &[], &Struct::default()).unwrap();
+        let partition = StructValue::default();
+        let metadata = MetadataValues {
+            file_path: "data.parquet",
+            spec_id: 0,
+            partition: &partition,
+            positions: None,
+            is_deleted: None,
+        };
+
+        // Data file written before `id` is promoted to long and `point.x`
+        // is promoted to double, `point.y` is added later.
+        let field_with_id = |name: &str, data_type: DataType, id: i32| {
+            ArrowField::new(name, data_type, true).with_metadata(HashMap::from([(
+                PARQUET_FIELD_ID_META_KEY.to_string(),
+                id.to_string(),
+            )]))
+        };
+        let point = StructArray::from(vec![(
+            Arc::new(field_with_id("x", DataType::Float32, 3)),
+            Arc::new(Float32Array::from(vec![1.5, 2.5])) as ArrayRef,
+        )]);
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            ("point", Arc::new(point) as ArrayRef),
+        ])
+        .unwrap();
+        let batch = projection
+            .project(
+                &batch,
+                |id| match id {
+                    1 => Some(0),
+                    2 => Some(1),
+                    _ => None,
+                },
+                &metadata,
+            )
+            .unwrap();
+
+        assert_eq!(batch.schema(), *projection.arrow_schema());
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![1, 2]
+        );
+        let point = batch.column(1).as_struct();
+        assert_eq!(point.column(0).null_count(), 2);
+        assert_eq!(
+            point
+                .column(1)
+                .as_primitive::<Float64Type>()
+                .values()
+                .to_vec(),
+            vec![1.5, 2.5]
+        );
+    }
+
+    #[test]
+    fn test_normalize_types() {
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::optional(1, "ts", Any::Primitive(Primitive::Timestampz)).into(),
+                Field::optional(2, "name", Any::Primitive(Primitive::String)).into(),
+                Field::optional(3, "time", Any::Primitive(Primitive::Time)).into(),
+            ]),
+        );
+        let projection = Projection::try_new(&schema, &[], &Struct::default()).unwrap();
+        let partition = StructValue::default();
+        let metadata = MetadataValues {
+            file_path: "data.parquet",
+            spec_id: 0,
+            partition: &partition,
+            positions: None,
+            is_deleted: None,
+        };
+
+        // Data file written by another engine, with nanosecond units, time
+        // zone name and dictionary encoding.
+        let ts =
+            TimestampNanosecondArray::from(vec![1_000_001_000, 2_000_002_000]).with_timezone("UTC");
+        let name = vec!["a", "a"]
+            .into_iter()
+            .collect::<DictionaryArray<Int32Type>>();
+        let time = Time64NanosecondArray::from(vec![3_000, 4_000]);
+        let batch = RecordBatch::try_from_iter([
+            ("ts", Arc::new(ts) as ArrayRef),
+            ("name", Arc::new(name) as ArrayRef),
+            ("time", Arc::new(time) as ArrayRef),
+        ])
+        .unwrap();
+        let batch = projection
+            .project(&batch, |id| Some(id as usize - 1), &metadata)
+            .unwrap();
+
+        assert_eq!(batch.schema(), *projection.arrow_schema());
+        assert_eq!(
+            batch.schema().field(0).metadata().get(COLUMN_ID_META_KEY),
+            Some(&"1".to_string())
+        );
+        assert_eq!(
+            batch
+                .column(0)
+                .as_primitive::<TimestampMicrosecondType>()
+                .values()
+                .to_vec(),
+            vec![1_000_001, 2_000_002]
+        );
+        assert_eq!(
+            batch
+                .column(1)
+                .as_string::<i32>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some("a"), Some("a")]
+        );
+        assert_eq!(
+            batch
+                .column(2)
+                .as_primitive::<Time64MicrosecondType>()
+                .values()
+                .to_vec(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn test_metadata_columns() {
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![Field::required(
+                1,
+                "id",
+                Any::Primitive(Primitive::Long),
+            )
+            .into()]),
+        );
+        let partition_type = Struct::new(vec![Field::optional(
+            1000,
+            "id_bucket",
+            Any::Primitive(Primitive::Int),
+        )
+        .into()]);
+        let column_names =
+            ["id", "_file", "_pos", "_spec_id", "_partition", "_deleted"].map(|s| s.to_string());
+        let projection = Projection::try_new(&schema, &column_names, &partition_type).unwrap();
+        assert_eq!(projection.field_ids().collect::<Vec<_>>(), vec![1]);
+
+        let mut partition = StructValueBuilder::new(Arc::new(partition_type));
+        partition
+            .add_field(1000, Some(AnyValue::Primitive(PrimitiveValue::Int(3))))
+            .unwrap();
+        let partition = partition.build().unwrap();
+        let metadata = MetadataValues {
+            file_path: "data.parquet",
+            spec_id: 1,
+            partition: &partition,
+            positions: Some(Int64Array::from(vec![5, 9])),
+            is_deleted: Some(BooleanArray::from(vec![false, true])),
+        };
+        let batch = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+        let batch = projection
+            .project(&batch, |id| (id == 1).then_some(0), &metadata)
+            .unwrap();
+
+        assert_eq!(batch.schema(), *projection.arrow_schema());
+        assert_eq!(
+            batch
+                .column(1)
+                .as_string::<i32>()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some("data.parquet"), Some("data.parquet")]
+        );
+        assert_eq!(
+            batch
+                .column(2)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![5, 9]
+        );
+        assert_eq!(
+            batch
+                .column(3)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![1, 1]
+        );
+        assert_eq!(
+            batch
+                .column(4)
+                .as_struct()
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec(),
+            vec![3, 3]
+        );
+        assert_eq!(
+            batch.column(5).as_boolean(),
+            &BooleanArray::from(vec![false, true])
+        );
+    }
+
+    #[test]
+    fn test_nested_projection() {
+        let address = Struct::new(vec![
+            Field::optional(3, "city", Any::Primitive(Primitive::String)).into(),
+            Field::optional(4, "zip", Any::Primitive(Primitive::Int)).into(),
+        ]);
+        let tag = Struct::new(vec![
+            Field::optional(8, "a", Any::Primitive(Primitive::String)).into(),
+            Field::optional(9, "b", Any::Primitive(Primitive::Int)).into(),
+        ]);
+        let tags = Map {
+            key_id: 5,
+            key_type: Box::new(Any::Primitive(Primitive::String)),
+            value_id: 6,
+            value_required: true,
+            value_type: Box::new(Any::Struct(Arc::new(tag))),
+        };
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::required(1, "id", Any::Primitive(Primitive::Long)).into(),
+                Field::optional(2, "address", Any::Struct(Arc::new(address))).into(),
+                Field::optional(7, "tags", Any::Map(tags.clone())).into(),
+            ]),
+        );
+        let column_names = ["address.city", "tags.key", "id"].map(|s| s.to_string());
+        let projection = Projection::try_new(&schema, &column_names, &Struct::default()).unwrap();
+
+        let mut field_ids = projection.field_ids().collect::<Vec<_>>();
+        field_ids.sort();
+        assert_eq!(field_ids, vec![1, 3, 5]);
+        assert_eq!(projection.null_value_ids(), &HashSet::from([6]));
+        assert_eq!(
+            projection
+                .arrow_schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().as_str())
+                .collect::<Vec<_>>(),
+            vec!["address", "tags", "id"]
+        );
+        let DataType::Struct(address_fields) = projection.arrow_schema().field(0).data_type()
+        else {
+            panic!("address must be a struct");
+        };
+        assert_eq!(address_fields.len(), 1);
+        assert_eq!(address_fields[0].name(), "city");
+        // Values become optional if only keys are selected.
+        let DataType::Map(entries, _) = projection.arrow_schema().field(1).data_type() else {
+            panic!("tags must be a map");
+        };
+        let DataType::Struct(kv) = entries.data_type() else {
+            panic!("entries must be a struct");
+        };
+        assert!(kv[1].is_nullable());
+        assert_eq!(
+            kv[1].metadata().get(PARQUET_FIELD_ID_META_KEY),
+            Some(&"6".to_string())
+        );
+
+        assert!(
+            Projection::try_new(&schema, &["address.street".to_string()], &Struct::default())
+                .is_err()
+        );
+
+        // Keys are read with the selected fields of values.
+        let projection =
+            Projection::try_new(&schema, &["tags.value.b".to_string()], &Struct::default())
+                .unwrap();
+        let mut field_ids = projection.field_ids().collect::<Vec<_>>();
+        field_ids.sort();
+        assert_eq!(field_ids, vec![5, 9]);
+        assert!(projection.null_value_ids().is_empty());
+
+        // Leaves of map keys and the first leaf of values are read if only
+        // keys are selected, since maps can't be read without values.
+        let parquet_schema = SchemaDescriptor::new(Arc::new(
+            parse_message_type(
+                "
+                message schema {
+                    required int64 id = 1;
+                    optional group address = 2 {
+                        optional binary city (UTF8) = 3;
+                        optional int32 zip = 4;
+                    }
+                    optional group tags (MAP) = 7 {
+                        repeated group key_value {
+                            required binary key (UTF8) = 5;
+                            required group value = 6 {
+                                optional binary a (UTF8) = 8;
+                                optional int32 b = 9;
+                            }
+                        }
+                    }
+                }
+                ",
+            )
+            .unwrap(),
+        ));
+        let field_ids = HashSet::from([1, 3, 5]);
+        let null_value_ids = HashSet::from([6]);
+        assert_eq!(
+            leaf_columns(&parquet_schema, &field_ids, &null_value_ids),
+            vec![0, 1, 3, 4]
+        );
+        let field_ids = HashSet::from([5, 9]);
+        assert_eq!(
+            leaf_columns(&parquet_schema, &field_ids, &HashSet::new()),
+            vec![3, 5]
+        );
+        let field_ids = HashSet::from([1, 7]);
+        assert_eq!(
+            leaf_columns(&parquet_schema, &field_ids, &HashSet::new()),
+            vec![0, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_project_map_keys() {
+        let tags = Map {
+            key_id: 2,
+            key_type: Box::new(Any::Primitive(Primitive::String)),
+            value_id: 3,
+            value_required: true,
+            value_type: Box::new(Any::Primitive(Primitive::Int)),
+        };
+        let schema = Schema::new(
+            0,
+            None,
+            Struct::new(vec![
+                Field::optional(1, "tags", Any::Map(tags.clone())).into()
+            ]),
+        );
+        let projection =
+            Projection::try_new(&schema, &["tags.key".to_string()], &Struct::default()).unwrap();
+
+        // Batch read from data file, with the value of the map.
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.keys().append_value("b");
+        builder.values().append_value(2);
+        builder.append(true).unwrap();
+        builder.append(false).unwrap();
+        let column = Arc::new(builder.finish()) as ArrayRef;
+        let read_field = ArrowField::try_from(Field::optional(1, "tags", Any::Map(tags))).unwrap();
+        let read_field = read_field.with_data_type(column.data_type().clone());
+        let batch =
+            RecordBatch::try_new(Arc::new(ArrowSchema::new(vec![read_field])), vec![column])
+                .unwrap();
+
+        let batch = projection
+            .project(
+                &batch,
+                |id| (id == 1).then_some(0),
+                &MetadataValues {
+                    file_path: "",
+                    spec_id: 0,
+                    partition: &StructValue::default(),
+                    positions: None,
+                    is_deleted: None,
+                },
+            )
+            .unwrap();
+        let tags = batch.column(0).as_map();
+        assert_eq!(tags.len(), 2);
+        assert!(tags.is_null(1));
+        assert_eq!(
+            tags.keys().as_string::<i32>().iter().collect::<Vec<_>>(),
+            vec![Some("a"), Some("b")]
+        );
+        assert_eq!(tags.values().null_count(), 2);
+    }
+}
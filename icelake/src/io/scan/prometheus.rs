@@ -0,0 +1,280 @@
+//! Prometheus metrics of the read path.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use prometheus::{
+    core::{AtomicU64, GenericCounter},
+    histogram_opts, opts, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, Histogram, HistogramVec, IntCounterVec, Registry,
+    DEFAULT_BUCKETS,
+};
+
+use crate::TableIdentifier;
+
+use super::{ReadObserver, ReadObserverRef};
+
+const READER_METRICS_LABEL_NAMES: &[&str] = &["context", "catalog", "table"];
+
+#[derive(Clone)]
+pub(crate) struct ReaderMetricsDef {
+    bytes_read: IntCounterVec,
+    range_requests: IntCounterVec,
+    files_opened: IntCounterVec,
+    rows_produced: IntCounterVec,
+    rows_filtered: IntCounterVec,
+    delete_files_applied: IntCounterVec,
+
+    file_open_latency: HistogramVec,
+    file_scan_latency: HistogramVec,
+}
+
+impl ReaderMetricsDef {
+    fn new(registry: &Registry) -> Self {
+        let bytes_read = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_bytes_read",
+                "Iceberg reader bytes read from data files",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let range_requests = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_range_requests",
+                "Iceberg reader read requests of data files",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let files_opened = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_files_opened",
+                "Iceberg reader data files opened",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let rows_produced = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_rows_produced",
+                "Iceberg reader rows produced",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let rows_filtered = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_rows_filtered",
+                "Iceberg reader rows read but filtered out by deletes or offset",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let delete_files_applied = register_int_counter_vec_with_registry!(
+            opts!(
+                "iceberg_reader_delete_files_applied",
+                "Iceberg reader delete files applied to data files",
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let file_open_latency = register_histogram_vec_with_registry!(
+            histogram_opts!(
+                "iceberg_reader_file_open_latency",
+                "Iceberg reader latency of loading deletes and opening a data file",
+                DEFAULT_BUCKETS.to_vec(),
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        let file_scan_latency = register_histogram_vec_with_registry!(
+            histogram_opts!(
+                "iceberg_reader_file_scan_latency",
+                "Iceberg reader latency of scanning a data file",
+                DEFAULT_BUCKETS.to_vec(),
+            ),
+            READER_METRICS_LABEL_NAMES,
+            registry,
+        )
+        .unwrap();
+
+        Self {
+            bytes_read,
+            range_requests,
+            files_opened,
+            rows_produced,
+            rows_filtered,
+            delete_files_applied,
+
+            file_open_latency,
+            file_scan_latency,
+        }
+    }
+}
+
+/// Prometheus layer of the read path, set the observer of a table by
+/// `TableScanBuilder::with_observer(layer.observer(table_name))`.
+///
+/// Metrics are registered when the layer is created, so one layer should be
+/// created for a registry and shared by tables.
+#[derive(Clone)]
+pub struct ReaderPrometheusLayer {
+    context_id: String,
+    catalog_name: String,
+    def: ReaderMetricsDef,
+}
+
+impl ReaderPrometheusLayer {
+    /// Create reader context.
+    pub fn new(
+        context_id: impl ToString,
+        catalog_name: impl ToString,
+        registry: &Registry,
+    ) -> Self {
+        Self {
+            context_id: context_id.to_string(),
+            catalog_name: catalog_name.to_string(),
+            def: ReaderMetricsDef::new(registry),
+        }
+    }
+
+    /// Get catalog name
+    pub fn catalog_name(&self) -> &str {
+        &self.catalog_name
+    }
+
+    /// Create the observer collecting metrics of reading the table.
+    pub fn observer(&self, table_name: &TableIdentifier) -> ReadObserverRef {
+        Arc::new(self.reader_metrics(table_name))
+    }
+
+    pub(crate) fn reader_metrics(&self, table_name: &TableIdentifier) -> ReaderMetrics {
+        let table_name = format!("{}", table_name);
+        let label_values = [
+            self.context_id.as_str(),
+            self.catalog_name(),
+            table_name.as_str(),
+        ];
+
+        let def = &self.def;
+        ReaderMetrics {
+            bytes_read: def
+                .bytes_read
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            range_requests: def
+                .range_requests
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            files_opened: def
+                .files_opened
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            rows_produced: def
+                .rows_produced
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            rows_filtered: def
+                .rows_filtered
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            delete_files_applied: def
+                .delete_files_applied
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            file_open_latency: def
+                .file_open_latency
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+            file_scan_latency: def
+                .file_scan_latency
+                .get_metric_with_label_values(&label_values)
+                .unwrap(),
+        }
+    }
+}
+
+pub(crate) struct ReaderMetrics {
+    pub(crate) bytes_read: GenericCounter<AtomicU64>,
+    pub(crate) range_requests: GenericCounter<AtomicU64>,
+    pub(crate) files_opened: GenericCounter<AtomicU64>,
+    pub(crate) rows_produced: GenericCounter<AtomicU64>,
+    pub(crate) rows_filtered: GenericCounter<AtomicU64>,
+    pub(crate) delete_files_applied: GenericCounter<AtomicU64>,
+
+    pub(crate) file_open_latency: Histogram,
+    pub(crate) file_scan_latency: Histogram,
+}
+
+impl ReadObserver for ReaderMetrics {
+    fn file_opened(&self, elapsed: Duration) {
+        self.files_opened.inc();
+        self.file_open_latency.observe(elapsed.as_secs_f64());
+    }
+
+    fn range_read(&self, bytes: usize) {
+        self.range_requests.inc();
+        self.bytes_read.inc_by(bytes as u64);
+    }
+
+    fn deletes_applied(&self, delete_files: usize) {
+        self.delete_files_applied.inc_by(delete_files as u64);
+    }
+
+    fn batch_read(&self, rows_read: usize, rows_produced: usize) {
+        self.rows_produced.inc_by(rows_produced as u64);
+        self.rows_filtered
+            .inc_by(rows_read.saturating_sub(rows_produced) as u64);
+    }
+
+    fn file_scanned(&self, elapsed: Duration) {
+        self.file_scan_latency.observe(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader_metrics() {
+        let registry = Registry::new();
+        let layer = ReaderPrometheusLayer::new("test", "catalog", &registry);
+        let table_name = TableIdentifier::new(["db", "t"]).unwrap();
+        let observer = layer.observer(&table_name);
+        // Tables share the metrics registered by the layer.
+        layer.observer(&TableIdentifier::new(["db", "t2"]).unwrap());
+
+        observer.file_opened(Duration::from_millis(1));
+        observer.range_read(100);
+        observer.range_read(28);
+        observer.deletes_applied(2);
+        observer.batch_read(10, 7);
+        observer.file_scanned(Duration::from_millis(3));
+
+        let metrics = layer.reader_metrics(&table_name);
+        assert_eq!(metrics.files_opened.get(), 1);
+        assert_eq!(metrics.range_requests.get(), 2);
+        assert_eq!(metrics.bytes_read.get(), 128);
+        assert_eq!(metrics.delete_files_applied.get(), 2);
+        assert_eq!(metrics.rows_produced.get(), 7);
+        assert_eq!(metrics.rows_filtered.get(), 3);
+        assert_eq!(metrics.file_open_latency.get_sample_count(), 1);
+        assert_eq!(metrics.file_scan_latency.get_sample_count(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+//! Report of scan planning, like `ScanReport` of Java implementation.
+
+use std::time::Duration;
+
+use super::Predicate;
+use crate::TableIdentifier;
+
+/// Metrics of planning a scan, like `ScanMetricsResult` of Java
+/// implementation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanMetrics {
+    /// Duration of planning tasks.
+    pub total_planning_duration: Duration,
+    /// Number of data files in planned tasks.
+    pub result_data_files: u64,
+    /// Number of delete files in planned tasks, a delete file is counted
+    /// once for each task it applies to.
+    pub result_delete_files: u64,
+    /// Number of data manifests in manifest lists of the scanned snapshots.
+    pub total_data_manifests: u64,
+    /// Number of delete manifests in manifest lists of the scanned
+    /// snapshots.
+    pub total_delete_manifests: u64,
+    /// Number of data manifests read.
+    pub scanned_data_manifests: u64,
+    /// Number of data manifests skipped without reading.
+    pub skipped_data_manifests: u64,
+    /// Number of delete manifests read.
+    pub scanned_delete_manifests: u64,
+    /// Number of delete manifests skipped without reading.
+    pub skipped_delete_manifests: u64,
+    /// Total size of data files in planned tasks.
+    pub total_file_size_in_bytes: u64,
+    /// Total size of delete files in planned tasks.
+    pub total_delete_file_size_in_bytes: u64,
+    /// Number of live data files filtered out by partition or metrics.
+    pub skipped_data_files: u64,
+    /// Number of indexed delete files which apply to none of the planned
+    /// tasks.
+    pub skipped_delete_files: u64,
+    /// Number of live delete files indexed to find deletes of data files.
+    pub indexed_delete_files: u64,
+    /// Number of indexed equality delete files.
+    pub equality_delete_files: u64,
+    /// Number of indexed position delete files.
+    pub positional_delete_files: u64,
+}
+
+/// Report of a planned scan, it's returned by
+/// [`super::TableScan::plan_tasks_with_report`] and sent to the catalog of
+/// the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanReport {
+    /// Name of the scanned table.
+    pub table_name: TableIdentifier,
+    /// Id of the scanned snapshot.
+    pub snapshot_id: i64,
+    /// Filter of the scan.
+    pub filter: Predicate,
+    /// Id of the schema of the scanned snapshot.
+    pub schema_id: i32,
+    /// Ids of the projected root fields, including metadata columns.
+    pub projected_field_ids: Vec<i32>,
+    /// Names of the projected root fields, including metadata columns.
+    pub projected_field_names: Vec<String>,
+    /// Metrics of planning.
+    pub metrics: ScanMetrics,
+}
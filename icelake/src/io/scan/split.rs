@@ -0,0 +1,309 @@
+//! Split planning of table scan: large data files are split into
+//! row-group-aligned splits, and small splits are combined into tasks of the
+//! target size.
+
+use std::collections::VecDeque;
+
+use crate::config::ReadSplitConfig;
+use crate::types::DataFileFormat;
+use crate::{Result, Table};
+
+use super::{
+    file_scan_stream, CombinedScanTask, FileScanStream, FileScanTask, RowLimit, TableScan,
+};
+
+impl TableScan {
+    /// Plan the tasks of this scan, splitting and combining data files by the
+    /// `read.split.*` properties of table, see [`ReadSplitConfig`].
+    ///
+    /// Data files larger than the target size are split at
+    /// `DataFile::split_offsets`, or at the target size if the file has no
+    /// valid split offsets. Splits are bin-packed into combined tasks of
+    /// about the target size, each split weighs at least the open file cost.
+    pub async fn plan_combined_tasks(&self, table: &Table) -> Result<Vec<CombinedScanTask>> {
+        let config = match &table.current_table_metadata().properties {
+            Some(properties) => ReadSplitConfig::try_from(properties)?,
+            None => ReadSplitConfig::default(),
+        };
+
+        let splits = self
+            .plan_tasks(table)
+            .await?
+            .into_iter()
+            .flat_map(|task| split_task(task, config.target_size))
+            .collect::<Vec<_>>();
+
+        Ok(pack(
+            splits,
+            |task| split_weight(task, config.open_file_cost),
+            config.target_size,
+            config.planning_lookback,
+        )
+        .into_iter()
+        .map(|tasks| CombinedScanTask { tasks })
+        .collect())
+    }
+
+    /// Open a [`super::FileScan`] for each split of the combined task with
+    /// configurations of this scan, the task may be planned by another
+    /// process. Each file scan reads the row groups, or orc stripes, starting
+    /// in the byte range of its split.
+    ///
+    /// The limit of this scan is shared by the splits, like file scans of
+    /// [`TableScan::scan`].
+    pub fn open_combined_task(
+        &self,
+        table: &Table,
+        task: CombinedScanTask,
+    ) -> Result<FileScanStream> {
+        let limit = self.limit.map(RowLimit::new);
+        let file_scans = task
+            .tasks
+            .into_iter()
+            .map(|task| {
+                let mut file_scan = self.open_task(table, task)?;
+                file_scan.limit = limit.clone();
+                Ok(file_scan)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(file_scan_stream(file_scans, limit))
+    }
+}
+
+/// Split the task into tasks of about `target_size` bytes. Only parquet
+/// files are split, since splits of other formats are not aligned.
+fn split_task(task: FileScanTask, target_size: u64) -> Vec<FileScanTask> {
+    let file_size = task.data_file.file_size_in_bytes as u64;
+    if file_size <= target_size || task.data_file.file_format != DataFileFormat::Parquet {
+        return vec![task];
+    }
+
+    let ranges = match valid_split_offsets(task.data_file.split_offsets.as_deref(), file_size) {
+        Some(offsets) => {
+            // Merge adjacent ranges between split offsets until they reach
+            // the target size, the first split starts at the file header.
+            let mut ranges = vec![];
+            let mut start = 0;
+            for end in offsets[1..].iter().copied().chain([file_size]) {
+                if end - start >= target_size {
+                    ranges.push((start, end - start));
+                    start = end;
+                }
+            }
+            if start < file_size {
+                ranges.push((start, file_size - start));
+            }
+            ranges
+        }
+        None => (0..file_size)
+            .step_by(target_size as usize)
+            .map(|start| (start, target_size.min(file_size - start)))
+            .collect(),
+    };
+
+    ranges
+        .into_iter()
+        .map(|(start, length)| FileScanTask {
+            start,
+            length,
+            ..task.clone()
+        })
+        .collect()
+}
+
+/// Returns split offsets if they are ascending and in the file.
+fn valid_split_offsets(offsets: Option<&[i64]>, file_size: u64) -> Option<Vec<u64>> {
+    let offsets = offsets?;
+    let valid = !offsets.is_empty()
+        && offsets[0] >= 0
+        && offsets.windows(2).all(|w| w[0] < w[1])
+        && (offsets[offsets.len() - 1] as u64) < file_size;
+    valid.then(|| offsets.iter().map(|o| *o as u64).collect())
+}
+
+/// Weight of a split in bin packing, delete files are read with each split
+/// so their sizes are counted too.
+fn split_weight(task: &FileScanTask, open_file_cost: u64) -> u64 {
+    let delete_size: u64 = task
+        .position_deletes()
+        .chain(task.equality_deletes())
+        .map(|f| f.file_size_in_bytes as u64)
+        .sum();
+    (task.length + delete_size).max(open_file_cost)
+}
+
+/// Pack items into bins whose weights don't exceed `target_weight`, unless an
+/// item is heavier than it. Only the last `lookback` bins are open to new
+/// items, the oldest bin is closed when a new bin is opened. Order of items
+/// is kept in bins.
+fn pack<T>(
+    items: impl IntoIterator<Item = T>,
+    weight: impl Fn(&T) -> u64,
+    target_weight: u64,
+    lookback: usize,
+) -> Vec<Vec<T>> {
+    let mut packed = vec![];
+    let mut bins: VecDeque<(u64, Vec<T>)> = VecDeque::new();
+    for item in items {
+        let item_weight = weight(&item);
+        match bins
+            .iter_mut()
+            .find(|(bin_weight, _)| *bin_weight + item_weight <= target_weight)
+        {
+            Some((bin_weight, bin)) => {
+                *bin_weight += item_weight;
+                bin.push(item);
+            }
+            None => {
+                bins.push_back((item_weight, vec![item]));
+                if bins.len() > lookback {
+                    packed.extend(bins.pop_front().map(|(_, bin)| bin));
+                }
+            }
+        }
+    }
+    packed.extend(bins.into_iter().map(|(_, bin)| bin));
+
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow_array::{cast::AsArray, types::Int64Type};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::io::scan::Predicate;
+    use crate::test_utils;
+    use crate::types::{DataContentType, DataFile};
+
+    /// Read ids of the combined task with `scan`.
+    async fn combined_task_ids(
+        table: &Table,
+        scan: &TableScan,
+        task: CombinedScanTask,
+    ) -> Result<Vec<i64>> {
+        let batches = scan
+            .open_combined_task(table, task)?
+            .and_then(|file_scan| file_scan.scan())
+            .try_flatten()
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column_by_name("id")
+                    .unwrap()
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_split_task() {
+        let mut data_file = DataFile::new(
+            DataContentType::Data,
+            "data",
+            DataFileFormat::Parquet,
+            1,
+            300,
+        );
+        data_file.split_offsets = Some(vec![4, 100, 150, 200]);
+        let task = FileScanTask {
+            data_file,
+            sequence_number: 1,
+            spec_id: 0,
+            schema_id: 0,
+            start: 0,
+            length: 300,
+            position_deletes: vec![],
+            equality_deletes: vec![],
+            residual: Predicate::AlwaysTrue,
+        };
+        let ranges = |tasks: Vec<FileScanTask>| {
+            tasks
+                .iter()
+                .map(|t| (t.start, t.length))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranges(split_task(task.clone(), 300)), vec![(0, 300)]);
+        assert_eq!(
+            ranges(split_task(task.clone(), 100)),
+            vec![(0, 100), (100, 100), (200, 100)]
+        );
+
+        let mut task = task;
+        task.data_file.split_offsets = Some(vec![4, 200, 100]);
+        assert_eq!(
+            ranges(split_task(task.clone(), 120)),
+            vec![(0, 120), (120, 120), (240, 60)]
+        );
+
+        task.data_file.file_format = DataFileFormat::Avro;
+        assert_eq!(ranges(split_task(task, 100)), vec![(0, 300)]);
+    }
+
+    #[test]
+    fn test_pack() {
+        let packed = pack([5, 6, 2, 7, 3, 12, 1], |w| *w, 10, 2);
+        assert_eq!(packed, vec![vec![5, 2], vec![6, 3], vec![7, 1], vec![12]]);
+
+        let packed = pack([5, 6, 2, 7, 3, 12, 1], |w| *w, 10, 1);
+        assert_eq!(
+            packed,
+            vec![vec![5], vec![6, 2], vec![7, 3], vec![12], vec![1]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_combined_task() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file_with_row_groups(
+            &table,
+            "a",
+            &(0..30).collect::<Vec<_>>(),
+            10,
+        )
+        .await?;
+        assert_eq!(a.split_offsets.as_ref().unwrap().len(), 3);
+        test_utils::commit(&mut table, vec![a], vec![]).await?;
+        // Every row group is a split of its own combined task.
+        test_utils::update_metadata(&mut table, |metadata| {
+            metadata.properties = Some(HashMap::from([
+                ("read.split.target-size".to_string(), "1".to_string()),
+                ("read.split.open-file-cost".to_string(), "1".to_string()),
+            ]));
+        })
+        .await?;
+
+        let scan = table.new_scan_builder().build().unwrap();
+        let tasks = scan.plan_combined_tasks(&table).await?;
+        assert_eq!(tasks.len(), 3);
+        for (i, task) in tasks.iter().enumerate() {
+            // Tasks may be executed by another process.
+            let task = CombinedScanTask::from_json(task.to_json()?, &table)?;
+            let i = i as i64;
+            assert_eq!(
+                combined_task_ids(&table, &scan, task).await?,
+                (i * 10..i * 10 + 10).collect::<Vec<_>>()
+            );
+        }
+
+        // The limit is shared by splits of the combined task.
+        let task = CombinedScanTask {
+            tasks: tasks.into_iter().flat_map(|t| t.tasks).collect(),
+        };
+        let scan = table.new_scan_builder().with_limit(15).build().unwrap();
+        assert_eq!(
+            combined_task_ids(&table, &scan, task).await?,
+            (0..15).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+//! Tail reader follows a table and reads the data appended by new
+//! snapshots, like a streaming source.
+
+use std::time::Duration;
+
+use arrow_array::RecordBatch;
+use derive_builder::Builder;
+use futures::{stream::BoxStream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::{CursorRecordBatchStream, Predicate, ScanCursor, TableScan, TableScanBuilder};
+use crate::catalog::CatalogRef;
+use crate::table::TableIdentifier;
+use crate::types::{Snapshot, TableMetadata};
+use crate::{Error, ErrorKind, Result, Table};
+
+/// Position of a [`TailReader`], it's emitted with each batch so that the
+/// reader can be restarted from it after a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TailPosition {
+    /// Data appended up to the snapshot (inclusive) is read.
+    After {
+        #[serde(rename = "snapshot-id")]
+        snapshot_id: i64,
+    },
+    /// Data appended by the snapshot of the cursor is read up to the cursor.
+    Within(ScanCursor),
+}
+
+impl TailPosition {
+    /// Serialize the position to json.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse the position from json.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Reader of the data appended to a table since a position, it polls the
+/// catalog for new snapshots of the main branch and never ends.
+///
+/// Snapshots only adding data files are read, which is checked by their
+/// manifests, and `replace` snapshots are skipped since they don't change
+/// table data. Other snapshots may remove rows, which can't be expressed by
+/// appends, so the stream fails on them.
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+#[builder(setter(prefix = "with"))]
+pub struct TailReader {
+    catalog: CatalogRef,
+    table_name: TableIdentifier,
+    /// Position to start from. To read a table from the beginning, scan
+    /// the current snapshot first and tail after it.
+    start_from: TailPosition,
+    /// Interval to load the table again when no new snapshot is found.
+    #[builder(default = "Duration::from_secs(10)")]
+    poll_interval: Duration,
+    #[builder(default)]
+    column_names: Vec<String>,
+    #[builder(default = "Predicate::AlwaysTrue")]
+    filter: Predicate,
+    #[builder(default = "1024")]
+    batch_size: usize,
+}
+
+/// Batches read by the tail reader, each batch comes with the position after
+/// it.
+pub type TailStream = BoxStream<'static, Result<(RecordBatch, TailPosition)>>;
+
+struct TailState {
+    reader: TailReader,
+    table: Table,
+    position: TailPosition,
+    /// Id of the snapshot being read, with its batches.
+    batches: Option<(i64, CursorRecordBatchStream)>,
+}
+
+impl TailReader {
+    /// Load the table and start reading from `start_from`.
+    pub async fn stream(self) -> Result<TailStream> {
+        let table = self.catalog.clone().load_table(&self.table_name).await?;
+        let state = TailState {
+            position: self.start_from,
+            reader: self,
+            table,
+            batches: None,
+        };
+
+        Ok(Box::pin(futures::stream::try_unfold(
+            state,
+            |mut state| async move {
+                loop {
+                    // The stream is taken out of state while polled, and put
+                    // back before yielding.
+                    if let Some((snapshot_id, mut batches)) = state.batches.take() {
+                        if let Some((batch, cursor)) = batches.try_next().await? {
+                            state.position = TailPosition::Within(cursor);
+                            state.batches = Some((snapshot_id, batches));
+                            return Ok(Some(((batch, state.position), state)));
+                        }
+                        state.position = TailPosition::After { snapshot_id };
+                    }
+
+                    let metadata = state.table.current_table_metadata();
+                    let (snapshot, cursor) = match state.position {
+                        TailPosition::Within(cursor) => (
+                            metadata.snapshot(cursor.snapshot_id).ok_or_else(|| {
+                                Error::new(
+                                    ErrorKind::Unexpected,
+                                    format!("Snapshot {} not found!", cursor.snapshot_id),
+                                )
+                            })?,
+                            Some(cursor),
+                        ),
+                        TailPosition::After { snapshot_id } => {
+                            match next_snapshot(metadata, snapshot_id)? {
+                                Some(snapshot) => (snapshot, None),
+                                None => {
+                                    tokio::time::sleep(state.reader.poll_interval).await;
+                                    state.table = state
+                                        .reader
+                                        .catalog
+                                        .clone()
+                                        .load_table(&state.reader.table_name)
+                                        .await?;
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+
+                    let snapshot_id = snapshot.snapshot_id;
+                    if snapshot.operation() == Some("replace") {
+                        state.position = TailPosition::After { snapshot_id };
+                        continue;
+                    }
+                    if !TableScan::is_append_only(&state.table, snapshot).await? {
+                        return Err(Error::new(
+                            ErrorKind::IcebergFeatureUnsupported,
+                            format!(
+                                "Tail reader doesn't support snapshot {} with operation {:?}, which removes data files or adds delete files",
+                                snapshot_id,
+                                snapshot.operation()
+                            ),
+                        ));
+                    }
+
+                    let mut builder = TableScanBuilder::default()
+                        .with_op(state.table.operator())
+                        .with_snapshot_id(snapshot_id)
+                        .with_column_names(state.reader.column_names.clone())
+                        .with_filter(state.reader.filter.clone())
+                        .with_batch_size(state.reader.batch_size);
+                    if let Some(parent_id) = snapshot.parent_snapshot_id {
+                        builder = builder.with_from_snapshot_id(parent_id);
+                    }
+                    if let Some(cursor) = cursor {
+                        builder = builder.with_start_from(cursor);
+                    }
+                    let scan = builder
+                        .build()
+                        .map_err(|e| Error::new(ErrorKind::Unexpected, format!("{}", e)))?;
+                    let batches = scan.scan_with_cursor(&state.table).await?;
+                    state.batches = Some((snapshot_id, batches));
+                }
+            },
+        )))
+    }
+}
+
+/// Find the child of the snapshot in the ancestors of the current snapshot.
+/// Returns `None` if the snapshot is the current snapshot.
+fn next_snapshot(metadata: &TableMetadata, snapshot_id: i64) -> Result<Option<&Snapshot>> {
+    let mut next = None;
+    let mut current = metadata.current_snapshot()?;
+    while let Some(snapshot) = current {
+        if snapshot.snapshot_id == snapshot_id {
+            return Ok(next);
+        }
+        next = Some(snapshot);
+        current = snapshot
+            .parent_snapshot_id
+            .and_then(|parent_id| metadata.snapshot(parent_id));
+    }
+
+    Err(Error::new(
+        ErrorKind::Unexpected,
+        format!(
+            "Snapshot {} is not an ancestor of the current snapshot!",
+            snapshot_id
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use arrow_array::{cast::AsArray, types::Int64Type};
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::types::parse_table_metadata;
+
+    async fn next_ids(stream: &mut TailStream) -> Result<(Vec<i64>, TailPosition)> {
+        let (batch, position) = stream.next().await.unwrap()?;
+        let ids = batch
+            .column_by_name("id")
+            .unwrap()
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec();
+        Ok((ids, position))
+    }
+
+    fn tail_reader(table: &Table, start_from: TailPosition) -> TailReader {
+        TailReaderBuilder::default()
+            .with_catalog(table.catalog())
+            .with_table_name(table.table_name().clone())
+            .with_start_from(start_from)
+            .with_poll_interval(Duration::from_millis(10))
+            .with_batch_size(1)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tail_stream() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        let s1 = test_utils::commit(&mut table, vec![a], vec![]).await?;
+
+        // The reader polls the catalog until a new snapshot is committed.
+        let mut stream = tail_reader(&table, TailPosition::After { snapshot_id: s1 })
+            .stream()
+            .await?;
+        let b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        let (next, s2) = tokio::join!(next_ids(&mut stream), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            test_utils::commit(&mut table, vec![b.clone()], vec![]).await
+        });
+        let s2 = s2?;
+        let (ids, position) = next?;
+        assert_eq!(ids, vec![3]);
+        assert!(matches!(position, TailPosition::Within(c) if c.snapshot_id == s2));
+
+        // Replace snapshots are skipped.
+        let c = test_utils::write_data_file(&table, "c", &[3]).await?;
+        test_utils::rewrite(&mut table, "replace", &[b], vec![c]).await?;
+        let d = test_utils::write_data_file(&table, "d", &[4, 5]).await?;
+        let s4 = test_utils::commit(&mut table, vec![d], vec![]).await?;
+        let (ids, position) = next_ids(&mut stream).await?;
+        assert_eq!(ids, vec![4]);
+        let TailPosition::Within(cursor) = position else {
+            panic!("unexpected position {:?}", position);
+        };
+        assert_eq!(cursor.snapshot_id, s4);
+        assert_eq!(next_ids(&mut stream).await?.0, vec![5]);
+
+        // Resumes within the snapshot from the position of a batch.
+        let mut stream = tail_reader(&table, position).stream().await?;
+        assert_eq!(next_ids(&mut stream).await?.0, vec![5]);
+
+        // Snapshots adding delete files fail the stream.
+        let delete = test_utils::write_equality_delete_file(&table, "e", &[1]).await?;
+        test_utils::commit(&mut table, vec![], vec![delete]).await?;
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IcebergFeatureUnsupported);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_snapshot() {
+        let path = format!(
+            "{}/../testdata/no_hint_table/metadata/00005-032145b7-6a0c-4a53-bc3d-b7b571ccab3b.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let metadata = parse_table_metadata(&fs::read(path).unwrap()).unwrap();
+        let next_id = |snapshot_id| {
+            next_snapshot(&metadata, snapshot_id)
+                .unwrap()
+                .map(|s| s.snapshot_id)
+        };
+
+        assert_eq!(next_id(8380191719297762539), Some(402819370943546960));
+        assert_eq!(next_id(2966623707104393227), Some(6788296308394418127));
+        assert_eq!(next_id(6788296308394418127), None);
+        assert!(next_snapshot(&metadata, 1).is_err());
+    }
+
+    #[test]
+    fn test_tail_position_json() {
+        let positions = [
+            TailPosition::After {
+                snapshot_id: 6788296308394418127,
+            },
+            TailPosition::Within(ScanCursor {
+                snapshot_id: 6788296308394418127,
+                task_index: 1,
+                row: 10,
+            }),
+        ];
+        let jsons = [
+            r#"{"type":"after","snapshot-id":6788296308394418127}"#,
+            r#"{"type":"within","snapshot-id":6788296308394418127,"task-index":1,"row":10}"#,
+        ];
+        for (position, json) in positions.iter().zip(jsons) {
+            assert_eq!(position.to_json().unwrap(), json);
+            assert_eq!(TailPosition::from_json(json).unwrap(), *position);
+        }
+    }
+}
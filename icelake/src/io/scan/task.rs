@@ -0,0 +1,336 @@
+//! Planned tasks of table scan.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{data_file_from_json, data_file_to_json, DataFile};
+use crate::{Error, ErrorKind, Result, Table};
+
+use super::{DeleteFileEntry, Predicate};
+
+/// A task to scan a data file, planned by [`super::TableScan::plan_tasks`].
+///
+/// Tasks can be serialized with [`FileScanTask::to_json`], so that they can
+/// be planned by a coordinator and executed by workers.
+#[derive(Debug, Clone)]
+pub struct FileScanTask {
+    pub(crate) data_file: DataFile,
+    /// Data sequence number of the data file.
+    pub(crate) sequence_number: i64,
+    pub(crate) spec_id: i32,
+    /// Id of the schema of the scanned snapshot, which the residual and
+    /// partition values are bound to.
+    pub(crate) schema_id: i32,
+    /// Byte offset in the data file where the split to scan starts.
+    pub(crate) start: u64,
+    /// Length in bytes of the split to scan.
+    pub(crate) length: u64,
+    pub(crate) position_deletes: Vec<DeleteFileEntry>,
+    pub(crate) equality_deletes: Vec<DeleteFileEntry>,
+    pub(crate) residual: Predicate,
+}
+
+impl FileScanTask {
+    /// The data file to scan.
+    pub fn data_file(&self) -> &DataFile {
+        &self.data_file
+    }
+
+    /// Partition spec id of the data file.
+    pub fn spec_id(&self) -> i32 {
+        self.spec_id
+    }
+
+    /// Id of the schema which the residual and partition values are bound
+    /// to, it's the schema of the scanned snapshot.
+    pub fn schema_id(&self) -> i32 {
+        self.schema_id
+    }
+
+    /// Byte offset in the data file where the split to scan starts, row
+    /// groups starting in the split are scanned.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Length in bytes of the split to scan.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Position delete files which must be applied to the data file.
+    pub fn position_deletes(&self) -> impl Iterator<Item = &DataFile> {
+        self.position_deletes.iter().map(|e| &e.delete_file)
+    }
+
+    /// Equality delete files which must be applied to the data file.
+    pub fn equality_deletes(&self) -> impl Iterator<Item = &DataFile> {
+        self.equality_deletes.iter().map(|e| &e.delete_file)
+    }
+
+    /// The filter which still needs to be applied to rows of the data file,
+    /// after partition values have been evaluated.
+    pub fn residual(&self) -> &Predicate {
+        &self.residual
+    }
+
+    /// Serialize the task to json value.
+    pub fn to_json(&self) -> Result<serde_json::Value> {
+        let delete_file = |e: &DeleteFileEntry| -> Result<DeleteFileSerDe> {
+            Ok(DeleteFileSerDe {
+                spec_id: e.spec_id,
+                sequence_number: e.sequence_number,
+                file: data_file_to_json(e.delete_file.clone())?,
+            })
+        };
+        let task = FileScanTaskSerDe {
+            spec_id: self.spec_id,
+            schema_id: Some(self.schema_id),
+            sequence_number: self.sequence_number,
+            start: self.start,
+            length: self.length,
+            data_file: data_file_to_json(self.data_file.clone())?,
+            position_deletes: self
+                .position_deletes
+                .iter()
+                .map(delete_file)
+                .collect::<Result<_>>()?,
+            equality_deletes: self
+                .equality_deletes
+                .iter()
+                .map(delete_file)
+                .collect::<Result<_>>()?,
+            residual: self.residual.to_json()?,
+        };
+        serde_json::to_value(task).map_err(|e| {
+            Error::new(
+                ErrorKind::Unexpected,
+                "Failed to serialize file scan task to json",
+            )
+            .set_source(e)
+        })
+    }
+
+    /// Parse the task from json value, partition values and literals of the
+    /// residual are resolved with the schema and partition specs of the task
+    /// in the metadata of `table`. Tasks serialized without schema id are
+    /// resolved with the current schema.
+    pub fn from_json(value: serde_json::Value, table: &Table) -> Result<FileScanTask> {
+        let task = serde_json::from_value::<FileScanTaskSerDe>(value).map_err(|e| {
+            Error::new(
+                ErrorKind::Unexpected,
+                "Failed to parse file scan task from json",
+            )
+            .set_source(e)
+        })?;
+        let metadata = table.current_table_metadata();
+        let schema = match task.schema_id {
+            Some(schema_id) => metadata.schema(schema_id).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::IcebergDataInvalid,
+                    format!("Schema id {} of file scan task not found!", schema_id),
+                )
+            })?,
+            None => metadata.current_schema()?,
+        };
+
+        let delete_file = |e: DeleteFileSerDe| -> Result<DeleteFileEntry> {
+            Ok(DeleteFileEntry {
+                delete_file: data_file_from_json(
+                    e.file,
+                    table.partition_type_of(e.spec_id, schema)?,
+                )?,
+                sequence_number: e.sequence_number,
+                spec_id: e.spec_id,
+            })
+        };
+
+        Ok(FileScanTask {
+            data_file: data_file_from_json(
+                task.data_file,
+                table.partition_type_of(task.spec_id, schema)?,
+            )?,
+            sequence_number: task.sequence_number,
+            spec_id: task.spec_id,
+            schema_id: schema.schema_id,
+            start: task.start,
+            length: task.length,
+            position_deletes: task
+                .position_deletes
+                .into_iter()
+                .map(delete_file)
+                .collect::<Result<_>>()?,
+            equality_deletes: task
+                .equality_deletes
+                .into_iter()
+                .map(delete_file)
+                .collect::<Result<_>>()?,
+            residual: Predicate::from_json(task.residual, schema)?,
+        })
+    }
+}
+
+/// A task combining splits of small data files or a large data file, planned
+/// by [`super::TableScan::plan_combined_tasks`]. It's executed by
+/// [`super::TableScan::open_combined_task`], which scans the splits one by
+/// one.
+#[derive(Debug, Clone)]
+pub struct CombinedScanTask {
+    pub(crate) tasks: Vec<FileScanTask>,
+}
+
+impl CombinedScanTask {
+    /// Tasks of the combined splits.
+    pub fn tasks(&self) -> &[FileScanTask] {
+        &self.tasks
+    }
+
+    /// Consume the combined task, returns tasks of the combined splits.
+    pub fn into_tasks(self) -> Vec<FileScanTask> {
+        self.tasks
+    }
+
+    /// Total length in bytes of the combined splits.
+    pub fn length(&self) -> u64 {
+        self.tasks.iter().map(|t| t.length).sum()
+    }
+
+    /// Serialize the task to json value.
+    pub fn to_json(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::Value::Array(
+            self.tasks
+                .iter()
+                .map(|t| t.to_json())
+                .collect::<Result<_>>()?,
+        ))
+    }
+
+    /// Parse the task from json value, see [`FileScanTask::from_json`].
+    pub fn from_json(value: serde_json::Value, table: &Table) -> Result<CombinedScanTask> {
+        let serde_json::Value::Array(tasks) = value else {
+            return Err(Error::new(
+                ErrorKind::Unexpected,
+                "Failed to parse combined scan task from json: not an array",
+            ));
+        };
+        Ok(CombinedScanTask {
+            tasks: tasks
+                .into_iter()
+                .map(|t| FileScanTask::from_json(t, table))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileScanTaskSerDe {
+    spec_id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema_id: Option<i32>,
+    sequence_number: i64,
+    start: u64,
+    length: u64,
+    data_file: serde_json::Value,
+    position_deletes: Vec<DeleteFileSerDe>,
+    equality_deletes: Vec<DeleteFileSerDe>,
+    residual: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DeleteFileSerDe {
+    spec_id: i32,
+    sequence_number: i64,
+    file: serde_json::Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use crate::types::{PrimitiveValue, Schema, Struct};
+
+    fn assert_task_eq(left: &FileScanTask, right: &FileScanTask) {
+        let deletes = |entries: &[DeleteFileEntry]| {
+            entries
+                .iter()
+                .map(|e| (e.delete_file.clone(), e.sequence_number, e.spec_id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(left.data_file, right.data_file);
+        assert_eq!(left.sequence_number, right.sequence_number);
+        assert_eq!(left.spec_id, right.spec_id);
+        assert_eq!(left.schema_id, right.schema_id);
+        assert_eq!((left.start, left.length), (right.start, right.length));
+        assert_eq!(
+            deletes(&left.position_deletes),
+            deletes(&right.position_deletes)
+        );
+        assert_eq!(
+            deletes(&left.equality_deletes),
+            deletes(&right.equality_deletes)
+        );
+        assert_eq!(left.residual, right.residual);
+    }
+
+    #[tokio::test]
+    async fn test_task_json() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        a.split_offsets = Some(vec![4]);
+        test_utils::commit(&mut table, vec![a.clone()], vec![]).await?;
+        let position_deletes =
+            test_utils::write_position_delete_file(&table, "pd", &[(&a, 0)]).await?;
+        let equality_deletes = test_utils::write_equality_delete_file(&table, "ed", &[2]).await?;
+        test_utils::commit(&mut table, vec![], vec![position_deletes, equality_deletes]).await?;
+
+        let mut task = table
+            .new_scan_builder()
+            .with_filter(Predicate::Eq(1, PrimitiveValue::Long(1)))
+            .build()
+            .unwrap()
+            .plan_tasks(&table)
+            .await?
+            .remove(0);
+        task.start = 4;
+        task.length = 100;
+        assert_eq!(task.position_deletes.len(), 1);
+        assert_eq!(task.equality_deletes.len(), 1);
+        assert_ne!(task.residual, Predicate::AlwaysTrue);
+
+        // `id` is dropped by the current schema, the task of the snapshot
+        // written before is resolved with the schema of the snapshot.
+        test_utils::update_metadata(&mut table, |metadata| {
+            let schema = metadata.current_schema().unwrap();
+            let fields = schema
+                .fields()
+                .iter()
+                .filter(|f| f.id != 1)
+                .cloned()
+                .collect();
+            metadata
+                .schemas
+                .push(Schema::new(1, None, Struct::new(fields)));
+            metadata.current_schema_id = 1;
+        })
+        .await?;
+
+        let parsed = FileScanTask::from_json(task.to_json()?, &table)?;
+        assert_eq!(parsed.schema_id(), 0);
+        assert_task_eq(&parsed, &task);
+
+        let mut other = task.clone();
+        other.start = 0;
+        other.length = 4;
+        let combined = CombinedScanTask {
+            tasks: vec![other, task],
+        };
+        let parsed = CombinedScanTask::from_json(combined.to_json()?, &table)?;
+        assert_eq!(parsed.tasks().len(), 2);
+        for (left, right) in parsed.tasks().iter().zip(combined.tasks()) {
+            assert_task_eq(left, right);
+        }
+        assert_eq!(parsed.length(), 104);
+        Ok(())
+    }
+}
@@ -17,3 +17,6 @@ pub mod config;
 pub mod io;
 pub mod transaction;
 pub mod types;
+
+#[cfg(test)]
+mod test_utils;
@@ -6,13 +6,18 @@ use std::sync::Arc;
 use crate::catalog::CatalogRef;
 use crate::error::Result;
 use crate::io::writer_builder::{new_writer_builder, WriterBuilder};
-use crate::io::{EmptyLayer, TableScanBuilder};
+use crate::io::{EmptyLayer, FileCacheRef, TableScanBuilder};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
 use opendal::Operator;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::config::{TableConfig, TableConfigRef};
-use crate::types::{Any, DataFile, PartitionSplitter, Schema, Snapshot, TableMetadata};
+use crate::types::{
+    Any, ManifestEntry, ManifestFile, ManifestListEntry, PartitionSplitter, Schema, Snapshot,
+    TableMetadata,
+};
 use crate::{types, Error, ErrorKind};
 
 pub(crate) const META_ROOT_PATH: &str = "metadata";
@@ -97,6 +102,9 @@ pub struct Table {
 
     table_config: TableConfigRef,
 
+    /// Cache of decoded manifests, shared with scans of the table.
+    file_cache: Option<FileCacheRef>,
+
     // Catalog res
     catalog: CatalogRef,
 }
@@ -110,6 +118,7 @@ pub struct TableBuilder {
     current_version: Option<String>,
     current_table_version: Option<i64>,
     table_config: Option<TableConfigRef>,
+    file_cache: Option<FileCacheRef>,
 }
 
 impl TableBuilder {
@@ -125,6 +134,12 @@ impl TableBuilder {
         self
     }
 
+    /// Set cache of decoded manifests and parquet footers.
+    pub fn with_file_cache(mut self, cache: FileCacheRef) -> Self {
+        self.file_cache = Some(cache);
+        self
+    }
+
     /// Set current version.
     pub fn with_current_version(mut self, location: String) -> Self {
         self.current_version = Some(location);
@@ -180,6 +195,7 @@ impl TableBuilder {
             table_config: self
                 .table_config
                 .unwrap_or_else(|| Arc::new(TableConfig::default())),
+            file_cache: self.file_cache,
             catalog: self.catalog,
         })
     }
@@ -204,6 +220,7 @@ impl Table {
             current_version: None,
             current_table_version: None,
             table_config: None,
+            file_cache: None,
         }
     }
 
@@ -217,6 +234,22 @@ impl Table {
         self.catalog.clone()
     }
 
+    /// Returns configuration of the table.
+    pub(crate) fn table_config(&self) -> &TableConfigRef {
+        &self.table_config
+    }
+
+    /// Set cache of decoded manifests and parquet footers, it's shared by
+    /// tables and scans using the same cache.
+    pub fn set_file_cache(&mut self, cache: FileCacheRef) {
+        self.file_cache = Some(cache);
+    }
+
+    /// Returns cache of decoded manifests and parquet footers.
+    pub fn file_cache(&self) -> Option<&FileCacheRef> {
+        self.file_cache.as_ref()
+    }
+
     /// Fetch current table metadata.
     pub fn current_table_metadata(&self) -> &types::TableMetadata {
         let current_version = self.current_version.as_ref().expect("table must be loaded");
@@ -269,19 +302,138 @@ impl Table {
         &self,
         snapshot: &Snapshot,
     ) -> Result<Vec<types::DataFile>> {
-        let manifest_list_path = self.rel_path(&snapshot.manifest_list)?;
-        let manifest_list_content = self.op.read(&manifest_list_path).await?;
-        let manifest_list = types::parse_manifest_list(&manifest_list_content)?;
+        self.manifest_entries_of_snapshot(snapshot, |_| true, |_| true)
+            .await?
+            .map_ok(|(_, entry)| entry.data_file)
+            .try_collect()
+            .await
+    }
+
+    /// Returns all manifests of the snapshot, paired with their entries in
+    /// the manifest list.
+    ///
+    /// Snapshot ids and sequence numbers omitted in manifest entries are
+    /// inherited from the manifest list entry.
+    pub async fn manifests_of_snapshot(
+        &self,
+        snapshot: &Snapshot,
+    ) -> Result<Vec<(ManifestListEntry, ManifestFile)>> {
+        let manifest_list = self.manifest_list_of_snapshot(snapshot).await?;
+
+        futures::stream::iter(manifest_list.entries.clone())
+            .map(|manifest_list_entry| async move {
+                let manifest = self.read_manifest(&manifest_list_entry).await?;
+                let manifest = Arc::try_unwrap(manifest).unwrap_or_else(|m| (*m).clone());
+                Ok((manifest_list_entry, manifest))
+            })
+            .buffered(self.table_config.manifest_reader.concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Returns a stream of manifest entries of the snapshot, paired with the
+    /// partition spec id of their manifests.
+    ///
+    /// Manifests accepted by `manifest_filter` are read and decoded
+    /// concurrently, see [`crate::config::ManifestReaderConfig`]. Entries are
+    /// filtered by `entry_filter` as soon as their manifest is decoded, so
+    /// only accepted entries are kept in memory. Entries are yielded in the
+    /// order of manifests in the manifest list.
+    pub async fn manifest_entries_of_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        manifest_filter: impl Fn(&ManifestListEntry) -> bool,
+        entry_filter: impl Fn(&ManifestEntry) -> bool + Send + Sync + 'static,
+    ) -> Result<BoxStream<'_, Result<(i32, ManifestEntry)>>> {
+        let manifest_list = self.manifest_list_of_snapshot(snapshot).await?;
+        let manifest_list_entries = manifest_list
+            .entries
+            .iter()
+            .filter(|e| manifest_filter(e))
+            .cloned()
+            .collect::<Vec<_>>();
+        let entry_filter = Arc::new(entry_filter);
+
+        let stream = futures::stream::iter(manifest_list_entries)
+            .map(move |manifest_list_entry| {
+                let entry_filter = entry_filter.clone();
+                async move {
+                    let manifest = self.read_manifest(&manifest_list_entry).await?;
+                    let spec_id = manifest_list_entry.partition_spec_id;
+                    let entries = manifest
+                        .entries
+                        .iter()
+                        .filter(|e| entry_filter(e))
+                        .cloned()
+                        .map(|e| Ok((spec_id, e)))
+                        .collect::<Vec<_>>();
+                    Ok::<_, Error>(futures::stream::iter(entries))
+                }
+            })
+            .buffered(self.table_config.manifest_reader.concurrency)
+            .try_flatten();
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Read the manifest list of the snapshot, from the file cache if set.
+    pub(crate) async fn manifest_list_of_snapshot(
+        &self,
+        snapshot: &Snapshot,
+    ) -> Result<Arc<types::ManifestList>> {
+        if snapshot.has_inline_manifests() {
+            // Inline manifests are not cached since entries of cached
+            // manifests inherit fields from their manifest list entries.
+            return Ok(Arc::new(
+                snapshot
+                    .load_inline_manifest_list(
+                        &self.op,
+                        |path| self.rel_path(path),
+                        self.table_config.manifest_reader.concurrency,
+                    )
+                    .await?,
+            ));
+        }
+        let manifest_list = &snapshot.manifest_list;
+        let load = async {
+            let manifest_list_path = self.rel_path(manifest_list)?;
+            let manifest_list_content = self.op.read(&manifest_list_path).await?;
+            let manifest_list = types::parse_manifest_list(&manifest_list_content)?;
+            Ok::<_, Error>(manifest_list)
+        };
 
-        let mut data_files: Vec<DataFile> = Vec::new();
-        for manifest_list_entry in manifest_list.entries {
+        match &self.file_cache {
+            Some(cache) => cache.manifest_list(manifest_list, load).await,
+            None => Ok(Arc::new(load.await?)),
+        }
+    }
+
+    /// Read the manifest of the manifest list entry, from the file cache if
+    /// set. Entries of the manifest inherit omitted fields from the manifest
+    /// list entry.
+    async fn read_manifest(
+        &self,
+        manifest_list_entry: &ManifestListEntry,
+    ) -> Result<Arc<ManifestFile>> {
+        let load = async {
             let manifest_path = self.rel_path(&manifest_list_entry.manifest_path)?;
             let manifest_content = self.op.read(&manifest_path).await?;
-            let manifest = types::parse_manifest_file(&manifest_content)?;
-            data_files.extend(manifest.entries.into_iter().map(|v| v.data_file));
-        }
+            let mut manifest = types::parse_manifest_file(&manifest_content)?;
+            manifest
+                .entries
+                .iter_mut()
+                .for_each(|entry| entry.inherit_from(manifest_list_entry));
+            Ok::<_, Error>(manifest)
+        };
 
-        Ok(data_files)
+        match &self.file_cache {
+            Some(cache) => {
+                cache
+                    .manifest_file(&manifest_list_entry.manifest_path, load)
+                    .await
+            }
+            None => Ok(Arc::new(load.await?)),
+        }
     }
 
     /// Get the relpath related to the base of table location.
@@ -426,10 +578,14 @@ impl Table {
         self.op.clone()
     }
 
+    /// Create a builder of table scan, which scans the current snapshot by
+    /// default.
     pub fn new_scan_builder(&self) -> TableScanBuilder {
-        TableScanBuilder::default()
-            .with_op(self.operator())
-            .with_snapshot_id(self.current_table_metadata().current_snapshot_id.unwrap())
+        let builder = TableScanBuilder::default().with_op(self.operator());
+        match self.current_table_metadata().current_snapshot_id {
+            Some(snapshot_id) => builder.with_snapshot_id(snapshot_id),
+            None => builder,
+        }
     }
 }
 
@@ -438,6 +594,7 @@ mod tests {
     use std::env;
 
     use crate::catalog::IcebergStorageCatalog;
+    use crate::test_utils;
 
     use super::*;
 
@@ -498,4 +655,65 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_manifest_entries_of_snapshot() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let mut data_files = vec![];
+        for i in 0..6 {
+            let data_file = test_utils::write_data_file(&table, &format!("{i}"), &[i]).await?;
+            data_files.push(data_file.file_path.clone());
+            test_utils::commit(&mut table, vec![data_file], vec![]).await?;
+        }
+        let delete_file = test_utils::write_equality_delete_file(&table, "d", &[1]).await?;
+        let delete_path = delete_file.file_path.clone();
+        test_utils::commit(&mut table, vec![], vec![delete_file]).await?;
+        let data_file = test_utils::write_data_file(&table, "6", &[6]).await?;
+        data_files.push(data_file.file_path.clone());
+        test_utils::commit(&mut table, vec![data_file], vec![]).await?;
+        let snapshot = table.current_table_metadata().current_snapshot()?.unwrap();
+
+        // Manifests are read concurrently, entries are yielded in the order
+        // of the manifest list.
+        let manifest_list = table.manifest_list_of_snapshot(snapshot).await?;
+        assert_eq!(manifest_list.entries.len(), 8);
+        let paths = table
+            .manifest_entries_of_snapshot(snapshot, |_| true, |_| true)
+            .await?
+            .map_ok(|(_, entry)| entry.data_file.file_path)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut expected = data_files.clone();
+        expected.insert(6, delete_path);
+        assert_eq!(paths, expected);
+        let manifests = table.manifests_of_snapshot(snapshot).await?;
+        assert_eq!(
+            manifests
+                .iter()
+                .map(|(entry, _)| entry.manifest_path.as_str())
+                .collect::<Vec<_>>(),
+            manifest_list
+                .entries
+                .iter()
+                .map(|entry| entry.manifest_path.as_str())
+                .collect::<Vec<_>>(),
+        );
+
+        // Both manifests and entries are filtered.
+        let removed = data_files[2].clone();
+        let paths = table
+            .manifest_entries_of_snapshot(
+                snapshot,
+                |m| m.content == types::ManifestContentType::Data,
+                move |e| e.data_file.file_path != removed,
+            )
+            .await?
+            .map_ok(|(_, entry)| entry.data_file.file_path)
+            .try_collect::<Vec<_>>()
+            .await?;
+        data_files.remove(2);
+        assert_eq!(paths, data_files);
+
+        Ok(())
+    }
 }
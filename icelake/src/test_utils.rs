@@ -0,0 +1,615 @@
+//! Tables written to a temporary directory for tests.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+use parquet::arrow::{AsyncArrowWriter, PARQUET_FIELD_ID_META_KEY};
+use parquet::file::footer::parse_metadata;
+use parquet::file::properties::WriterProperties;
+use tempfile::TempDir;
+
+use crate::catalog::{IcebergStorageCatalog, MetadataUpdate, UpdateTable};
+use crate::transaction::Transaction;
+use crate::types::{
+    serialize_table_meta, DataContentType, DataFile, DataFileFormat, ManifestContentType,
+    ManifestEntry, ManifestFile, ManifestList, ManifestListWriter, ManifestMetadata,
+    ManifestStatus, ManifestWriter, SnapshotReferenceType, TableMetadata, MAIN_BRANCH,
+};
+use crate::{Error, ErrorKind, Result, Table};
+
+const TABLE_METADATA: &str = r#"{
+  "format-version" : 2,
+  "table-uuid" : "1b2d8c4e-5f0a-4d3b-9a6e-7c8f9a0b1c2d",
+  "location" : "{location}",
+  "last-sequence-number" : 0,
+  "last-updated-ms" : 1700000000000,
+  "last-column-id" : 2,
+  "current-schema-id" : 0,
+  "schemas" : [ {
+    "type" : "struct",
+    "schema-id" : 0,
+    "fields" : [ {
+      "id" : 1,
+      "name" : "id",
+      "required" : false,
+      "type" : "long"
+    }, {
+      "id" : 2,
+      "name" : "v",
+      "required" : false,
+      "type" : "string"
+    } ]
+  } ],
+  "default-spec-id" : 0,
+  "partition-specs" : [ {
+    "spec-id" : 0,
+    "fields" : [ ]
+  } ],
+  "last-partition-id" : 999,
+  "default-sort-order-id" : 0,
+  "sort-orders" : [ {
+    "order-id" : 0,
+    "fields" : [ ]
+  } ],
+  "properties" : { }
+}"#;
+
+/// Create an empty unpartitioned table with schema `id: long, v: string` in
+/// a temporary directory, which is removed when the returned `TempDir` is
+/// dropped.
+pub(crate) async fn create_table() -> Result<(TempDir, Table)> {
+    let dir = TempDir::new().unwrap();
+    let path = format!("{}/t", dir.path().to_str().unwrap());
+    std::fs::create_dir_all(format!("{path}/metadata")).unwrap();
+    // Paths in metadata are urls whose host is the name of the operator,
+    // which is empty for the file system.
+    std::fs::write(
+        format!("{path}/metadata/v1.metadata.json"),
+        TABLE_METADATA.replace("{location}", &format!("fs://{path}")),
+    )
+    .unwrap();
+    std::fs::write(format!("{path}/metadata/version-hint.text"), "1").unwrap();
+
+    let table = IcebergStorageCatalog::load_table(&path).await?;
+    Ok((dir, table))
+}
+
+/// Write the updated metadata of table as a new version, for updates not
+/// supported by transactions yet, like evolving schemas or partition specs.
+pub(crate) async fn update_metadata(
+    table: &mut Table,
+    update: impl FnOnce(&mut TableMetadata),
+) -> Result<()> {
+    let mut metadata = table.current_table_metadata().clone();
+    update(&mut metadata);
+    let version = table.current_table_version() + 1;
+    let op = table.operator();
+    op.write(
+        &format!("metadata/v{version}.metadata.json"),
+        serialize_table_meta(metadata)?,
+    )
+    .await?;
+    op.write("metadata/version-hint.text", version.to_string())
+        .await?;
+
+    let location = &table.current_table_metadata().location;
+    *table = IcebergStorageCatalog::load_table(location.trim_start_matches("fs://")).await?;
+    Ok(())
+}
+
+/// Write a data file of rows `(id, "v{id}")` to `data/{name}.parquet`.
+pub(crate) async fn write_data_file(table: &Table, name: &str, ids: &[i64]) -> Result<DataFile> {
+    let batch = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(vec![
+            field("id", DataType::Int64, 1),
+            field("v", DataType::Utf8, 2),
+        ])),
+        vec![
+            Arc::new(Int64Array::from(ids.to_vec())) as ArrayRef,
+            Arc::new(StringArray::from_iter_values(
+                ids.iter().map(|id| format!("v{id}")),
+            )),
+        ],
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    write_file(table, DataContentType::Data, name, batch).await
+}
+
+/// Write a data file of rows `(id, "v{id}")` to `data/{name}.parquet` in row
+/// groups of `row_group_size` rows, split offsets of the data file are the
+/// start offsets of row groups.
+pub(crate) async fn write_data_file_with_row_groups(
+    table: &Table,
+    name: &str,
+    ids: &[i64],
+    row_group_size: usize,
+) -> Result<DataFile> {
+    let batch = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(vec![
+            field("id", DataType::Int64, 1),
+            field("v", DataType::Utf8, 2),
+        ])),
+        vec![
+            Arc::new(Int64Array::from(ids.to_vec())) as ArrayRef,
+            Arc::new(StringArray::from_iter_values(
+                ids.iter().map(|id| format!("v{id}")),
+            )),
+        ],
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    let props = WriterProperties::builder()
+        .set_max_row_group_size(row_group_size)
+        .build();
+    let mut data_file =
+        write_file_with_props(table, DataContentType::Data, name, batch, Some(props)).await?;
+
+    let content = table
+        .operator()
+        .read(&format!("data/{name}.parquet"))
+        .await?;
+    let metadata = parse_metadata(&bytes::Bytes::from(content))?;
+    data_file.split_offsets = Some(
+        metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| row_group.column(0).byte_range().0 as i64)
+            .collect(),
+    );
+    Ok(data_file)
+}
+
+/// Write a data file of rows `(id, "v{id}")` to `data/{name}.parquet`
+/// without field ids, like files imported from Hive tables, columns are
+/// named by `names`.
+pub(crate) async fn write_data_file_without_field_ids(
+    table: &Table,
+    name: &str,
+    names: [&str; 2],
+    ids: &[i64],
+) -> Result<DataFile> {
+    let batch = RecordBatch::try_from_iter([
+        (
+            names[0],
+            Arc::new(Int64Array::from(ids.to_vec())) as ArrayRef,
+        ),
+        (
+            names[1],
+            Arc::new(StringArray::from_iter_values(
+                ids.iter().map(|id| format!("v{id}")),
+            )),
+        ),
+    ])
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    write_file(table, DataContentType::Data, name, batch).await
+}
+
+/// Write a position delete file of `(file_path, pos)` rows to
+/// `data/{name}.parquet`.
+pub(crate) async fn write_position_delete_file(
+    table: &Table,
+    name: &str,
+    deletes: &[(&DataFile, i64)],
+) -> Result<DataFile> {
+    let batch = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(vec![
+            field("file_path", DataType::Utf8, 2147483546),
+            field("pos", DataType::Int64, 2147483545),
+        ])),
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                deletes.iter().map(|(f, _)| f.file_path.as_str()),
+            )) as ArrayRef,
+            Arc::new(Int64Array::from_iter_values(
+                deletes.iter().map(|(_, pos)| *pos),
+            )),
+        ],
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    write_file(table, DataContentType::PostionDeletes, name, batch).await
+}
+
+/// Write an equality delete file deleting rows by `id` to
+/// `data/{name}.parquet`.
+pub(crate) async fn write_equality_delete_file(
+    table: &Table,
+    name: &str,
+    ids: &[i64],
+) -> Result<DataFile> {
+    let batch = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(vec![field("id", DataType::Int64, 1)])),
+        vec![Arc::new(Int64Array::from(ids.to_vec())) as ArrayRef],
+    )
+    .map_err(|e| Error::new(ErrorKind::ArrowError, format!("{}", e)))?;
+    let mut delete_file = write_file(table, DataContentType::EqualityDeletes, name, batch).await?;
+    delete_file.equality_ids = Some(vec![1]);
+    Ok(delete_file)
+}
+
+/// Write an orc data file of rows `(id, "v{id}")` to `data/{name}.orc`,
+/// each slice of `stripes` is written as a stripe. Orc types carry field ids
+/// unless the columns are named by `names`, like files imported from Hive
+/// tables. Streams are compressed by zlib if `zlib` is set.
+pub(crate) async fn write_orc_data_file(
+    table: &Table,
+    name: &str,
+    stripes: &[&[i64]],
+    names: Option<[&str; 2]>,
+    zlib: bool,
+) -> Result<DataFile> {
+    let compress = |data: Vec<u8>| {
+        if !zlib {
+            return data;
+        }
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        let chunk = encoder.finish().unwrap();
+        // Chunks start with 3 bytes of length, the last bit is unset for
+        // compressed chunks.
+        let header = (chunk.len() << 1) as u32;
+        [&header.to_le_bytes()[..3], &chunk].concat()
+    };
+
+    let mut buf = b"ORC".to_vec();
+    let mut stripe_infos = vec![];
+    let mut stripe_stats = vec![];
+    let mut split_offsets = vec![];
+    for ids in stripes {
+        let values = ids.iter().map(|id| format!("v{id}")).collect::<Vec<_>>();
+        let streams = [
+            (1, 1, orc_ints(ids, true)),
+            (
+                2,
+                2,
+                orc_ints(
+                    &values.iter().map(|v| v.len() as i64).collect::<Vec<_>>(),
+                    false,
+                ),
+            ),
+            (2, 1, values.concat().into_bytes()),
+        ];
+        let offset = buf.len();
+        split_offsets.push(offset as i64);
+        let mut footer = OrcProto::default();
+        for (column, kind, data) in streams {
+            let data = compress(data);
+            footer = footer.message(
+                1,
+                OrcProto::default()
+                    .uint(1, kind)
+                    .uint(2, column)
+                    .uint(3, data.len() as u64),
+            );
+            buf.extend(data);
+        }
+        let data_length = buf.len() - offset;
+        for _ in 0..3 {
+            footer = footer.message(2, OrcProto::default().uint(1, 0));
+        }
+        let footer = compress(footer.0);
+        buf.extend(&footer);
+        stripe_infos.push(
+            OrcProto::default()
+                .uint(1, offset as u64)
+                .uint(2, 0)
+                .uint(3, data_length as u64)
+                .uint(4, footer.len() as u64)
+                .uint(5, ids.len() as u64),
+        );
+
+        let (min, max) = (ids.iter().min().unwrap(), ids.iter().max().unwrap());
+        let (min_v, max_v) = (values.iter().min().unwrap(), values.iter().max().unwrap());
+        stripe_stats.push(
+            OrcProto::default()
+                .message(1, OrcProto::default().uint(1, ids.len() as u64))
+                .message(
+                    1,
+                    OrcProto::default().uint(1, ids.len() as u64).message(
+                        2,
+                        OrcProto::default()
+                            .uint(1, zigzag(*min))
+                            .uint(2, zigzag(*max)),
+                    ),
+                )
+                .message(
+                    1,
+                    OrcProto::default().uint(1, ids.len() as u64).message(
+                        4,
+                        OrcProto::default()
+                            .bytes(1, min_v.as_bytes())
+                            .bytes(2, max_v.as_bytes()),
+                    ),
+                ),
+        );
+    }
+
+    let mut metadata = OrcProto::default();
+    for stats in stripe_stats {
+        metadata = metadata.message(1, stats);
+    }
+    let metadata = compress(metadata.0);
+    let column_names = names.unwrap_or(["id", "v"]);
+    let column_type = |kind: u64, field_id: i32| {
+        let ty = OrcProto::default().uint(1, kind);
+        match names {
+            Some(_) => ty,
+            None => ty.message(
+                7,
+                OrcProto::default()
+                    .bytes(1, b"iceberg.id")
+                    .bytes(2, field_id.to_string().as_bytes()),
+            ),
+        }
+    };
+    let mut footer = OrcProto::default().uint(1, 3).uint(2, buf.len() as u64 - 3);
+    for stripe in stripe_infos {
+        footer = footer.message(3, stripe);
+    }
+    let footer = footer
+        .message(
+            4,
+            OrcProto::default()
+                .uint(1, 12)
+                .uint(2, 1)
+                .uint(2, 2)
+                .bytes(3, column_names[0].as_bytes())
+                .bytes(3, column_names[1].as_bytes()),
+        )
+        .message(4, column_type(4, 1))
+        .message(4, column_type(7, 2))
+        .uint(6, stripes.iter().map(|ids| ids.len() as u64).sum());
+    let footer = compress(footer.0);
+    let postscript = OrcProto::default()
+        .uint(1, footer.len() as u64)
+        .uint(2, zlib as u64)
+        .uint(3, 256 * 1024)
+        .uint(5, metadata.len() as u64)
+        .bytes(8000, b"ORC")
+        .0;
+    buf.extend(metadata);
+    buf.extend(footer);
+    let postscript_len = postscript.len() as u8;
+    buf.extend(postscript);
+    buf.push(postscript_len);
+
+    let path = format!("data/{name}.orc");
+    let file_size = buf.len() as i64;
+    table.operator().write(&path, buf).await?;
+    let mut data_file = DataFile::new(
+        DataContentType::Data,
+        format!("{}/{path}", table.current_table_metadata().location),
+        DataFileFormat::Orc,
+        stripes.iter().map(|ids| ids.len() as i64).sum(),
+        file_size,
+    );
+    data_file.split_offsets = Some(split_offsets);
+    Ok(data_file)
+}
+
+/// Protobuf message of orc metadata.
+#[derive(Default)]
+pub(crate) struct OrcProto(pub(crate) Vec<u8>);
+
+impl OrcProto {
+    pub(crate) fn uint(mut self, field: u64, value: u64) -> Self {
+        orc_varint(&mut self.0, field << 3);
+        orc_varint(&mut self.0, value);
+        self
+    }
+
+    pub(crate) fn bytes(mut self, field: u64, value: &[u8]) -> Self {
+        orc_varint(&mut self.0, field << 3 | 2);
+        orc_varint(&mut self.0, value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    pub(crate) fn message(self, field: u64, message: OrcProto) -> Self {
+        self.bytes(field, &message.0)
+    }
+}
+
+fn orc_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Integers in orc run length encoding version 1, as literals.
+pub(crate) fn orc_ints(values: &[i64], signed: bool) -> Vec<u8> {
+    let mut buf = vec![];
+    for chunk in values.chunks(128) {
+        buf.push((256 - chunk.len()) as u8);
+        for value in chunk {
+            orc_varint(
+                &mut buf,
+                if signed {
+                    zigzag(*value)
+                } else {
+                    *value as u64
+                },
+            );
+        }
+    }
+    buf
+}
+
+/// Booleans in orc byte run length encoding, as literals.
+pub(crate) fn orc_bools(values: &[bool]) -> Vec<u8> {
+    let bytes = values
+        .chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (idx, v)| byte | ((*v as u8) << (7 - idx)))
+        })
+        .collect::<Vec<_>>();
+    let mut buf = vec![];
+    for chunk in bytes.chunks(128) {
+        buf.push((256 - chunk.len()) as u8);
+        buf.extend_from_slice(chunk);
+    }
+    buf
+}
+
+fn field(name: &str, data_type: DataType, field_id: i32) -> ArrowField {
+    ArrowField::new(name, data_type, true).with_metadata(HashMap::from([(
+        PARQUET_FIELD_ID_META_KEY.to_string(),
+        field_id.to_string(),
+    )]))
+}
+
+async fn write_file(
+    table: &Table,
+    content: DataContentType,
+    name: &str,
+    batch: RecordBatch,
+) -> Result<DataFile> {
+    write_file_with_props(table, content, name, batch, None).await
+}
+
+async fn write_file_with_props(
+    table: &Table,
+    content: DataContentType,
+    name: &str,
+    batch: RecordBatch,
+    props: Option<WriterProperties>,
+) -> Result<DataFile> {
+    let mut buf = vec![];
+    let mut writer = AsyncArrowWriter::try_new(&mut buf, batch.schema(), 0, props)?;
+    writer.write(&batch).await?;
+    writer.close().await?;
+
+    let path = format!("data/{name}.parquet");
+    let file_size = buf.len() as i64;
+    table.operator().write(&path, buf).await?;
+    Ok(DataFile::new(
+        content,
+        format!("{}/{path}", table.current_table_metadata().location),
+        DataFileFormat::Parquet,
+        batch.num_rows() as i64,
+        file_size,
+    ))
+}
+
+/// Commit a snapshot adding the data and delete files by [`Transaction`].
+pub(crate) async fn commit(
+    table: &mut Table,
+    data_files: Vec<DataFile>,
+    delete_files: Vec<DataFile>,
+) -> Result<i64> {
+    let mut tx = Transaction::new(table);
+    tx.append_data_file(data_files);
+    tx.append_delete_file(delete_files);
+    tx.commit().await?;
+    Ok(table.current_table_metadata().current_snapshot_id.unwrap())
+}
+
+/// Commit a snapshot of the operation removing the `removed` data files and
+/// adding `added`, e.g. a `replace` snapshot like a compaction. Delete
+/// manifests are kept as they are.
+pub(crate) async fn rewrite(
+    table: &mut Table,
+    operation: &str,
+    removed: &[DataFile],
+    added: Vec<DataFile>,
+) -> Result<i64> {
+    let metadata = table.current_table_metadata().clone();
+    let snapshot = metadata.current_snapshot()?.unwrap().clone();
+    let snapshot_id = snapshot.snapshot_id + 1;
+    let sequence_number = metadata.last_sequence_number + 1;
+
+    let mut manifest_list = ManifestList { entries: vec![] };
+    let mut entries = vec![];
+    for (manifest_list_entry, manifest) in table.manifests_of_snapshot(&snapshot).await? {
+        if manifest_list_entry.content == ManifestContentType::Deletes {
+            manifest_list.entries.push(manifest_list_entry);
+            continue;
+        }
+        for mut entry in manifest.entries.into_iter().filter(|e| e.is_alive()) {
+            if removed
+                .iter()
+                .any(|f| f.file_path == entry.data_file.file_path)
+            {
+                entry.status = ManifestStatus::Deleted;
+                entry.snapshot_id = Some(snapshot_id);
+            } else {
+                entry.status = ManifestStatus::Existing;
+            }
+            entries.push(entry);
+        }
+    }
+    entries.extend(added.into_iter().map(|data_file| ManifestEntry {
+        status: ManifestStatus::Added,
+        snapshot_id: Some(snapshot_id),
+        sequence_number: Some(sequence_number),
+        file_sequence_number: Some(sequence_number),
+        data_file,
+    }));
+
+    let manifest_list_entry = ManifestWriter::new(
+        metadata.current_partition_spec()?.clone(),
+        table.operator(),
+        metadata.location.as_str(),
+        Table::metadata_path(format!("rewrite-{snapshot_id}-m0.avro")),
+        snapshot_id,
+        sequence_number,
+    )
+    .write(ManifestFile {
+        metadata: ManifestMetadata {
+            schema: metadata.current_schema()?.clone(),
+            schema_id: metadata.current_schema_id,
+            partition_spec: metadata.current_partition_spec()?.clone(),
+            format_version: Some(metadata.format_version),
+            content: ManifestContentType::Data,
+        },
+        entries,
+    })
+    .await?;
+    manifest_list.entries.insert(0, manifest_list_entry);
+
+    let manifest_list_path = Table::metadata_path(format!("snap-{snapshot_id}-rewrite.avro"));
+    ManifestListWriter::new(
+        table.operator(),
+        manifest_list_path.clone(),
+        snapshot_id,
+        Some(snapshot.snapshot_id),
+        sequence_number,
+    )
+    .write(manifest_list)
+    .await?;
+
+    let mut new_snapshot = snapshot.clone();
+    new_snapshot.snapshot_id = snapshot_id;
+    new_snapshot.parent_snapshot_id = Some(snapshot.snapshot_id);
+    new_snapshot.sequence_number = sequence_number;
+    new_snapshot.manifest_list = format!("{}/{manifest_list_path}", metadata.location);
+    new_snapshot.summary = HashMap::from([("operation".to_string(), operation.to_string())]);
+
+    let mut builder = UpdateTable::builder(table.table_name().clone());
+    builder.add_updates(vec![
+        MetadataUpdate::AddSnapshot {
+            snapshot: new_snapshot,
+        },
+        MetadataUpdate::SetSnapshotRef {
+            snapshot_id,
+            ref_name: MAIN_BRANCH.to_string(),
+            typ: SnapshotReferenceType::Branch,
+            min_snapshots_to_keep: None,
+            max_snapshot_ages: None,
+            max_ref_ages: None,
+        },
+    ]);
+    *table = table.catalog().update_table(&builder.build()).await?;
+    Ok(snapshot_id)
+}
@@ -9,6 +9,7 @@ use crate::types::{
 };
 use crate::Table;
 use opendal::Operator;
+use std::collections::HashMap;
 use std::mem::swap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
@@ -192,24 +193,32 @@ impl<'a> Transaction<'a> {
             }
         }
 
+        // Deletes of rows are recorded by delete files, data files are never
+        // removed by a transaction.
+        let operation = match (
+            data_manifest_entries.is_empty(),
+            delete_manifest_entries.is_empty(),
+        ) {
+            (_, true) => "append",
+            (true, false) => "delete",
+            (false, false) => "overwrite",
+        };
+
         let manifest_list_path = {
-            let data_manifest_list_entry = Self::produce_new_manifest_list_entry(
-                data_manifest_entries,
-                ManifestContentType::Data,
-                table,
-                cur_metadata,
-                &mut ctx,
-                next_snapshot_id,
-                next_seq_number,
-            )
-            .await?;
-            let delete_manifest_list_entry = if delete_manifest_entries.is_empty() {
-                None
-            } else {
-                Some(
+            // Manifests without entries are not written, since empty avro
+            // files have no header and can't be read.
+            let mut new_manifest_list_entries = Vec::with_capacity(2);
+            for (manifest_entries, content) in [
+                (data_manifest_entries, ManifestContentType::Data),
+                (delete_manifest_entries, ManifestContentType::Deletes),
+            ] {
+                if manifest_entries.is_empty() {
+                    continue;
+                }
+                new_manifest_list_entries.push(
                     Self::produce_new_manifest_list_entry(
-                        delete_manifest_entries,
-                        ManifestContentType::Deletes,
+                        manifest_entries,
+                        content,
                         table,
                         cur_metadata,
                         &mut ctx,
@@ -217,26 +226,14 @@ impl<'a> Transaction<'a> {
                         next_seq_number,
                     )
                     .await?,
-                )
-            };
+                );
+            }
             // Load existing manifest list
-            let manifest_list = match cur_metadata.current_snapshot()? {
-                Some(s) => {
-                    let mut ret = s.load_manifest_list(&ctx.io).await?;
-                    ret.entries.push(data_manifest_list_entry);
-                    if let Some(delete_manifest_list_entry) = delete_manifest_list_entry {
-                        ret.entries.push(delete_manifest_list_entry);
-                    }
-                    ret
-                }
-                None => {
-                    let mut entries = vec![data_manifest_list_entry];
-                    if let Some(delete_manifest_list_entry) = delete_manifest_list_entry {
-                        entries.push(delete_manifest_list_entry);
-                    }
-                    ManifestList { entries }
-                }
+            let mut manifest_list = match cur_metadata.current_snapshot()? {
+                Some(s) => table.manifest_list_of_snapshot(s).await?.as_ref().clone(),
+                None => ManifestList { entries: vec![] },
             };
+            manifest_list.entries.extend(new_manifest_list_entries);
 
             let manifest_list_path = Transaction::manifest_list_path(&mut ctx, next_snapshot_id);
             // Writing manifest list
@@ -267,9 +264,87 @@ impl<'a> Transaction<'a> {
         new_snapshot.timestamp_ms =
             SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
         new_snapshot.manifest_list = manifest_list_path;
+        new_snapshot.manifests = None;
         new_snapshot.schema_id = Some(cur_metadata.current_schema_id as i64);
+        new_snapshot.summary = HashMap::from([("operation".to_string(), operation.to_string())]);
 
-        // TODO: Add operations
         Ok(new_snapshot)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Result;
+    use crate::test_utils;
+    use crate::types::{ManifestContentType, ManifestStatus};
+    use crate::Table;
+
+    /// Operation and content types of the manifests of the current snapshot.
+    async fn current_snapshot(table: &Table) -> Result<(String, Vec<ManifestContentType>)> {
+        let snapshot = table.current_table_metadata().current_snapshot()?.unwrap();
+        let manifest_list = table.manifest_list_of_snapshot(snapshot).await?;
+        Ok((
+            snapshot.operation().unwrap().to_string(),
+            manifest_list.entries.iter().map(|e| e.content).collect(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_operation() -> Result<()> {
+        let (_dir, mut table) = test_utils::create_table().await?;
+        let a = test_utils::write_data_file(&table, "a", &[1, 2]).await?;
+        test_utils::commit(&mut table, vec![a], vec![]).await?;
+        // Only a data manifest is written for appends.
+        assert_eq!(
+            current_snapshot(&table).await?,
+            ("append".to_string(), vec![ManifestContentType::Data])
+        );
+
+        let d = test_utils::write_equality_delete_file(&table, "d", &[1]).await?;
+        test_utils::commit(&mut table, vec![], vec![d]).await?;
+        // The delete commit adds only a delete manifest, no empty data
+        // manifest.
+        assert_eq!(
+            current_snapshot(&table).await?,
+            (
+                "delete".to_string(),
+                vec![ManifestContentType::Data, ManifestContentType::Deletes]
+            )
+        );
+
+        let b = test_utils::write_data_file(&table, "b", &[3]).await?;
+        let e = test_utils::write_equality_delete_file(&table, "e", &[2]).await?;
+        test_utils::commit(&mut table, vec![b], vec![e]).await?;
+        assert_eq!(
+            current_snapshot(&table).await?,
+            (
+                "overwrite".to_string(),
+                vec![
+                    ManifestContentType::Data,
+                    ManifestContentType::Deletes,
+                    ManifestContentType::Data,
+                    ManifestContentType::Deletes,
+                ]
+            )
+        );
+
+        // Every written manifest can be read back and holds the files added
+        // by its snapshot.
+        let metadata = table.current_table_metadata();
+        let snapshot = metadata.current_snapshot()?.unwrap();
+        let manifests = table.manifests_of_snapshot(snapshot).await?;
+        let files = manifests
+            .iter()
+            .map(|(entry, manifest)| {
+                assert!(manifest
+                    .entries
+                    .iter()
+                    .all(|e| e.status == ManifestStatus::Added
+                        && e.snapshot_id == Some(entry.added_snapshot_id)));
+                manifest.entries.len()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec![1, 1, 1, 1]);
+        Ok(())
+    }
+}
@@ -238,7 +238,26 @@ pub fn to_anyvalue_array_with_type(
                 "Time32Nanosecond is not supported",
             )),
         },
-        DataType::Time64(_) => todo!(),
+        DataType::Time64(unit) => match unit {
+            TimeUnit::Microsecond => array
+                .as_any()
+                .downcast_ref::<arrow_array::Time64MicrosecondArray>()
+                .unwrap()
+                .to_anyvalue_array(),
+            TimeUnit::Nanosecond => array
+                .as_any()
+                .downcast_ref::<arrow_array::Time64NanosecondArray>()
+                .unwrap()
+                .to_anyvalue_array(),
+            TimeUnit::Second => Err(Error::new(
+                crate::ErrorKind::DataTypeUnsupported,
+                "Time64Second is not supported",
+            )),
+            TimeUnit::Millisecond => Err(Error::new(
+                crate::ErrorKind::DataTypeUnsupported,
+                "Time64Millisecond is not supported",
+            )),
+        },
         DataType::Duration(_) => todo!(),
         DataType::Interval(_) => todo!(),
         DataType::FixedSizeBinary(_) => todo!(),
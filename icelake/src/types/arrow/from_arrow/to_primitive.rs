@@ -169,6 +169,28 @@ impl ToPrimitiveValue for i64 {
                     Ok(PrimitiveValue::Timestampz(Utc.from_utc_datetime(&dt)))
                 }
             }
+            DataType::Time64(unit) => {
+                let nanos = match unit {
+                    TimeUnit::Microsecond => self.checked_mul(1_000),
+                    TimeUnit::Nanosecond => Some(self),
+                    _ => None,
+                };
+                nanos
+                    .filter(|nanos| (0..86_400_000_000_000).contains(nanos))
+                    .and_then(|nanos| {
+                        NaiveTime::from_num_seconds_from_midnight_opt(
+                            (nanos / 1_000_000_000) as u32,
+                            (nanos % 1_000_000_000) as u32,
+                        )
+                    })
+                    .map(PrimitiveValue::Time)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::DataTypeUnsupported,
+                            format!("Cannot convert i64 to {:?}: time out of range ", data_type),
+                        )
+                    })
+            }
             _ => Err(Error::new(
                 ErrorKind::DataTypeUnsupported,
                 format!("Cannot convert i64 to {:?}", data_type),
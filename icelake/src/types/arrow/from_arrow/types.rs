@@ -21,7 +21,7 @@ impl TryFrom<ArrowDataType> for Any {
             }
             .into(),
             ArrowDataType::Date32 => types::Primitive::Date.into(),
-            ArrowDataType::Time32(TimeUnit::Microsecond) => types::Primitive::Time.into(),
+            ArrowDataType::Time64(TimeUnit::Microsecond) => types::Primitive::Time.into(),
             ArrowDataType::Timestamp(TimeUnit::Microsecond, None) => {
                 types::Primitive::Timestamp.into()
             }
@@ -10,6 +10,7 @@ use chrono::NaiveDateTime;
 use chrono::NaiveTime;
 use chrono::Utc;
 use chrono::{DateTime, Datelike};
+use futures::{StreamExt, TryStreamExt};
 use opendal::Operator;
 use ordered_float::OrderedFloat;
 use parquet::format::FileMetaData;
@@ -20,10 +21,10 @@ use serde::Serialize;
 use std::hash::Hash;
 use uuid::Uuid;
 
-use crate::types::parse_manifest_list;
+use crate::types::parse_manifest_file;
+use crate::Error;
 use crate::ErrorKind;
 use crate::Result;
-use crate::{Error, Table};
 
 pub(crate) const UNASSIGNED_SEQ_NUM: i64 = -1;
 pub(crate) const MAIN_BRANCH: &str = "main";
@@ -239,6 +240,30 @@ pub enum PrimitiveValue {
     Binary(Vec<u8>),
 }
 
+/// Values of the same type are ordered by the sort order of Iceberg, values
+/// of different types are not comparable.
+impl PartialOrd for PrimitiveValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (PrimitiveValue::Boolean(l), PrimitiveValue::Boolean(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Int(l), PrimitiveValue::Int(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Long(l), PrimitiveValue::Long(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Float(l), PrimitiveValue::Float(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Double(l), PrimitiveValue::Double(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Decimal(l), PrimitiveValue::Decimal(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Date(l), PrimitiveValue::Date(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Time(l), PrimitiveValue::Time(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Timestamp(l), PrimitiveValue::Timestamp(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Timestampz(l), PrimitiveValue::Timestampz(r)) => l.partial_cmp(r),
+            (PrimitiveValue::String(l), PrimitiveValue::String(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Uuid(l), PrimitiveValue::Uuid(r)) => l.partial_cmp(r),
+            (PrimitiveValue::Fixed(l), PrimitiveValue::Fixed(r))
+            | (PrimitiveValue::Binary(l), PrimitiveValue::Binary(r)) => l.partial_cmp(r),
+            _ => None,
+        }
+    }
+}
+
 impl From<PrimitiveValue> for AnyValue {
     fn from(value: PrimitiveValue) -> Self {
         AnyValue::Primitive(value)
@@ -272,6 +297,103 @@ impl Serialize for PrimitiveValue {
     }
 }
 
+impl PrimitiveValue {
+    /// Decode the value of type `ty` from the binary single-value
+    /// serialization, which is used by bounds in manifests.
+    ///
+    /// Values of promoted types may be stored with the original type, like
+    /// int values of long columns.
+    ///
+    /// Ref: <https://iceberg.apache.org/spec/#binary-single-value-serialization>
+    pub fn try_from_bytes(ty: &Primitive, bytes: &[u8]) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!("Invalid binary value {:?} of type {:?}", bytes, ty),
+            )
+        };
+        let le_i32 = || {
+            <[u8; 4]>::try_from(bytes)
+                .map(i32::from_le_bytes)
+                .map_err(|_| invalid())
+        };
+        let le_i64 = || match bytes.len() {
+            4 => le_i32().map(|v| v as i64),
+            _ => <[u8; 8]>::try_from(bytes)
+                .map(i64::from_le_bytes)
+                .map_err(|_| invalid()),
+        };
+        let le_f32 = || {
+            <[u8; 4]>::try_from(bytes)
+                .map(f32::from_le_bytes)
+                .map_err(|_| invalid())
+        };
+        let timestamp = || NaiveDateTime::from_timestamp_micros(le_i64()?).ok_or_else(invalid);
+
+        let value = match ty {
+            Primitive::Boolean => PrimitiveValue::Boolean(*bytes.first().ok_or_else(invalid)? != 0),
+            Primitive::Int => PrimitiveValue::Int(le_i32()?),
+            Primitive::Long => PrimitiveValue::Long(le_i64()?),
+            Primitive::Float => PrimitiveValue::Float(le_f32()?.into()),
+            Primitive::Double => PrimitiveValue::Double(
+                match bytes.len() {
+                    4 => le_f32()? as f64,
+                    _ => <[u8; 8]>::try_from(bytes)
+                        .map(f64::from_le_bytes)
+                        .map_err(|_| invalid())?,
+                }
+                .into(),
+            ),
+            Primitive::Decimal { scale, .. } => {
+                // Unscaled value in big-endian two's complement.
+                if bytes.len() > 16 {
+                    return Err(invalid());
+                }
+                let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+                let mut buf = if negative { [0xff; 16] } else { [0; 16] };
+                buf[16 - bytes.len()..].copy_from_slice(bytes);
+                PrimitiveValue::Decimal(
+                    Decimal::try_from_i128_with_scale(i128::from_be_bytes(buf), *scale as u32)
+                        .map_err(|_| invalid())?,
+                )
+            }
+            Primitive::Date => PrimitiveValue::Date(
+                NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap()
+                    .checked_add_signed(chrono::Duration::days(le_i32()? as i64))
+                    .ok_or_else(invalid)?,
+            ),
+            Primitive::Time => {
+                let micros = le_i64()?;
+                if micros < 0 {
+                    return Err(invalid());
+                }
+                PrimitiveValue::Time(
+                    NaiveTime::from_num_seconds_from_midnight_opt(
+                        (micros / 1_000_000) as u32,
+                        (micros % 1_000_000 * 1000) as u32,
+                    )
+                    .ok_or_else(invalid)?,
+                )
+            }
+            Primitive::Timestamp => PrimitiveValue::Timestamp(timestamp()?),
+            Primitive::Timestampz => PrimitiveValue::Timestampz(
+                DateTime::<Utc>::from_naive_utc_and_offset(timestamp()?, Utc),
+            ),
+            Primitive::String => {
+                PrimitiveValue::String(std::str::from_utf8(bytes).map_err(|_| invalid())?.into())
+            }
+            Primitive::Uuid => {
+                PrimitiveValue::Uuid(Uuid::from_slice(bytes).map_err(|_| invalid())?)
+            }
+            Primitive::Fixed(_) => PrimitiveValue::Fixed(bytes.to_vec()),
+            Primitive::Binary => PrimitiveValue::Binary(bytes.to_vec()),
+        };
+
+        Ok(value)
+    }
+}
+
 /// A struct is a tuple of typed values.
 ///
 /// - Each field in the tuple is named and has an integer id that is unique in the table schema.
@@ -1127,6 +1249,43 @@ pub struct ManifestListEntry {
     pub key_metadata: Option<Vec<u8>>,
 }
 
+impl ManifestListEntry {
+    /// Entry of a manifest listed inline by a v1 snapshot. Like Java
+    /// implementation, the manifest is considered as added by the snapshot,
+    /// and counts are computed from its entries.
+    pub(crate) fn of_inline_manifest(
+        manifest_path: String,
+        manifest_length: i64,
+        snapshot_id: i64,
+        manifest: &ManifestFile,
+    ) -> Self {
+        let mut files_count = [0; 3];
+        let mut rows_count = [0; 3];
+        for entry in &manifest.entries {
+            files_count[entry.status as usize] += 1;
+            rows_count[entry.status as usize] += entry.data_file.record_count;
+        }
+
+        Self {
+            manifest_path,
+            manifest_length,
+            partition_spec_id: manifest.metadata.partition_spec.spec_id,
+            content: manifest.metadata.content,
+            sequence_number: 0,
+            min_sequence_number: 0,
+            added_snapshot_id: snapshot_id,
+            added_data_files_count: files_count[ManifestStatus::Added as usize],
+            existing_data_files_count: files_count[ManifestStatus::Existing as usize],
+            deleted_data_files_count: files_count[ManifestStatus::Deleted as usize],
+            added_rows_count: rows_count[ManifestStatus::Added as usize],
+            existing_rows_count: rows_count[ManifestStatus::Existing as usize],
+            deleted_rows_count: rows_count[ManifestStatus::Deleted as usize],
+            partitions: None,
+            key_metadata: None,
+        }
+    }
+}
+
 mod manifest_list {
     use super::*;
     use once_cell::sync::Lazy;
@@ -1262,6 +1421,32 @@ impl ManifestEntry {
             ManifestStatus::Added | ManifestStatus::Existing
         )
     }
+
+    /// Fill the snapshot id and sequence numbers omitted in manifest file
+    /// with the values of the manifest list entry tracking this manifest.
+    ///
+    /// Reference:
+    ///
+    /// - [Sequence Number Inheritance](https://iceberg.apache.org/spec/#sequence-number-inheritance)
+    pub(crate) fn inherit_from(&mut self, manifest: &ManifestListEntry) {
+        if self.snapshot_id.is_none() {
+            self.snapshot_id = Some(manifest.added_snapshot_id);
+        }
+
+        if self.status == ManifestStatus::Added {
+            if self.sequence_number.is_none() {
+                self.sequence_number = Some(manifest.sequence_number);
+            }
+            if self.file_sequence_number.is_none() {
+                self.file_sequence_number = Some(manifest.sequence_number);
+            }
+        }
+    }
+
+    /// Data sequence number of the file, `0` for files of v1 tables.
+    pub fn data_sequence_number(&self) -> i64 {
+        self.sequence_number.unwrap_or(0)
+    }
 }
 
 mod manifest_file {
@@ -1955,7 +2140,12 @@ pub struct Snapshot {
     pub timestamp_ms: i64,
     /// The location of a manifest list for this snapshot that tracks
     /// manifest files with additional metadata
+    ///
+    /// It's empty for v1 snapshots which list `manifests` inline instead.
     pub manifest_list: String,
+    /// A list of manifest file locations, used by v1 snapshots written
+    /// without a manifest list
+    pub manifests: Option<Vec<String>>,
     /// A string map that summarizes the snapshot changes, including
     /// operation (see below)
     ///
@@ -1992,11 +2182,62 @@ pub struct Snapshot {
 }
 
 impl Snapshot {
-    pub(crate) async fn load_manifest_list(&self, op: &Operator) -> Result<ManifestList> {
-        parse_manifest_list(
-            &op.read(Table::relative_path(op, self.manifest_list.as_str())?.as_str())
-                .await?,
-        )
+    /// Operation of the snapshot recorded in summary, like `append`.
+    pub fn operation(&self) -> Option<&str> {
+        self.summary.get("operation").map(|s| s.as_str())
+    }
+
+    /// Build the manifest list of a v1 snapshot which lists manifests
+    /// inline, `rel_path` maps manifest paths to paths of the operator.
+    /// Manifests are read concurrently for their partition spec ids and
+    /// counts, in the order they are listed.
+    pub(crate) async fn load_inline_manifest_list(
+        &self,
+        op: &Operator,
+        rel_path: impl Fn(&str) -> Result<String>,
+        concurrency: usize,
+    ) -> Result<ManifestList> {
+        let snapshot_id = self.snapshot_id;
+        let reads = self
+            .inline_manifests()?
+            .iter()
+            .map(|manifest_path| {
+                let path = rel_path(manifest_path);
+                async move {
+                    let content = op.read(&path?).await?;
+                    Ok::<_, Error>(ManifestListEntry::of_inline_manifest(
+                        manifest_path.clone(),
+                        content.len() as i64,
+                        snapshot_id,
+                        &parse_manifest_file(&content)?,
+                    ))
+                }
+            })
+            .collect::<Vec<_>>();
+        let entries = futures::stream::iter(reads)
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+        Ok(ManifestList { entries })
+    }
+
+    /// Returns whether the snapshot lists manifests inline instead of in a
+    /// manifest list, which is allowed in v1 tables.
+    pub fn has_inline_manifests(&self) -> bool {
+        self.manifest_list.is_empty()
+    }
+
+    /// Manifests listed inline by a v1 snapshot without manifest list.
+    pub(crate) fn inline_manifests(&self) -> Result<&[String]> {
+        self.manifests.as_deref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Snapshot {} has neither manifest list nor manifests",
+                    self.snapshot_id
+                ),
+            )
+        })
     }
 
     pub(crate) fn log(&self) -> SnapshotLog {
@@ -2266,6 +2507,17 @@ impl TableMetadata {
         }
     }
 
+    /// Returns the snapshot which was current at the timestamp, according to
+    /// the snapshot log.
+    pub fn snapshot_as_of_timestamp(&self, timestamp_ms: i64) -> Option<&Snapshot> {
+        self.snapshot_log
+            .as_ref()?
+            .iter()
+            .filter(|log| log.timestamp_ms <= timestamp_ms)
+            .max_by_key(|log| log.timestamp_ms)
+            .and_then(|log| self.snapshot(log.snapshot_id))
+    }
+
     /// Returns snapshot reference of branch
     pub fn snapshot_ref(&self, branch: &str) -> Option<&SnapshotReference> {
         self.refs.get(branch)
@@ -2465,4 +2717,48 @@ mod test {
         assert_eq!(struct_type2.lookup_field(3).unwrap().name, "c");
         assert_eq!(struct_type2.lookup_field(4).unwrap().name, "d");
     }
+
+    #[test]
+    fn test_primitive_value_from_bytes() {
+        use crate::types::Primitive;
+
+        let cases = [
+            (Primitive::Int, vec![0x2a, 0, 0, 0], PrimitiveValue::Int(42)),
+            (
+                Primitive::Long,
+                vec![0xff, 0xff, 0xff, 0xff],
+                PrimitiveValue::Long(-1),
+            ),
+            (
+                Primitive::Double,
+                1.5f64.to_le_bytes().to_vec(),
+                PrimitiveValue::Double(1.5.into()),
+            ),
+            (
+                Primitive::Decimal {
+                    precision: 9,
+                    scale: 2,
+                },
+                vec![0xfe, 0x0c],
+                PrimitiveValue::Decimal(rust_decimal::Decimal::new(-500, 2)),
+            ),
+            (
+                Primitive::Date,
+                vec![1, 0, 0, 0],
+                PrimitiveValue::Date(chrono::NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()),
+            ),
+            (
+                Primitive::String,
+                b"iceberg".to_vec(),
+                PrimitiveValue::String("iceberg".to_string()),
+            ),
+        ];
+        for (ty, bytes, expected) in cases {
+            assert_eq!(
+                PrimitiveValue::try_from_bytes(&ty, &bytes).unwrap(),
+                expected
+            );
+        }
+        assert!(PrimitiveValue::try_from_bytes(&Primitive::Int, &[1, 2]).is_err());
+    }
 }
@@ -12,6 +12,8 @@ pub use self::arrow::from_arrow::*;
 pub use self::arrow::to_arrow::*;
 
 mod to_avro;
+#[cfg(test)]
+pub(crate) use to_avro::to_avro_schema;
 
 mod transform;
 pub use transform::*;
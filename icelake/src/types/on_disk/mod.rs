@@ -33,3 +33,4 @@ pub(crate) use table_metadata::TableMetadata as TableMetadataSerDe;
 mod types;
 
 mod value;
+pub(crate) use value::Value as ValueSerDe;
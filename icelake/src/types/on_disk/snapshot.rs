@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::types;
 use crate::Error;
+use crate::ErrorKind;
 use crate::Result;
 
 /// Parse snapshot from json bytes.
@@ -22,7 +23,11 @@ pub struct Snapshot {
     #[serde(default)]
     sequence_number: i64,
     timestamp_ms: i64,
-    manifest_list: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest_list: Option<String>,
+    /// Manifests listed inline by v1 snapshots without manifest list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifests: Option<Vec<String>>,
     summary: Option<HashMap<String, String>>,
     schema_id: Option<i64>,
 }
@@ -31,12 +36,23 @@ impl TryFrom<Snapshot> for types::Snapshot {
     type Error = Error;
 
     fn try_from(v: Snapshot) -> Result<Self> {
+        if v.manifest_list.is_none() && v.manifests.is_none() {
+            return Err(Error::new(
+                ErrorKind::IcebergDataInvalid,
+                format!(
+                    "Snapshot {} has neither manifest-list nor manifests",
+                    v.snapshot_id
+                ),
+            ));
+        }
+
         Ok(types::Snapshot {
             snapshot_id: v.snapshot_id,
             parent_snapshot_id: v.parent_snapshot_id,
             sequence_number: v.sequence_number,
             timestamp_ms: v.timestamp_ms,
-            manifest_list: v.manifest_list,
+            manifest_list: v.manifest_list.unwrap_or_default(),
+            manifests: v.manifests,
             summary: v.summary.unwrap_or_default(),
             schema_id: v.schema_id,
         })
@@ -52,7 +68,8 @@ impl TryFrom<types::Snapshot> for Snapshot {
             parent_snapshot_id: value.parent_snapshot_id,
             sequence_number: value.sequence_number,
             timestamp_ms: value.timestamp_ms,
-            manifest_list: value.manifest_list,
+            manifest_list: (!value.manifest_list.is_empty()).then_some(value.manifest_list),
+            manifests: value.manifests,
             summary: Some(value.summary),
             schema_id: value.schema_id,
         })
@@ -98,6 +115,7 @@ mod tests {
                 sequence_number: 0,
                 timestamp_ms: 1686911671713,
                 manifest_list: "/opt/bitnami/spark/warehouse/db/table/metadata/snap-1646658105718557341-1-10d28031-9739-484c-92db-cdf2975cead4.avro".to_string(),
+                manifests: None,
                 summary: {
                     let mut m = HashMap::new();
                     m.insert("operation", "append");
@@ -118,4 +136,38 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_parse_snapshot_with_inline_manifests() {
+        let content = r#"
+{
+    "snapshot-id" : 3051729675574597004,
+    "timestamp-ms" : 1515100955770,
+    "summary" : {
+      "operation" : "append"
+    },
+    "manifests" : [
+      "s3://bucket/table/metadata/manifest-1.avro",
+      "s3://bucket/table/metadata/manifest-2.avro"
+    ]
+  }
+        "#;
+
+        let v = parse_snapshot(content.as_bytes()).unwrap();
+        assert!(v.has_inline_manifests());
+        assert_eq!(
+            v.manifests,
+            Some(vec![
+                "s3://bucket/table/metadata/manifest-1.avro".to_string(),
+                "s3://bucket/table/metadata/manifest-2.avro".to_string(),
+            ])
+        );
+
+        let json = serde_json::to_string(&Snapshot::try_from(v).unwrap()).unwrap();
+        assert!(json.contains(r#""manifests":["#));
+        assert!(!json.contains("manifest-list"));
+
+        let content = r#"{"snapshot-id" : 1, "timestamp-ms" : 1515100955770}"#;
+        assert!(parse_snapshot(content.as_bytes()).is_err());
+    }
 }
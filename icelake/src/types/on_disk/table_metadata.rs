@@ -357,6 +357,32 @@ mod tests {
         assert_eq!(metadata.current_snapshot_id, Some(1646658105718557341));
     }
 
+    #[test]
+    fn test_snapshot_as_of_timestamp() {
+        let path = format!(
+            "{}/../testdata/simple_table/metadata/v2.metadata.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+
+        let bs = fs::read(path).expect("read_file must succeed");
+
+        let metadata = parse_table_metadata(&bs).expect("parse_table_metadata v2 must succeed");
+
+        assert!(metadata.snapshot_as_of_timestamp(1686911671712).is_none());
+        assert_eq!(
+            metadata
+                .snapshot_as_of_timestamp(1686911671713)
+                .map(|s| s.snapshot_id),
+            Some(1646658105718557341)
+        );
+        assert_eq!(
+            metadata
+                .snapshot_as_of_timestamp(i64::MAX)
+                .map(|s| s.snapshot_id),
+            Some(1646658105718557341)
+        );
+    }
+
     #[test]
     fn test_serialize_table_metadata() {
         let metadata = types::TableMetadata {
@@ -400,6 +426,7 @@ mod tests {
                 sequence_number: 2,
                 timestamp_ms: 1686911671713,
                 manifest_list: "/opt/bitnami/spark/warehouse/db/table/1.avro".to_string(),
+                manifests: None,
                 summary: HashMap::default(),
                 schema_id: Some(0),
             }]),